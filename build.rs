@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Generates one test per line of every `src/specs/*.expected` sidecar, see
+// tests/specs.rs. Only the solver and part are read here to name the test,
+// the line itself is parsed and checked by `answers::check_example`.
+fn main() {
+    println!("cargo:rerun-if-changed=src/specs");
+
+    let mut sidecars = fs::read_dir("src/specs")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "expected"))
+        .collect::<Vec<_>>();
+    sidecars.sort();
+
+    let mut tests = String::new();
+    let mut names = HashSet::new();
+    for sidecar in sidecars {
+        let example = sidecar.file_stem().unwrap().to_str().unwrap();
+        let spec = format!("src/specs/{}", example);
+        let content = fs::read_to_string(&sidecar).unwrap();
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        for (index, line) in entries.enumerate() {
+            let mut fields = line.split_whitespace();
+            let solver = fields.next().unwrap_or("unknown");
+            let part = fields.next().unwrap_or("part");
+            let mut name = if solver == example {
+                format!("{}_{}", example, part)
+            } else {
+                format!("{}_{}_{}", example, solver, part)
+            };
+            name = name.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+            if !names.insert(name.clone()) {
+                name = format!("{}_{}", name, index);
+                names.insert(name.clone());
+            }
+            tests.push_str(&format!(
+                "#[test]\nfn {}() {{\n    check({:?}, {});\n}}\n\n",
+                name, spec, index
+            ));
+        }
+    }
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("spec_tests.rs");
+    fs::write(out, tests).unwrap();
+}
@@ -0,0 +1,208 @@
+use crate::error::{from_path, Line, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Params, Part};
+
+// Known answers, one per line: `day part input answer`, e.g.
+// `day8 part1 src/inputs/day8 1840`. Multi-line answers keep their line
+// breaks escaped as `\n`.
+pub const MANIFEST: &str = "src/answers";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    pub day: String,
+    pub part: Part,
+    pub input: String,
+    pub answer: String,
+}
+
+pub fn escape(answer: &str) -> String {
+    answer.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+pub fn unescape(answer: &str) -> String {
+    let mut out = String::new();
+    let mut chars = answer.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Lines that are neither blank nor `#` comments, shared by the manifest and
+// the example sidecars.
+pub fn entries(input: &str) -> impl Iterator<Item = Line<'_>> {
+    lines(input).filter(|line| !line.text.trim().is_empty() && !line.text.starts_with('#'))
+}
+
+fn parse_part<'a>(line: &Line<'a>, part: &'a str) -> ParseResult<Part> {
+    match part {
+        "part1" => Ok(Part::One),
+        "part2" => Ok(Part::Two),
+        _ => Err(line.error(part, "part1 or part2")),
+    }
+}
+
+pub fn parse_str(input: &str) -> ParseResult<Vec<Expected>> {
+    entries(input)
+        .map(|line| {
+            let mut fields = line.text.splitn(4, ' ');
+            let mut field = |expected| {
+                fields
+                    .next()
+                    .filter(|f| !f.is_empty())
+                    .ok_or_else(|| line.error(&line.text[line.text.len()..], expected))
+            };
+            let day = field("a day")?;
+            let part = parse_part(&line, field("a part")?)?;
+            let input = field("an input path")?;
+            let answer = field("an answer")?;
+            Ok(Expected {
+                day: day.to_string(),
+                part,
+                input: input.to_string(),
+                answer: unescape(answer),
+            })
+        })
+        .collect()
+}
+
+pub fn parse_manifest(path: &str) -> ParseResult<Vec<Expected>> {
+    from_path(path, parse_str)
+}
+
+// Expected answer for an example in `src/specs`, read from the sidecar next
+// to it: `solver part answer [--key value]...`, e.g. `day15 part1 26 --row 10`.
+#[derive(Debug, Clone)]
+pub struct Example {
+    pub solver: String,
+    pub part: Part,
+    pub answer: String,
+    pub params: Params,
+}
+
+pub fn sidecar_path(spec: &str) -> String {
+    format!("{}.expected", spec)
+}
+
+pub fn parse_examples_str(input: &str) -> ParseResult<Vec<Example>> {
+    entries(input)
+        .map(|line| {
+            let mut fields = line.text.split_whitespace();
+            let end = &line.text[line.text.len()..];
+            let solver = fields.next().ok_or_else(|| line.error(end, "a solver"))?;
+            let part = parse_part(
+                &line,
+                fields.next().ok_or_else(|| line.error(end, "a part"))?,
+            )?;
+            let answer = fields.next().ok_or_else(|| line.error(end, "an answer"))?;
+            let mut params = Params::new();
+            while let Some(key) = fields.next() {
+                let name = key
+                    .strip_prefix("--")
+                    .ok_or_else(|| line.error(key, "an option like --row"))?;
+                let value = fields
+                    .next()
+                    .ok_or_else(|| line.error(end, "an option value"))?;
+                params.set(name, value);
+            }
+            Ok(Example {
+                solver: solver.to_string(),
+                part,
+                answer: unescape(answer),
+                params,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_examples(spec: &str) -> ParseResult<Vec<Example>> {
+    from_path(&sidecar_path(spec), parse_examples_str)
+}
+
+// Runs the `index`th example listed for `spec`, the spec tests generated by
+// build.rs call this once per sidecar line.
+pub fn check_example(spec: &str, index: usize) -> Result<(), String> {
+    let examples = parse_examples(spec).map_err(|e| e.to_string())?;
+    let example =
+        examples
+            .get(index)
+            .ok_or(format!("{} has no example {}", sidecar_path(spec), index))?;
+    let entry = crate::registry::find(&example.solver)
+        .ok_or(format!("unknown solver {}", example.solver))?;
+    let answer = entry
+        .build(&example.params)?
+        .solve(spec, example.part)
+        .map_err(|e| e.to_string())?;
+    if answer.to_string() != example.answer {
+        return Err(format!(
+            "{} {} on {}: expected {}, got {}",
+            example.solver,
+            example.part,
+            spec,
+            escape(&example.answer),
+            escape(&answer.to_string())
+        ));
+    }
+    Ok(())
+}
+
+impl Expected {
+    pub fn matches(&self, answer: &Answer) -> bool {
+        self.answer == answer.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = parse_str("# comment\nday5 part1 src/inputs/day5 FJSRQCFTN\n\nday10 part2 src/inputs/day10 #.\\n.#\n").unwrap();
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest[0].part, Part::One);
+        assert!(manifest[0].matches(&Answer::Text("FJSRQCFTN".to_string())));
+        assert_eq!(manifest[1].answer, "#.\n.#");
+        assert_eq!(escape(&manifest[1].answer), "#.\\n.#");
+
+        let error = parse_str("day1 part3 src/inputs/day1 1").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "<input>:1:6: expected part1 or part2, found `part3`"
+        );
+        assert!(parse_str("day1 part1 src/inputs/day1").is_err());
+    }
+
+    #[test]
+    fn test_parse_examples() {
+        let examples = parse_examples_str("day15 part1 26 --row 10 --max 20,20\n").unwrap();
+        assert_eq!(examples[0].solver, "day15");
+        assert_eq!(examples[0].answer, "26");
+        assert_eq!(examples[0].params.get("row", 0), Ok(10));
+
+        let error = parse_examples_str("day15 part1 26 row 10").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "<input>:1:16: expected an option like --row, found `row`"
+        );
+        assert!(parse_examples_str("day15 part1 26 --row").is_err());
+    }
+
+    #[test]
+    fn test_manifest_covers_inputs() {
+        let manifest = parse_manifest(MANIFEST).unwrap();
+        for expected in manifest.iter() {
+            assert!(crate::registry::find(&expected.day).is_some());
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::error::ParseResult;
+use crate::solution::{Part, Solver};
+use std::fmt::Write;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let mid = sorted.len() / 2;
+        let median = match sorted.len() {
+            0 => Duration::ZERO,
+            n if n % 2 == 0 => (sorted[mid - 1] + sorted[mid]) / 2,
+            _ => sorted[mid],
+        };
+        Stats {
+            min: sorted.first().copied().unwrap_or_default(),
+            median,
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub day: String,
+    pub part: Part,
+    pub runs: usize,
+    pub parse: Stats,
+    pub solve: Stats,
+    pub total: Stats,
+}
+
+// Runs one part `runs` times on the same input, parsing every time so the
+// parse and solve split is measured on each run.
+pub fn bench(
+    name: &str,
+    solver: &dyn Solver,
+    input: &str,
+    part: Part,
+    runs: usize,
+) -> ParseResult<Report> {
+    let mut parse = vec![];
+    let mut solve = vec![];
+    let mut total = vec![];
+    for _ in 0..runs {
+        let timed = solver.solve_timed(input, part)?;
+        parse.push(timed.parse);
+        solve.push(timed.solve);
+        total.push(timed.parse + timed.solve);
+    }
+    Ok(Report {
+        day: name.to_string(),
+        part,
+        runs,
+        parse: Stats::from_samples(&parse),
+        solve: Stats::from_samples(&solve),
+        total: Stats::from_samples(&total),
+    })
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64() * 1000.0)
+}
+
+pub fn to_table(reports: &[Report]) -> String {
+    let mut out = format!(
+        "{:<8} {:<5} {:>4} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+        "day", "part", "runs", "min ms", "median ms", "max ms", "parse ms", "solve ms"
+    );
+    for r in reports {
+        writeln!(
+            out,
+            "{:<8} {:<5} {:>4} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            r.day,
+            r.part.number(),
+            r.runs,
+            millis(r.total.min),
+            millis(r.total.median),
+            millis(r.total.max),
+            millis(r.parse.median),
+            millis(r.solve.median),
+        )
+        .unwrap();
+    }
+    out
+}
+
+// Times are written in microseconds so files from different commits can be
+// compared without parsing units.
+pub fn to_csv(reports: &[Report]) -> String {
+    let mut out = String::from("day,part,runs");
+    for kind in ["parse", "solve", "total"] {
+        for stat in ["min", "median", "max"] {
+            write!(out, ",{}_{}_us", kind, stat).unwrap();
+        }
+    }
+    out.push('\n');
+    for r in reports {
+        write!(out, "{},{},{}", r.day, r.part.number(), r.runs).unwrap();
+        for stats in [r.parse, r.solve, r.total] {
+            for d in [stats.min, stats.median, stats.max] {
+                write!(out, ",{}", d.as_micros()).unwrap();
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn stats_json(stats: &Stats) -> String {
+    format!(
+        "{{\"min_us\": {}, \"median_us\": {}, \"max_us\": {}}}",
+        stats.min.as_micros(),
+        stats.median.as_micros(),
+        stats.max.as_micros()
+    )
+}
+
+pub fn to_json(reports: &[Report]) -> String {
+    let rows = reports
+        .iter()
+        .map(|r| {
+            format!(
+                "  {{\"day\": \"{}\", \"part\": {}, \"runs\": {}, \"parse\": {}, \"solve\": {}, \"total\": {}}}",
+                r.day,
+                r.part.number(),
+                r.runs,
+                stats_json(&r.parse),
+                stats_json(&r.solve),
+                stats_json(&r.total)
+            )
+        })
+        .collect::<Vec<String>>();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+    use crate::solution::Params;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::from_samples(&[ms(5), ms(1), ms(3)]);
+        assert_eq!((stats.min, stats.median, stats.max), (ms(1), ms(3), ms(5)));
+        let stats = Stats::from_samples(&[ms(4), ms(1), ms(2), ms(8)]);
+        assert_eq!(stats.median, ms(3));
+    }
+
+    #[test]
+    fn test_bench_output() {
+        let entry = registry::find("day6").unwrap();
+        let solver = entry.build(&Params::new()).unwrap();
+        let input = std::fs::read_to_string(entry.spec_path()).unwrap();
+        let report = bench(entry.name, solver.as_ref(), &input, Part::One, 3).unwrap();
+        assert_eq!(report.runs, 3);
+        assert!(report.total.min <= report.total.max);
+
+        let reports = [report];
+        let csv = to_csv(&reports);
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("day,part,runs,parse_min_us,"));
+        assert!(lines.next().unwrap().starts_with("day6,1,3,"));
+
+        let json = to_json(&reports);
+        assert!(json.contains("\"day\": \"day6\", \"part\": 1, \"runs\": 3"));
+    }
+}
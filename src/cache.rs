@@ -0,0 +1,157 @@
+use crate::answers::{escape, unescape};
+use crate::input::normalise;
+use crate::registry::Entry;
+use crate::solution::{Answer, Params, Part};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+// Answers of earlier runs, one file per day, part, solver version,
+// parameters and input. Anything changing one of those misses the cache, so
+// nothing in it ever has to be invalidated, only cleared to save space.
+pub const DIR: &str = ".cache/answers";
+
+// FNV-1a, stable across runs and Rust versions unlike the std hasher.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    day: &'static str,
+    part: Part,
+    version: u32,
+    hash: u64,
+}
+
+impl Key {
+    pub fn new(entry: &Entry, part: Part, params: &Params, input: &str) -> Key {
+        let mut keys = params.keys().collect::<Vec<_>>();
+        keys.sort();
+        let mut content = String::new();
+        for key in keys {
+            let value = params.get(key, String::new()).unwrap();
+            content.push_str(&format!("--{} {}\n", key, value));
+        }
+        content.push_str(&normalise(input));
+        Key {
+            day: entry.name,
+            part,
+            version: entry.version,
+            hash: hash(content.as_bytes()),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "{}_{}_v{}_{:016x}",
+            self.day, self.part, self.version, self.hash
+        )
+    }
+}
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl AsRef<Path>) -> Cache {
+        Cache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    // Unreadable entries are misses, they get written again.
+    pub fn get(&self, key: &Key) -> Option<Answer> {
+        let content = fs::read_to_string(self.dir.join(key.file_name())).ok()?;
+        match content.trim_end_matches('\n').split_once(' ')? {
+            ("number", n) => n.parse().ok().map(Answer::Number),
+            ("text", text) => Some(Answer::Text(unescape(text))),
+            _ => None,
+        }
+    }
+
+    // Only real answers are kept, unsolved or timed out parts run again.
+    pub fn put(&self, key: &Key, answer: &Answer) -> io::Result<()> {
+        let content = match answer {
+            Answer::Number(n) => format!("number {}\n", n),
+            Answer::Text(text) => format!("text {}\n", escape(text)),
+            Answer::Unsolved | Answer::TimedOut => return Ok(()),
+        };
+        fs::create_dir_all(&self.dir)?;
+        // written aside first so parallel runs never read half an answer
+        let path = self.dir.join(key.file_name());
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp, content)?;
+        fs::rename(tmp, path)
+    }
+
+    // Number of answers removed.
+    pub fn clear(&self) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            fs::remove_file(entry?.path())?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache::new(DIR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_cache() {
+        let dir = std::env::temp_dir().join(format!("advent-cache-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let day15 = registry::find("day15").unwrap();
+        let key = Key::new(day15, Part::One, &Params::new(), "input\n");
+
+        assert_eq!(cache.get(&key), None);
+        cache.put(&key, &Answer::Number(26)).unwrap();
+        assert_eq!(cache.get(&key), Some(Answer::Number(26)));
+        // line endings do not matter, parameters and other parts do
+        assert_eq!(
+            cache.get(&Key::new(day15, Part::One, &Params::new(), "input\r\n")),
+            Some(Answer::Number(26))
+        );
+        let mut params = Params::new();
+        params.set("row", "10");
+        assert_eq!(
+            cache.get(&Key::new(day15, Part::One, &params, "input\n")),
+            None
+        );
+        assert_eq!(
+            cache.get(&Key::new(day15, Part::Two, &Params::new(), "input\n")),
+            None
+        );
+
+        let text = Key::new(day15, Part::Two, &Params::new(), "input\n");
+        cache
+            .put(&text, &Answer::Text("#.\n.#".to_string()))
+            .unwrap();
+        assert_eq!(cache.get(&text), Some(Answer::Text("#.\n.#".to_string())));
+        cache.put(&text, &Answer::TimedOut).unwrap();
+        assert_eq!(cache.get(&text), Some(Answer::Text("#.\n.#".to_string())));
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get(&key), None);
+        assert_eq!(cache.clear().unwrap(), 0);
+        fs::remove_dir(dir).unwrap();
+    }
+}
@@ -0,0 +1,649 @@
+use advent_2022::answers;
+use advent_2022::frames::ImageFormat;
+use advent_2022::registry::{self, Entry};
+use advent_2022::trace::{self, Filter};
+use advent_2022::{Params, Part};
+use std::time::Duration;
+
+pub const USAGE: &str = "usage:
+  advent-2022 run --day N [--part 1|2] [--input PATH|-] [--trace FILTERS] [--timeout SECS]
+                  [--format text|json] [--no-cache] [--watch] [--<param> VALUE]...
+  advent-2022 run --all [--part 1|2] [--trace FILTERS] [--timeout SECS] [--jobs N]
+                  [--format text|json] [--no-cache]
+  advent-2022 cache clear
+  advent-2022 serve [--port N] [--timeout SECS]
+  advent-2022 repl [--day N]
+  advent-2022 new-day N
+  advent-2022 check [--day N] [--manifest PATH]
+  advent-2022 bench (--day N|--all) [--part 1|2] [--runs N] [--output FILE.csv|FILE.json]
+  advent-2022 animate --day 9|10|14|17 [--part 1|2] [--input PATH|-] [--every N] [--delay MS]
+                      [--output FILE.txt|FILE.pgm|FILE.ppm] [--scale N]
+  advent-2022 generate --day N [--size N] [--seed N] [--output PATH]
+
+days with parameters:
+  day1     --total-elves --above --target --tolerance
+  day6     --window --window-part2
+  day11    --rounds --rounds-part2
+  day11_2  --rounds
+  day15    --row --min x,y --max x,y
+
+trace filters are comma separated `day[=info|debug|trace]`, `all` for every
+day, e.g. --trace day16 or --trace day20=trace,day21. Events go to stderr.
+
+--timeout stops the slow solvers (day15 part 2, day16, day21 part 2) after
+that many seconds, they report `timed out` instead of an answer.
+
+--jobs runs the days and parts on that many threads and prints one table
+with every answer, time and error once all of them are done, a day without
+an input fails there. --format json prints the same as a JSON array instead,
+with details such as the indices of the elves for day1 or of the divider
+packets for day13.
+
+day1 --above N lists the elves carrying more than N calories, --target N the
+elves reaching N together with the least to spare and --tolerance N the
+fewest snacks to move so every elf is within N of the mean, in the --format
+json details of part 1, other commands and formats refuse them. --target is
+at most 1000000, the queries are left out of the time of part 1 but
+--timeout applies to them. Proving no shorter plan exists can take far too
+long on a full input, --timeout is for that.
+
+run keeps answers in .cache/answers and reuses them as long as the day, its
+solver, the parameters and the input are the same. --no-cache solves again,
+cached answers come without their details. Answers asked with the day1
+queries are never taken from the cache.
+
+--watch solves the input and the example in src/specs again whenever one of
+them changes and prints what changed, until interrupted.
+
+serve listens on 127.0.0.1 (port 8022 by default) for
+`POST /days/{n}/parts/{p}?<param>=VALUE` with the input as the body and
+answers with the JSON of run --format json. It handles 32 connections at
+once, the next ones wait, and drops clients idle for 10 seconds.
+
+repl reads commands such as `day 14`, `load example`, `run 2`, `sim 1`,
+`step 10`, `show` and `reset` from stdin, `help` lists them.
+
+new-day writes src/dayN.rs from a template with an empty example and input,
+and registers it in src/lib.rs, the registry and the generators. It refuses
+to overwrite anything that exists already.
+
+animate draws in the terminal unless --output is given, image sequences are
+numbered after the file name: out/day14.ppm gives out/day14_00001.ppm, ...
+
+generate writes a random input to stdout unless --output is given. --size
+defaults to 100, without --seed one is picked and printed to stderr.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Default,
+    Stdin,
+    Path(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnswerFormat {
+    Text,
+    Json,
+}
+
+pub struct RunArgs {
+    pub days: Vec<&'static Entry>,
+    pub part: Option<Part>,
+    pub input: Input,
+    pub params: Params,
+    pub trace: Vec<Filter>,
+    pub timeout: Option<Duration>,
+    pub jobs: Option<usize>,
+    pub format: AnswerFormat,
+    pub cache: bool,
+    pub watch: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+pub struct BenchArgs {
+    pub run: RunArgs,
+    pub runs: usize,
+    pub output: Option<(String, Format)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameOutput {
+    Terminal,
+    Text(String),
+    Images(String, ImageFormat),
+}
+
+pub struct AnimateArgs {
+    pub run: RunArgs,
+    pub output: FrameOutput,
+    pub every: usize,
+    pub delay: Duration,
+    pub scale: usize,
+}
+
+pub struct CheckArgs {
+    pub day: Option<&'static Entry>,
+    pub manifest: String,
+}
+
+pub struct GenerateArgs {
+    pub day: &'static Entry,
+    pub size: usize,
+    pub seed: Option<u64>,
+    pub output: Option<String>,
+}
+
+pub struct ServeArgs {
+    pub port: u16,
+    pub timeout: Option<Duration>,
+}
+
+pub enum Command {
+    Run(RunArgs),
+    Bench(BenchArgs),
+    Animate(AnimateArgs),
+    Check(CheckArgs),
+    Generate(GenerateArgs),
+    ClearCache,
+    Serve(ServeArgs),
+    Repl(Option<&'static Entry>),
+    NewDay(u8),
+    Help,
+}
+
+fn value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a String, String> {
+    args.next().ok_or(format!("missing value for {}", flag))
+}
+
+fn positive(v: &str, flag: &str) -> Result<usize, String> {
+    match v.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("invalid value for {}: {}", flag, v)),
+    }
+}
+
+fn parse_check<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut day = None;
+    let mut manifest = answers::MANIFEST.to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" => {
+                let v = value(&mut args, arg)?;
+                day = Some(
+                    registry::find(&format!("day{}", v)).ok_or(format!("unknown day: {}", v))?,
+                );
+            }
+            "--manifest" => manifest = value(&mut args, arg)?.to_string(),
+            other => return Err(format!("unexpected argument: {}", other)),
+        }
+    }
+    Ok(Command::Check(CheckArgs { day, manifest }))
+}
+
+fn parse_generate<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut day = None;
+    let mut size = 100;
+    let mut seed = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" => {
+                let v = value(&mut args, arg)?;
+                day = Some(
+                    registry::find(&format!("day{}", v)).ok_or(format!("unknown day: {}", v))?,
+                );
+            }
+            "--size" => size = positive(value(&mut args, arg)?, arg)?,
+            "--seed" => {
+                let v = value(&mut args, arg)?;
+                seed = Some(
+                    v.parse()
+                        .map_err(|_| format!("invalid value for {}: {}", arg, v))?,
+                );
+            }
+            "--output" => output = Some(value(&mut args, arg)?.to_string()),
+            other => return Err(format!("unexpected argument: {}", other)),
+        }
+    }
+    Ok(Command::Generate(GenerateArgs {
+        day: day.ok_or("generate needs --day")?,
+        size,
+        seed,
+        output,
+    }))
+}
+
+fn parse_timeout(v: &str) -> Result<Duration, String> {
+    v.parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or(format!("invalid value for --timeout: {}", v))
+}
+
+fn parse_serve<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut serve = ServeArgs {
+        port: 8022,
+        timeout: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let v = value(&mut args, arg)?;
+                serve.port = v
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", arg, v))?;
+            }
+            "--timeout" => serve.timeout = Some(parse_timeout(value(&mut args, arg)?)?),
+            other => return Err(format!("unexpected argument: {}", other)),
+        }
+    }
+    Ok(Command::Serve(serve))
+}
+
+fn parse_repl<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut day = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" => {
+                let v = value(&mut args, arg)?;
+                day = Some(
+                    registry::find(&format!("day{}", v)).ok_or(format!("unknown day: {}", v))?,
+                );
+            }
+            other => return Err(format!("unexpected argument: {}", other)),
+        }
+    }
+    Ok(Command::Repl(day))
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    let command = match args.next().map(|s| s.as_str()) {
+        Some(cmd @ ("run" | "bench" | "animate")) => cmd,
+        Some("check") => return parse_check(args),
+        Some("generate") => return parse_generate(args),
+        Some("serve") => return parse_serve(args),
+        Some("repl") => return parse_repl(args),
+        Some("new-day") => {
+            return match (args.next(), args.next()) {
+                (Some(v), None) => v
+                    .parse()
+                    .ok()
+                    .filter(|day| (1..=25).contains(day))
+                    .map(Command::NewDay)
+                    .ok_or(format!("invalid day: {}", v)),
+                _ => Err("new-day needs a day number".to_string()),
+            }
+        }
+        Some("cache") => {
+            return match args.next().map(|s| s.as_str()) {
+                Some("clear") => Ok(Command::ClearCache),
+                _ => Err("cache needs clear".to_string()),
+            }
+        }
+        Some("help") | Some("--help") | Some("-h") | None => return Ok(Command::Help),
+        Some(cmd) => return Err(format!("unknown command: {}", cmd)),
+    };
+
+    let mut day: Option<&'static Entry> = None;
+    let mut all = false;
+    let mut part = None;
+    let mut input = Input::Default;
+    let mut params = Params::new();
+    let mut runs = 10;
+    let mut output = None;
+    let mut filters = Vec::new();
+    let mut every = 1;
+    let mut delay = 50;
+    let mut scale = 4;
+    let mut timeout = None;
+    let mut jobs = None;
+    let mut format = AnswerFormat::Text;
+    let mut cache = true;
+    let mut watch = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--day" => {
+                let v = value(&mut args, arg)?;
+                day = Some(
+                    registry::find(&format!("day{}", v)).ok_or(format!("unknown day: {}", v))?,
+                );
+            }
+            "--part" => {
+                part = Some(match value(&mut args, arg)?.as_str() {
+                    "1" => Part::One,
+                    "2" => Part::Two,
+                    v => return Err(format!("unknown part: {}", v)),
+                })
+            }
+            "--input" => {
+                input = match value(&mut args, arg)?.as_str() {
+                    "-" => Input::Stdin,
+                    path => Input::Path(path.to_string()),
+                }
+            }
+            "--runs" if command == "bench" => runs = positive(value(&mut args, arg)?, arg)?,
+            "--every" if command == "animate" => every = positive(value(&mut args, arg)?, arg)?,
+            "--scale" if command == "animate" => scale = positive(value(&mut args, arg)?, arg)?,
+            "--delay" if command == "animate" => {
+                let v = value(&mut args, arg)?;
+                delay = v
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid value for --delay: {}", v))?;
+            }
+            "--trace" => {
+                if command != "run" {
+                    return Err("--trace is only supported by run".to_string());
+                }
+                filters.extend(trace::parse_filters(value(&mut args, arg)?)?);
+            }
+            "--jobs" if command == "run" => jobs = Some(positive(value(&mut args, arg)?, arg)?),
+            "--no-cache" if command == "run" => cache = false,
+            "--watch" if command == "run" => watch = true,
+            "--format" if command == "run" => {
+                format = match value(&mut args, arg)?.as_str() {
+                    "text" => AnswerFormat::Text,
+                    "json" => AnswerFormat::Json,
+                    v => return Err(format!("unknown format: {}", v)),
+                }
+            }
+            "--timeout" if command == "run" => {
+                timeout = Some(parse_timeout(value(&mut args, arg)?)?)
+            }
+            "--output" if command == "bench" || command == "animate" => {
+                output = Some(value(&mut args, arg)?.to_string());
+            }
+            flag if flag.starts_with("--") => {
+                let v = value(&mut args, arg)?;
+                params.set(&flag[2..], v);
+            }
+            other => return Err(format!("unexpected argument: {}", other)),
+        }
+    }
+
+    let days = match (day, all) {
+        (Some(_), true) => return Err("--day and --all are exclusive".to_string()),
+        (Some(entry), false) => vec![entry],
+        (None, true) if command == "animate" => {
+            return Err("animate needs a single --day".to_string())
+        }
+        (None, true) if watch => return Err("--watch needs a single --day".to_string()),
+        (None, true) => {
+            if input != Input::Default {
+                return Err("--input needs a single --day".to_string());
+            }
+            registry::DAYS.iter().collect()
+        }
+        (None, false) => return Err(format!("{} needs --day N or --all", command)),
+    };
+
+    // their answers are only in the details, which text output leaves out
+    let detail = days
+        .iter()
+        .flat_map(|entry| entry.detail_params)
+        .find(|key| params.keys().any(|k| k == *key));
+    if let Some(key) = detail.filter(|_| command != "run" || format != AnswerFormat::Json) {
+        return Err(format!("--{} needs run --format json", key));
+    }
+
+    let run = RunArgs {
+        days,
+        part,
+        input,
+        params,
+        trace: filters,
+        timeout,
+        jobs,
+        format,
+        cache,
+        watch,
+    };
+    if watch && run.input == Input::Stdin {
+        return Err("--watch needs an input file".to_string());
+    }
+    let extension = |path: &str| path.rsplit_once('.').map(|(_, ext)| ext.to_string());
+    Ok(match command {
+        "bench" => {
+            let output = match output {
+                Some(path) => {
+                    let format = match extension(&path).as_deref() {
+                        Some("csv") => Format::Csv,
+                        Some("json") => Format::Json,
+                        _ => return Err(format!("--output must end in .csv or .json: {}", path)),
+                    };
+                    Some((path, format))
+                }
+                None => None,
+            };
+            Command::Bench(BenchArgs { run, runs, output })
+        }
+        "animate" => {
+            let entry = run.days[0];
+            if entry.animate.is_none() {
+                return Err(format!("{} has no simulation to animate", entry.name));
+            }
+            let output = match output {
+                None => FrameOutput::Terminal,
+                Some(path) => match extension(&path).as_deref() {
+                    Some("txt") => FrameOutput::Text(path),
+                    Some("pgm") => FrameOutput::Images(path, ImageFormat::Pgm),
+                    Some("ppm") => FrameOutput::Images(path, ImageFormat::Ppm),
+                    _ => return Err(format!("--output must end in .txt, .pgm or .ppm: {}", path)),
+                },
+            };
+            Command::Animate(AnimateArgs {
+                run,
+                output,
+                every,
+                delay: Duration::from_millis(delay),
+                scale,
+            })
+        }
+        _ => Command::Run(run),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_run() {
+        let cmd = parse_args(&args("run --day 15 --part 2 --input - --row 10")).unwrap();
+        match cmd {
+            Command::Run(run) => {
+                assert_eq!(run.days[0].name, "day15");
+                assert_eq!(run.part, Some(Part::Two));
+                assert_eq!(run.input, Input::Stdin);
+                assert_eq!(run.params.get("row", 0), Ok(10));
+            }
+            _ => panic!("expected run"),
+        }
+
+        match parse_args(&args("run --day 16 --trace day16=trace,day21")).unwrap() {
+            Command::Run(run) => {
+                assert_eq!(run.trace.len(), 2);
+                assert_eq!(run.trace[0].level, trace::Level::Trace);
+            }
+            _ => panic!("expected run"),
+        }
+
+        match parse_args(&args("run --all")).unwrap() {
+            Command::Run(run) => assert_eq!(run.days.len(), registry::DAYS.len()),
+            _ => panic!("expected run"),
+        }
+
+        match parse_args(&args("run --day 15 --timeout 1.5")).unwrap() {
+            Command::Run(run) => assert_eq!(run.timeout, Some(Duration::from_millis(1500))),
+            _ => panic!("expected run"),
+        }
+        assert!(parse_args(&args("run --day 15 --timeout soon")).is_err());
+
+        match parse_args(&args("run --all --jobs 4")).unwrap() {
+            Command::Run(run) => assert_eq!(run.jobs, Some(4)),
+            _ => panic!("expected run"),
+        }
+        assert!(parse_args(&args("run --all --jobs 0")).is_err());
+
+        match parse_args(&args("run --day 1 --format json")).unwrap() {
+            Command::Run(run) => assert_eq!(run.format, AnswerFormat::Json),
+            _ => panic!("expected run"),
+        }
+        assert!(parse_args(&args("run --day 1 --format yaml")).is_err());
+        assert!(parse_args(&args("run --day 1 --above 10 --format json")).is_ok());
+        assert_eq!(
+            parse_args(&args("run --all --above 10")).err().unwrap(),
+            "--above needs run --format json"
+        );
+        assert!(parse_args(&args("bench --day 1 --target 10")).is_err());
+
+        match parse_args(&args("run --day 1 --no-cache")).unwrap() {
+            Command::Run(run) => assert!(!run.cache),
+            _ => panic!("expected run"),
+        }
+        assert!(matches!(
+            parse_args(&args("cache clear")),
+            Ok(Command::ClearCache)
+        ));
+        assert!(parse_args(&args("cache")).is_err());
+
+        match parse_args(&args("run --day 5 --watch --part 1")).unwrap() {
+            Command::Run(run) => assert!(run.watch),
+            _ => panic!("expected run"),
+        }
+        assert!(parse_args(&args("run --all --watch")).is_err());
+        assert!(parse_args(&args("run --day 5 --watch --input -")).is_err());
+    }
+
+    #[test]
+    fn test_parse_bench() {
+        match parse_args(&args("bench --day 6 --runs 3 --output out.json --window 4")).unwrap() {
+            Command::Bench(bench) => {
+                assert_eq!(bench.run.days[0].name, "day6");
+                assert_eq!(bench.runs, 3);
+                assert_eq!(bench.output, Some(("out.json".to_string(), Format::Json)));
+                assert_eq!(bench.run.params.get("window", 0), Ok(4));
+            }
+            _ => panic!("expected bench"),
+        }
+
+        assert!(parse_args(&args("bench --all --runs 0")).is_err());
+        assert!(parse_args(&args("bench --all --output out.txt")).is_err());
+    }
+
+    #[test]
+    fn test_parse_animate() {
+        match parse_args(&args(
+            "animate --day 14 --part 2 --every 10 --output out/day14.pgm",
+        ))
+        .unwrap()
+        {
+            Command::Animate(animate) => {
+                assert_eq!(animate.run.days[0].name, "day14");
+                assert_eq!(animate.every, 10);
+                assert_eq!(
+                    animate.output,
+                    FrameOutput::Images("out/day14.pgm".to_string(), ImageFormat::Pgm)
+                );
+            }
+            _ => panic!("expected animate"),
+        }
+        match parse_args(&args("animate --day 9")).unwrap() {
+            Command::Animate(animate) => assert_eq!(animate.output, FrameOutput::Terminal),
+            _ => panic!("expected animate"),
+        }
+
+        assert!(parse_args(&args("animate --day 1")).is_err());
+        assert!(parse_args(&args("animate --all")).is_err());
+        assert!(parse_args(&args("animate --day 9 --output frames.gif")).is_err());
+    }
+
+    #[test]
+    fn test_parse_check() {
+        match parse_args(&args("check --day 11_2")).unwrap() {
+            Command::Check(check) => {
+                assert_eq!(check.day.unwrap().name, "day11_2");
+                assert_eq!(check.manifest, answers::MANIFEST);
+            }
+            _ => panic!("expected check"),
+        }
+        assert!(parse_args(&args("check --row 10")).is_err());
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        match parse_args(&args("serve --port 9000 --timeout 30")).unwrap() {
+            Command::Serve(serve) => {
+                assert_eq!(serve.port, 9000);
+                assert_eq!(serve.timeout, Some(Duration::from_secs(30)));
+            }
+            _ => panic!("expected serve"),
+        }
+        match parse_args(&args("serve")).unwrap() {
+            Command::Serve(serve) => assert_eq!((serve.port, serve.timeout), (8022, None)),
+            _ => panic!("expected serve"),
+        }
+        assert!(parse_args(&args("serve --port 70000")).is_err());
+        assert!(parse_args(&args("serve --day 1")).is_err());
+    }
+
+    #[test]
+    fn test_parse_repl() {
+        match parse_args(&args("repl --day 14")).unwrap() {
+            Command::Repl(Some(day)) => assert_eq!(day.name, "day14"),
+            _ => panic!("expected repl"),
+        }
+        assert!(matches!(parse_args(&args("repl")), Ok(Command::Repl(None))));
+        assert!(parse_args(&args("repl --day 30")).is_err());
+        assert!(parse_args(&args("repl --part 1")).is_err());
+        assert!(matches!(
+            parse_args(&args("new-day 22")),
+            Ok(Command::NewDay(22))
+        ));
+        assert!(parse_args(&args("new-day 26")).is_err());
+        assert!(parse_args(&args("new-day")).is_err());
+        assert!(parse_args(&args("new-day 22 23")).is_err());
+    }
+
+    #[test]
+    fn test_parse_generate() {
+        match parse_args(&args("generate --day 21 --size 30 --seed 7")).unwrap() {
+            Command::Generate(generate) => {
+                assert_eq!(generate.day.name, "day21");
+                assert_eq!((generate.size, generate.seed), (30, Some(7)));
+                assert_eq!(generate.output, None);
+            }
+            _ => panic!("expected generate"),
+        }
+        assert!(parse_args(&args("generate --size 30")).is_err());
+        assert!(parse_args(&args("generate --day 1 --seed x")).is_err());
+        assert!(parse_args(&args("generate --day 1 --size 0")).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_args(&args("run")).is_err());
+        assert!(parse_args(&args("run --day 30")).is_err());
+        assert!(parse_args(&args("run --day 1 --part 3")).is_err());
+        assert!(parse_args(&args("run --all --input foo")).is_err());
+        assert!(parse_args(&args("run --day 1 --total-elves")).is_err());
+        assert!(parse_args(&args("walk")).is_err());
+        assert!(parse_args(&args("run --day 16 --trace day16=loud")).is_err());
+        assert!(parse_args(&args("bench --day 16 --trace day16")).is_err());
+    }
+}
@@ -1,34 +1,841 @@
-use std::env;
-use std::fs::read_to_string;
-
-pub fn get_elf_and_energy(path: &str, total_elves: usize) -> (usize, Vec<usize>) {
-    let file = read_to_string(path).expect("Unable to read file");
-    let mut lines = file
-        .split("\n\n")
-        .map(|s| {
-            s.split("\n")
-                .into_iter()
-                .fold(0, |acc, s| acc + s.parse::<usize>().unwrap_or(0))
+use crate::error::{Line, ParseError, ParseResult, INPUT};
+use crate::input::Lines;
+use crate::json::Json;
+use crate::progress::{Progress, TimedOut};
+use crate::solution::{Answer, Details, FromParams, Params, Part, Solution};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+// What one elf carries, `id` counts the elves from 1 in input order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elf {
+    pub id: usize,
+    pub items: usize,
+    pub total: usize,
+    pub max: usize,
+}
+
+// Reads the elves one at a time, so an input never has to be held whole.
+// Lines are taken the way `input::normalise` would leave them, anything but
+// a blank line or a number is an error and ends the iteration.
+pub struct Elves<R> {
+    lines: Lines<R>,
+    elves: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Elves<R> {
+    pub fn new(reader: R) -> Self {
+        Elves {
+            lines: Lines::new(reader),
+            elves: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Elves<R> {
+    // The next elf along with every snack it carries, in input order.
+    pub fn next_with_snacks(&mut self) -> Option<ParseResult<(Elf, Vec<usize>)>> {
+        let mut snacks = vec![];
+        while !self.done {
+            let (number, text) = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(error)) => {
+                    self.done = true;
+                    let file = INPUT.to_string();
+                    return Some(Err(ParseError::Io { file, error }));
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+            if text.is_empty() {
+                if !snacks.is_empty() {
+                    break;
+                }
+                continue;
+            }
+
+            let line = Line {
+                number,
+                text: &text,
+            };
+            match line.parse::<usize>(&text, "a calorie count") {
+                Ok(calories) => snacks.push(calories),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        if snacks.is_empty() {
+            return None;
+        }
+        self.elves += 1;
+        Some(Ok((Elf::new(self.elves, &snacks), snacks)))
+    }
+}
+
+impl<R: BufRead> Iterator for Elves<R> {
+    type Item = ParseResult<Elf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_snacks().map(|elf| elf.map(|(elf, _)| elf))
+    }
+}
+
+impl Elf {
+    pub fn new(id: usize, items: &[usize]) -> Elf {
+        Elf {
+            id,
+            items: items.len(),
+            total: items.iter().sum(),
+            max: items.iter().max().cloned().unwrap_or(0),
+        }
+    }
+}
+
+pub fn parse_str(input: &str) -> ParseResult<Vec<Elf>> {
+    Elves::new(input.as_bytes()).collect()
+}
+
+fn open(path: &str) -> ParseResult<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|error| ParseError::Io {
+            file: path.to_string(),
+            error,
         })
-        .zip(0..)
-        .collect::<Vec<(usize, usize)>>();
+}
+
+pub fn parse_input(path: &str) -> ParseResult<Vec<Elf>> {
+    Elves::new(open(path)?)
+        .collect::<ParseResult<_>>()
+        .map_err(|e| e.in_file(path))
+}
+
+// The elves and, for the queries moving snacks around, what each carries.
+#[derive(Debug, Default, PartialEq)]
+pub struct Inventory {
+    pub elves: Vec<Elf>,
+    pub snacks: Vec<Vec<usize>>,
+}
+
+pub fn parse_inventory(input: &str) -> ParseResult<Inventory> {
+    let mut elves = Elves::new(input.as_bytes());
+    let mut inventory = Inventory::default();
+    while let Some(elf) = elves.next_with_snacks() {
+        let (elf, snacks) = elf?;
+        inventory.elves.push(elf);
+        inventory.snacks.push(snacks);
+    }
+    Ok(inventory)
+}
+
+// Orders elves from worst to best: fewer calories, then the later elf when
+// two carry the same, so ties always go to the elf that came first.
+#[derive(Debug, PartialEq, Eq)]
+struct Ranked(Elf);
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.total, Reverse(self.0.id)).cmp(&(other.0.total, Reverse(other.0.id)))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// The `k` best elves seen so far, in a heap with the worst of them on top so
+// it is the one pushed out by a better elf.
+pub struct TopK {
+    k: usize,
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl TopK {
+    pub fn new(k: usize) -> TopK {
+        TopK {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub fn push(&mut self, elf: Elf) {
+        self.heap.push(Reverse(Ranked(elf)));
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    // Best first.
+    pub fn into_sorted_vec(self) -> Vec<Elf> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(Ranked(elf))| elf)
+            .collect()
+    }
+}
+
+pub fn top_k(elves: impl IntoIterator<Item = Elf>, k: usize) -> Vec<Elf> {
+    let mut top = TopK::new(k);
+    elves.into_iter().for_each(|elf| top.push(elf));
+    top.into_sorted_vec()
+}
+
+// Totals of the whole expedition, percentiles are read from every elf's total
+// so those are kept, sorted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub elves: usize,
+    pub items: usize,
+    pub calories: usize,
+    pub mean: f64,
+    pub median: f64,
+    totals: Vec<usize>,
+}
+
+impl Stats {
+    // None without any elf.
+    pub fn new(items: usize, mut totals: Vec<usize>) -> Option<Stats> {
+        if totals.is_empty() {
+            return None;
+        }
+        totals.sort_unstable();
+        let calories = totals.iter().sum::<usize>();
+        let mut stats = Stats {
+            elves: totals.len(),
+            items,
+            calories,
+            mean: calories as f64 / totals.len() as f64,
+            median: 0.0,
+            totals,
+        };
+        stats.median = stats.percentile(50.0);
+        Some(stats)
+    }
+
+    // `p` in 0..=100, between the two closest totals when it falls between
+    // elves.
+    pub fn percentile(&self, p: f64) -> f64 {
+        let rank = p.clamp(0.0, 100.0) / 100.0 * (self.totals.len() - 1) as f64;
+        let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+        let (low, high) = (self.totals[below] as f64, self.totals[above] as f64);
+        low + (high - low) * (rank - below as f64)
+    }
+}
+
+// Goes through the elves once, keeping only the best `k` of them and their
+// totals for the statistics.
+pub fn survey(
+    elves: impl IntoIterator<Item = ParseResult<Elf>>,
+    k: usize,
+) -> ParseResult<(Vec<Elf>, Option<Stats>)> {
+    let mut top = TopK::new(k);
+    let mut items = 0;
+    let mut totals = vec![];
+    for elf in elves {
+        let elf = elf?;
+        items += elf.items;
+        totals.push(elf.total);
+        top.push(elf);
+    }
+    Ok((top.into_sorted_vec(), Stats::new(items, totals)))
+}
+
+// Calories of the elves together and their zero based indices.
+fn summed(top: Vec<Elf>) -> (usize, Vec<usize>) {
+    (
+        top.iter().map(|elf| elf.total).sum(),
+        top.iter().map(|elf| elf.id - 1).collect(),
+    )
+}
+
+pub fn top_elves(elves: &[Elf], total_elves: usize) -> (usize, Vec<usize>) {
+    summed(top_k(elves.iter().cloned(), total_elves))
+}
+
+pub fn get_elf_and_energy(path: &str, total_elves: usize) -> ParseResult<(usize, Vec<usize>)> {
+    let mut top = TopK::new(total_elves);
+    for elf in Elves::new(open(path)?) {
+        top.push(elf.map_err(|e| e.in_file(path))?);
+    }
+    Ok(summed(top.into_sorted_vec()))
+}
+
+// Elves carrying more than `threshold` calories, in input order.
+pub fn above(elves: &[Elf], threshold: usize) -> Vec<Elf> {
+    elves
+        .iter()
+        .filter(|elf| elf.total > threshold)
+        .cloned()
+        .collect()
+}
+
+// Elves whose calories together reach `target` with as little to spare as
+// possible, and among those the fewest elves. None when all of them together
+// fall short. A subset sum over every total up to the target plus the
+// biggest elf, so it takes elves × target steps and bits, which is why
+// --target is at most `MAX_TARGET`.
+pub fn combine(
+    elves: &[Elf],
+    target: usize,
+    progress: &Progress,
+) -> Result<Option<Vec<Elf>>, TimedOut> {
+    if elves.iter().map(|elf| elf.total).sum::<usize>() < target {
+        return Ok(None);
+    }
+    let bound = target + elves.iter().map(|elf| elf.total).max().unwrap_or(0) + 1;
+    // fewest elves adding up to each total, and for every elf the totals
+    // where adding it is what improved them
+    let mut fewest = vec![usize::MAX; bound];
+    fewest[0] = 0;
+    let mut taken = vec![vec![0u64; bound.div_ceil(64)]; elves.len()];
+    for (i, elf) in elves.iter().enumerate() {
+        progress.step(i as u64, Some(elves.len() as u64))?;
+        for sum in (elf.total.max(1)..bound).rev() {
+            let without = fewest[sum - elf.total];
+            if without != usize::MAX && without + 1 < fewest[sum] {
+                fewest[sum] = without + 1;
+                taken[i][sum / 64] |= 1 << (sum % 64);
+            }
+        }
+    }
+
+    let Some(mut sum) = (target..bound).find(|sum| fewest[*sum] != usize::MAX) else {
+        return Ok(None);
+    };
+    let mut combination = vec![];
+    for (i, elf) in elves.iter().enumerate().rev() {
+        if taken[i][sum / 64] & (1 << (sum % 64)) != 0 {
+            combination.push(*elf);
+            sum -= elf.total;
+        }
+    }
+    combination.reverse();
+    Ok(Some(combination))
+}
+
+// One snack handed from an elf to another, both by id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub calories: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+// The band every elf has to end up in. Totals are scaled by the number of
+// elves so the mean stays in integers.
+struct Band {
+    elves: i128,
+    sum: i128,
+    tolerance: i128,
+}
+
+impl Band {
+    fn new(totals: &[i128], tolerance: usize) -> Band {
+        Band {
+            elves: totals.len() as i128,
+            sum: totals.iter().sum(),
+            tolerance: tolerance as i128,
+        }
+    }
+
+    // how far a total is outside the band, scaled
+    fn outside(&self, total: i128) -> i128 {
+        ((total * self.elves - self.sum).abs() - self.tolerance * self.elves).max(0)
+    }
 
-    lines.sort_by(|a, b| b.0.cmp(&a.0));
-    lines[0..total_elves]
+    fn over(&self, total: i128) -> bool {
+        total * self.elves - self.sum > self.tolerance * self.elves
+    }
+}
+
+fn totals(inventory: &[Vec<usize>]) -> Vec<i128> {
+    inventory
         .iter()
-        .fold((0, Vec::new()), |(sum, mut elves), (calories, elf)| {
-            elves.push(*elf);
-            (sum + calories, elves)
+        .map(|items| items.iter().sum::<usize>() as i128)
+        .collect()
+}
+
+// A plan never needs to move a snack twice, only where each one ends up
+// matters, so a search through which snacks move where, one more snack at a
+// time.
+struct Search {
+    // every snack and the elf carrying it, grouped by elf
+    snacks: Vec<(usize, i128)>,
+    // past this many snacks an elf has nothing left to hand over
+    ends: Vec<usize>,
+    totals: Vec<i128>,
+    band: Band,
+    moves: Vec<Move>,
+    nodes: u64,
+}
+
+impl Search {
+    // Whether moving at most `left` of the snacks from `start` on is enough.
+    fn moves(&mut self, start: usize, left: usize, progress: &Progress) -> Result<bool, TimedOut> {
+        if self.nodes.is_multiple_of(1024) {
+            progress.step(self.nodes, None)?;
+        }
+        self.nodes += 1;
+        let out = self
+            .totals
+            .iter()
+            .filter(|total| self.band.outside(**total) > 0)
+            .count();
+        if out == 0 {
+            return Ok(true);
+        }
+        // a move brings at most two elves into the band, and an elf over it
+        // with nothing left to hand over stays there
+        if 2 * left < out
+            || (0..self.totals.len())
+                .any(|elf| self.ends[elf] <= start && self.band.over(self.totals[elf]))
+        {
+            return Ok(false);
+        }
+
+        for i in start..self.snacks.len() {
+            // the same snack of the same elf again only repeats the search
+            if i > start && self.snacks[i - 1] == self.snacks[i] {
+                continue;
+            }
+            let (from, calories) = self.snacks[i];
+            for to in 0..self.totals.len() {
+                if to == from
+                    || (self.ends[to] <= i + 1 && self.band.over(self.totals[to] + calories))
+                {
+                    continue;
+                }
+                self.totals[from] -= calories;
+                self.totals[to] += calories;
+                self.moves.push(Move {
+                    calories: calories as usize,
+                    from: from + 1,
+                    to: to + 1,
+                });
+                if self.moves(i + 1, left - 1, progress)? {
+                    return Ok(true);
+                }
+                self.moves.pop();
+                self.totals[from] += calories;
+                self.totals[to] -= calories;
+            }
+        }
+        Ok(false)
+    }
+}
+
+// Snacks to hand over until every elf carries within `tolerance` calories of
+// the mean, as few as possible. None when there is no such plan, which is
+// certain when a single snack is heavier than the mean plus the tolerance.
+// The search is exponential in the number of moves, so it starts from a
+// plan found by `greedy` and only looks for shorter ones, giving up once
+// `progress` says so.
+pub fn rebalance(
+    inventory: &[Vec<usize>],
+    tolerance: usize,
+    progress: &Progress,
+) -> Result<Option<Vec<Move>>, TimedOut> {
+    let band = Band::new(&totals(inventory), tolerance);
+    // whoever ends up with such a snack is over the band
+    if inventory
+        .iter()
+        .flatten()
+        .any(|calories| band.over(*calories as i128))
+    {
+        return Ok(None);
+    }
+
+    let plan = greedy(inventory, tolerance);
+    let mut snacks = vec![];
+    let mut ends = vec![];
+    for (elf, items) in inventory.iter().enumerate() {
+        snacks.extend(items.iter().map(|calories| (elf, *calories as i128)));
+        ends.push(snacks.len());
+    }
+    let limit = plan.as_ref().map_or(snacks.len() + 1, Vec::len);
+    let mut search = Search {
+        snacks,
+        ends,
+        totals: totals(inventory),
+        band,
+        moves: vec![],
+        nodes: 0,
+    };
+    for left in 0..limit {
+        if search.moves(0, left, progress)? {
+            return Ok(Some(search.moves));
+        }
+    }
+    Ok(plan)
+}
+
+// Takes the best single move each time: the one bringing the elves closest
+// to the band, or if none does, closer to each other. None when no snack can
+// move without making things worse, even with a plan in reach.
+fn greedy(inventory: &[Vec<usize>], tolerance: usize) -> Option<Vec<Move>> {
+    let mut inventory = inventory.to_vec();
+    let mut totals = totals(&inventory);
+    let band = Band::new(&totals, tolerance);
+    let outside = |total: i128| band.outside(total);
+
+    let mut moves = vec![];
+    while totals.iter().any(|total| outside(*total) > 0) {
+        let mut best: Option<((i128, i128), usize, usize, usize)> = None;
+        for (from, items) in inventory.iter().enumerate() {
+            for (item, calories) in items.iter().map(|c| *c as i128).enumerate() {
+                for to in (0..totals.len()).filter(|to| *to != from) {
+                    let (f, t) = (totals[from], totals[to]);
+                    let closer =
+                        outside(f - calories) + outside(t + calories) - outside(f) - outside(t);
+                    let spread = 2 * calories * (calories - f + t);
+                    if best.is_none_or(|(key, ..)| (closer, spread) < key) {
+                        best = Some(((closer, spread), from, to, item));
+                    }
+                }
+            }
+        }
+        let (_, from, to, item) = best.filter(|(key, ..)| *key < (0, 0))?;
+        let calories = inventory[from].swap_remove(item);
+        inventory[to].push(calories);
+        totals[from] -= calories as i128;
+        totals[to] += calories as i128;
+        moves.push(Move {
+            calories,
+            from: from + 1,
+            to: to + 1,
+        });
+    }
+    Some(moves)
+}
+
+// Keeps `combine` to a few tens of megabytes on inputs of a few hundred
+// elves.
+pub const MAX_TARGET: usize = 1_000_000;
+
+pub struct Day1 {
+    pub total_elves: usize,
+    pub above: Option<usize>,
+    pub target: Option<usize>,
+    pub tolerance: Option<usize>,
+}
+
+impl Default for Day1 {
+    fn default() -> Self {
+        Day1 {
+            total_elves: 3,
+            above: None,
+            target: None,
+            tolerance: None,
+        }
+    }
+}
+
+impl FromParams for Day1 {
+    fn from_params(params: &Params) -> Result<Self, String> {
+        let target = params.optional("target")?;
+        if target.is_some_and(|target: usize| target > MAX_TARGET) {
+            return Err(format!("--target is at most {}", MAX_TARGET));
+        }
+        Ok(Day1 {
+            total_elves: params.get("total-elves", 3)?,
+            above: params.optional("above")?,
+            target,
+            tolerance: params.optional("tolerance")?,
         })
+    }
+}
+
+// zero based, like the indices in the details
+fn indices(elves: Vec<Elf>) -> Json {
+    Json::from(elves.iter().map(|elf| elf.id - 1).collect::<Vec<_>>())
+}
+
+impl Solution for Day1 {
+    type Input = Inventory;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_inventory(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        top_elves(&input.elves, 1).0.into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        top_elves(&input.elves, self.total_elves).0.into()
+    }
+
+    // zero based indices of the elves carrying the most, and with part 1 the
+    // answers to whichever of --above, --target and --tolerance were asked
+    fn details(&self, input: &Self::Input, part: Part) -> Details {
+        // nothing stops it, so it always finishes
+        self.details_with(input, part, &Progress::none())
+            .unwrap_or_default()
+    }
+
+    fn details_with(
+        &self,
+        input: &Self::Input,
+        part: Part,
+        progress: &Progress,
+    ) -> Result<Details, TimedOut> {
+        let elves = &input.elves;
+        let total_elves = match part {
+            Part::One => 1,
+            Part::Two => self.total_elves,
+        };
+        let mut details = vec![("elves", Json::from(top_elves(elves, total_elves).1))];
+        if part == Part::Two {
+            return Ok(details);
+        }
+        if let Some(threshold) = self.above {
+            details.push(("above", indices(above(elves, threshold))));
+        }
+        if let Some(target) = self.target {
+            details.push((
+                "combination",
+                combine(elves, target, progress)?.map(indices).into(),
+            ));
+        }
+        if let Some(tolerance) = self.tolerance {
+            let moves = rebalance(&input.snacks, tolerance, progress)?.map(|moves| {
+                moves
+                    .iter()
+                    .map(|m| {
+                        Json::object([
+                            ("calories", Json::from(m.calories)),
+                            ("from", Json::from(m.from - 1)),
+                            ("to", Json::from(m.to - 1)),
+                        ])
+                    })
+                    .collect::<Vec<_>>()
+            });
+            details.push(("moves", moves.into()));
+        }
+        Ok(details)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::solution::Solver;
     #[test]
     fn test_day1() {
         let file = "src/specs/day1";
-        let result = get_elf_and_energy(file, 1);
+        let result = get_elf_and_energy(file, 1).unwrap();
         assert_eq!(result, (24000, vec![3]));
+        assert_eq!(get_elf_and_energy(file, 9).unwrap().0, 55000);
+    }
+
+    #[test]
+    fn test_elves() {
+        let elves = parse_input("src/specs/day1").unwrap();
+        assert_eq!(elves.len(), 5);
+        assert_eq!(
+            elves[3],
+            Elf {
+                id: 4,
+                items: 3,
+                total: 24000,
+                max: 9000
+            }
+        );
+        // blank runs, CRLF and a byte order mark change nothing
+        let messy = "\u{feff}1000\r\n2000 \r\n\r\n\r\n\r\n4000\r\n\r\n";
+        assert_eq!(
+            parse_str(messy).unwrap(),
+            parse_str("1000\n2000\n\n4000\n").unwrap()
+        );
+
+        assert_eq!(
+            parse_str("1000\n\n40x0\n5\n").unwrap_err().to_string(),
+            "<input>:3:1: expected a calorie count, found `40x0`"
+        );
+        let mut elves = Elves::new("1\n\n-2\n\n3\n".as_bytes());
+        assert!(elves.next().unwrap().is_ok());
+        assert!(elves.next().unwrap().is_err());
+        assert!(elves.next().is_none());
+    }
+
+    #[test]
+    fn test_top_k() {
+        let elves = parse_str("5\n\n7\n\n5\n\n7\n\n1\n").unwrap();
+        let ids = |k| {
+            top_k(elves.iter().cloned(), k)
+                .iter()
+                .map(|elf| elf.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(3), [2, 4, 1]);
+        assert_eq!(ids(9), [2, 4, 1, 3, 5]);
+        assert_eq!(ids(0), []);
+        assert_eq!(
+            top_k(elves.iter().rev().cloned(), 3),
+            top_k(elves.clone(), 3)
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let inventory =
+            parse_inventory(&crate::error::read_input("src/specs/day1").unwrap()).unwrap();
+        let elves = inventory.elves;
+        assert_eq!(elves, parse_input("src/specs/day1").unwrap());
+        assert_eq!(inventory.snacks[2], [5000, 6000]);
+        let ids = |elves: Vec<Elf>| elves.iter().map(|elf| elf.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(above(&elves, 10000)), [3, 4]);
+        assert_eq!(ids(above(&elves, 24000)), []);
+
+        // 10000 + 4000 rather than 11000 + 4000 or the single 24000
+        let combined = |target| combine(&elves, target, &Progress::none()).unwrap();
+        assert_eq!(ids(combined(14000).unwrap()), [2, 5]);
+        assert_eq!(ids(combined(24000).unwrap()), [4]);
+        assert_eq!(ids(combined(30000).unwrap()), [1, 4]);
+        assert_eq!(ids(combined(0).unwrap()), []);
+        assert_eq!(ids(combined(55000).unwrap()), [1, 2, 3, 4, 5]);
+        assert_eq!(combined(55001), None);
+
+        let cancelled = std::sync::atomic::AtomicBool::new(true);
+        let progress = Progress::new().with_cancel(&cancelled);
+        assert_eq!(combine(&elves, 14000, &progress), Err(TimedOut));
+        let mut params = Params::new();
+        params.set("target", &(MAX_TARGET + 1).to_string());
+        assert_eq!(
+            Day1::from_params(&params).err().unwrap(),
+            "--target is at most 1000000"
+        );
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let rebalanced = |inventory: &[Vec<usize>], tolerance| {
+            rebalance(inventory, tolerance, &Progress::none()).unwrap()
+        };
+        // what every elf carries once the moves are made
+        let totals = |inventory: &[Vec<usize>], moves: &[Move]| {
+            let mut inventory = inventory.to_vec();
+            for m in moves.iter() {
+                let item = inventory[m.from - 1]
+                    .iter()
+                    .position(|c| *c == m.calories)
+                    .unwrap();
+                inventory[m.from - 1].swap_remove(item);
+                inventory[m.to - 1].push(m.calories);
+            }
+            inventory
+                .iter()
+                .map(|snacks| snacks.iter().sum::<usize>())
+                .collect::<Vec<_>>()
+        };
+
+        let inventory = vec![vec![1000, 2000, 3000], vec![4000], vec![5000, 6000]];
+        assert_eq!(
+            rebalanced(&inventory, 2000).unwrap(),
+            [Move {
+                calories: 5000,
+                from: 3,
+                to: 2
+            }]
+        );
+        assert_eq!(rebalanced(&inventory, 5000).unwrap(), []);
+
+        // every elf ends up within the tolerance of the mean, 11000
+        let inventory = parse_inventory(&crate::error::read_input("src/specs/day1").unwrap())
+            .unwrap()
+            .snacks;
+        let moves = rebalanced(&inventory, 2000).unwrap();
+        assert!(moves.len() <= greedy(&inventory, 2000).unwrap().len());
+        assert!(totals(&inventory, &moves)
+            .iter()
+            .all(|total| total.abs_diff(11000) <= 2000));
+
+        // the best move first leaves two more to make where two in all do
+        let inventory = vec![vec![2000], vec![2000], vec![5000, 8000, 1000]];
+        assert_eq!(greedy(&inventory, 2000).unwrap().len(), 3);
+        let moves = rebalanced(&inventory, 2000).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert!(totals(&inventory, &moves)
+            .iter()
+            .all(|total| total.abs_diff(6000) <= 2000));
+        // and finds a plan where every single move makes things worse
+        let inventory = vec![vec![3000, 6000, 5000], vec![], vec![6000, 5000, 2000]];
+        assert_eq!(greedy(&inventory, 1000), None);
+        assert_eq!(rebalanced(&inventory, 1000).unwrap().len(), 2);
+
+        // a single snack cannot be split
+        assert_eq!(rebalanced(&[vec![10], vec![]], 2), None);
+        assert_eq!(rebalanced(&[], 0).unwrap(), []);
+
+        let cancelled = std::sync::atomic::AtomicBool::new(true);
+        let progress = Progress::new().with_cancel(&cancelled);
+        assert_eq!(
+            rebalance(&[vec![100; 10], vec![]], 0, &progress),
+            Err(TimedOut)
+        );
+    }
+
+    #[test]
+    fn test_details() {
+        let mut params = Params::new();
+        params.set("above", "10000");
+        params.set("target", "14000");
+        params.set("tolerance", "2000");
+        let day1 = Day1::from_params(&params).unwrap();
+        let input = day1
+            .parse_str("1000\n2000\n3000\n\n4000\n\n5000\n6000\n")
+            .unwrap();
+        assert_eq!(
+            Json::object(day1.details(&input, Part::One)).to_string(),
+            r#"{"elves": [2], "above": [2], "combination": [1, 2], "moves": [{"calories": 5000, "from": 2, "to": 1}]}"#
+        );
+        assert_eq!(
+            Json::object(day1.details(&input, Part::Two)).to_string(),
+            r#"{"elves": [2, 0, 1]}"#
+        );
+
+        // the queries run under the timeout like the answers do
+        let cancelled = std::sync::atomic::AtomicBool::new(true);
+        let progress = Progress::new().with_cancel(&cancelled);
+        let timed =
+            Solver::solve_timed_with(&day1, "7000\n\n7000\n", Part::One, &progress, true).unwrap();
+        assert_eq!((timed.answer, timed.details), (Answer::TimedOut, vec![]));
+        // and are left alone unless asked for
+        let timed =
+            Solver::solve_timed_with(&day1, "7000\n\n7000\n", Part::One, &Progress::none(), false)
+                .unwrap();
+        assert_eq!(
+            (timed.answer, timed.details),
+            (Answer::Number(7000), vec![])
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let (top, stats) = survey(Elves::new(open("src/specs/day1").unwrap()), 2).unwrap();
+        assert_eq!(top.iter().map(|elf| elf.id).collect::<Vec<_>>(), [4, 3]);
+        let stats = stats.unwrap();
+        assert_eq!((stats.elves, stats.items, stats.calories), (5, 10, 55000));
+        assert_eq!((stats.mean, stats.median), (11000.0, 10000.0));
+        assert_eq!(stats.percentile(0.0), 4000.0);
+        assert_eq!(stats.percentile(25.0), 6000.0);
+        assert_eq!(stats.percentile(90.0), 18800.0);
+        assert_eq!(stats.percentile(100.0), 24000.0);
+        assert_eq!(
+            survey(parse_str("").unwrap().into_iter().map(Ok), 3).unwrap(),
+            (vec![], None)
+        );
     }
 }
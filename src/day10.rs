@@ -1,9 +1,14 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use crate::error::{from_path, ParseResult};
+use crate::frames::{Animate, Frame, FrameSink};
+use crate::input::lines;
+use crate::simulation::{Simulate, Simulation};
+use crate::solution::{Answer, Part, Solution};
+use crate::trace;
+use std::fmt::{Display, Error, Formatter};
+use std::io;
 
 pub fn check_probe(counter: usize, value: i32, probes: &mut Vec<usize>) -> i32 {
-    if probes.len() == 0 {
+    if probes.is_empty() {
         return 0;
     };
     let probe = probes.pop().unwrap();
@@ -11,76 +16,223 @@ pub fn check_probe(counter: usize, value: i32, probes: &mut Vec<usize>) -> i32 {
         return value * probe as i32;
     }
     probes.push(probe);
-    return 0;
+    0
 }
 
 pub fn check_intersection(counter: usize, value: i32) -> bool {
     (counter % 40) as i32 <= value + 1 && (counter % 40) as i32 >= value - 1
 }
 
-pub fn parse_input(path: &str) -> (i32, String) {
-    let file = File::open(path).expect("Unable to open file");
+pub fn parse_str(input: &str) -> ParseResult<Vec<Option<i32>>> {
+    lines(input)
+        .map(|line| {
+            if line.text == "noop" {
+                Ok(None)
+            } else if let Some(value) = line.text.strip_prefix("addx ") {
+                Ok(Some(line.parse::<i32>(value, "a register value")?))
+            } else {
+                Err(line.error(line.text, "noop or addx"))
+            }
+        })
+        .collect()
+}
+
+pub fn read_program(path: &str) -> ParseResult<Vec<Option<i32>>> {
+    from_path(path, parse_str)
+}
+
+// The register at every cycle, starting from cycle 0 before the program runs.
+pub fn register_cycles(program: &[Option<i32>]) -> Vec<(usize, i32)> {
     let mut counter = 0;
     let mut value = 1;
     let mut cycles: Vec<(usize, i32)> = vec![];
-    let mut probes = vec![20, 60, 100, 140, 180, 220];
-    let mut result = 0;
-    probes.reverse();
 
     cycles.push((counter, value));
 
-    for l in io::BufReader::new(file).lines() {
-        let line = l.unwrap();
-        if line == "noop" {
-            counter += 1;
-            cycles.push((counter, value));
-            result += check_probe(counter, value, &mut probes);
-        } else {
-            let next_value = line[5..].parse::<i32>().unwrap();
-            counter += 1;
-            cycles.push((counter, value));
-            result += check_probe(counter, value, &mut probes);
-
-            counter += 1;
-            value += next_value;
-            cycles.push((counter, value));
-            result += check_probe(counter, value, &mut probes);
+    for instruction in program {
+        match instruction {
+            None => {
+                counter += 1;
+                cycles.push((counter, value));
+            }
+            Some(next_value) => {
+                counter += 1;
+                cycles.push((counter, value));
+
+                counter += 1;
+                value += next_value;
+                cycles.push((counter, value));
+            }
         }
     }
 
-    let pattern = cycles
+    cycles
+}
+
+fn pixel(counter: usize, value: i32) -> char {
+    if check_intersection(counter, value) {
+        '#'
+    } else {
+        '.'
+    }
+}
+
+pub fn run_program(program: &[Option<i32>]) -> (i32, String) {
+    let cycles = register_cycles(program);
+    let mut probes = vec![20, 60, 100, 140, 180, 220];
+    probes.reverse();
+
+    let result = cycles
         .iter()
-        .map(|(counter, value)| {
-            if check_intersection(*counter, *value) {
-                return "#";
-            }
+        .skip(1)
+        .map(|(counter, value)| check_probe(*counter, *value, &mut probes))
+        .sum();
 
-            "."
-        })
-        .collect::<Vec<&str>>()
-        .join("");
+    let pattern = cycles
+        .iter()
+        .map(|(counter, value)| pixel(*counter, *value))
+        .collect::<String>();
 
-    // println!("{}", pattern);
+    trace!(Debug, "crt", signal = result, pattern = pattern);
 
     (result, pattern)
 }
 
+pub fn parse_input(path: &str) -> ParseResult<(i32, String)> {
+    Ok(run_program(&read_program(path)?))
+}
+
+// The CRT is 40 pixels wide and 6 rows high, the last cycle is not drawn.
+pub fn render_crt(pattern: &str) -> String {
+    pattern
+        .chars()
+        .collect::<Vec<char>>()
+        .chunks(40)
+        .take(6)
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// One frame per cycle, pixels the beam has not reached yet are blank.
+pub fn animate_crt(program: &[Option<i32>], sink: &mut dyn FrameSink) -> io::Result<()> {
+    let pattern = run_program(program).1;
+    let pixels = pattern.len().min(240);
+    for drawn in 0..=pixels {
+        let screen = format!("{:<240}", &pattern[..drawn]);
+        sink.frame(&Frame::from_text(&render_crt(&screen)))?;
+    }
+    sink.finish()
+}
+
+// The CRT cycle by cycle.
+pub struct Crt {
+    cycles: Vec<(usize, i32)>,
+    drawn: usize,
+}
+
+impl Crt {
+    pub fn new(program: &[Option<i32>]) -> Crt {
+        Crt {
+            cycles: register_cycles(program),
+            drawn: 0,
+        }
+    }
+}
+
+impl Simulation for Crt {
+    fn step(&mut self) -> Option<String> {
+        let (counter, value) = *self.cycles.get(self.drawn)?;
+        self.drawn += 1;
+        Some(format!(
+            "cycle {}: X is {}, draws {}",
+            counter,
+            value,
+            pixel(counter, value)
+        ))
+    }
+}
+
+impl Display for Crt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let (counter, value) = self.cycles[self.drawn.max(1) - 1];
+        writeln!(f, "cycle {}, X is {}", counter, value)?;
+        let pattern = self.cycles[..self.drawn]
+            .iter()
+            .map(|(counter, value)| pixel(*counter, *value))
+            .collect::<String>();
+        write!(f, "{}", render_crt(&format!("{:<240}", pattern)))
+    }
+}
+
+#[derive(Default)]
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Vec<Option<i32>>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        run_program(input).0.into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        render_crt(&run_program(input).1).into()
+    }
+}
+
+impl Animate for Day10 {
+    fn animate(
+        &self,
+        input: &Self::Input,
+        _part: Part,
+        sink: &mut dyn FrameSink,
+    ) -> io::Result<()> {
+        animate_crt(input, sink)
+    }
+}
+
+impl Simulate for Day10 {
+    fn simulate(&self, input: &Self::Input, _part: Part) -> Box<dyn Simulation> {
+        Box::new(Crt::new(input))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     #[test]
     fn test_day10_part1() {
         let file = "src/specs/day10";
-        let result = parse_input(file);
+        let result = parse_input(file).unwrap();
         assert_eq!(result.0, 13140);
     }
 
     #[test]
     fn test_day10_part2() {
         let file = "src/specs/day10";
-        let result = parse_input(file);
+        let result = parse_input(file).unwrap();
         let out = "##..##..##..##..##..##..##..##..##..##..###...###...###...###...###...###...###.####....####....####....####....####....#####.....#####.....#####.....#####.....######......######......######......###########.......#######.......#######......";
 
         assert_eq!(result.1, out);
     }
+
+    #[test]
+    fn test_animate_crt() {
+        let program = read_program("src/specs/day10").unwrap();
+        let mut frames = vec![];
+        animate_crt(&program, &mut frames).unwrap();
+        assert_eq!(frames.len(), 241);
+        assert_eq!(
+            frames[3].to_text().lines().next(),
+            Some("##.                                     ")
+        );
+        assert_eq!(
+            frames[240].to_text(),
+            render_crt(&parse_input("src/specs/day10").unwrap().1)
+        );
+    }
 }
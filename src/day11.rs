@@ -1,36 +1,92 @@
+use crate::error::{from_path, Line, ParseResult};
+use crate::input::paragraphs;
+use crate::solution::{Answer, FromParams, Params, Solution};
+use crate::trace;
 use regex::Regex;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
 
-pub fn parse_input(path: &str) -> Vec<String> {
-    let file = File::open(path).expect("Unable to open file");
-    let mut monkey_strings = vec![String::new()];
-
-    for i in io::BufReader::new(file).lines() {
-        let line = i.unwrap();
+const NOTES: [(&str, &str); 6] = [
+    (r"^Monkey \d+:$", "Monkey N:"),
+    (
+        r"^\s*Starting items:( \d+(, \d+)*)?$",
+        "Starting items: a, b, ...",
+    ),
+    (
+        r"^\s*Operation: new = old [+*] (\d+|old)$",
+        "Operation: new = old + N",
+    ),
+    (r"^\s*Test: divisible by \d+$", "Test: divisible by N"),
+    (
+        r"^\s*If true: throw to monkey \d+$",
+        "If true: throw to monkey N",
+    ),
+    (
+        r"^\s*If false: throw to monkey \d+$",
+        "If false: throw to monkey N",
+    ),
+];
+
+// One monkey's notes, as joined by `parse_str`.
+pub const MONKEY: &str = r"Monkey (\d+):.*Starting items:(.*)Operation:.*new = old (.) (\d+|old).*Test: divisible by (\d+).*If true: throw to monkey (\d+).*If false: throw to monkey (\d+)";
+
+// Checks every monkey note line by line and joins each monkey into a single
+// string for the solvers.
+pub fn parse_str(input: &str) -> ParseResult<Vec<String>> {
+    let notes = NOTES.map(|(re, expected)| (Regex::new(re).unwrap(), expected));
+    let monkeys = paragraphs(input);
+    let joined = monkeys
+        .iter()
+        .map(|monkey| {
+            for (i, (re, expected)) in notes.iter().enumerate() {
+                let last = monkey.last().unwrap();
+                let line = monkey.get(i).unwrap_or(last);
+                if monkey.len() <= i || !re.is_match(line.text) {
+                    return Err(line.error(line.text, expected));
+                }
+            }
 
-        if line.len() < 3 {
-            monkey_strings.push(String::new());
-        } else {
-            let result = monkey_strings.pop().unwrap();
+            Ok(monkey.iter().map(|l| l.text).collect::<String>())
+        })
+        .collect::<ParseResult<Vec<_>>>()?;
 
-            monkey_strings.push(format!("{}{}", result, line));
+    // the answer multiplies the counts of the two most active monkeys
+    if monkeys.len() < 2 {
+        return Err(Line::end(input).error("", "the notes of another monkey"));
+    }
+    let names = monkeys
+        .iter()
+        .map(|monkey| {
+            monkey[0]
+                .text
+                .trim_start_matches("Monkey ")
+                .trim_end_matches(':')
+        })
+        .collect::<Vec<_>>();
+    for line in monkeys.iter().flat_map(|monkey| &monkey[4..6]) {
+        let target = line.text.rsplit(' ').next().unwrap();
+        if !names.contains(&target) {
+            return Err(line.error(target, "a monkey from the notes"));
+        }
+    }
+    for line in monkeys.iter().map(|monkey| &monkey[3]) {
+        let divisor = line.text.rsplit(' ').next().unwrap();
+        if !matches!(divisor.parse::<i32>(), Ok(n) if n > 0) {
+            return Err(line.error(divisor, "a divisor above 0"));
         }
     }
+    Ok(joined)
+}
 
-    monkey_strings
+pub fn parse_input(path: &str) -> ParseResult<Vec<String>> {
+    from_path(path, parse_str)
 }
 
-pub fn monkeys_to_string(path: &str, rounds: usize) -> usize {
-    let input = parse_input(path);
+pub fn monkey_business(input: &[String], rounds: usize) -> usize {
     let mut monkeys: HashMap<String, Monkey> = HashMap::new();
-    let regexp =
-        Regex::new(r"Monkey (\d+):.*Starting items:(.*)Operation:.*new = old (.) (\d+|old).*Test: divisible by (\d+).*If true: throw to monkey (\d+).*If false: throw to monkey (\d+)")
-            .unwrap();
+    let regexp = Regex::new(MONKEY).unwrap();
 
     for monkey_s in input {
-        let captures = regexp.captures(&monkey_s).unwrap();
+        let captures = regexp.captures(monkey_s).unwrap();
         let monkey_id = captures.get(1).unwrap().as_str();
         let items = captures
             .get(2)
@@ -38,12 +94,12 @@ pub fn monkeys_to_string(path: &str, rounds: usize) -> usize {
             .as_str()
             .trim()
             .split(", ")
-            .map(|i| i.parse::<f32>().unwrap())
-            .collect::<Vec<f32>>();
+            .map(|i| i.parse::<u128>().unwrap())
+            .collect::<Vec<u128>>();
 
         let op = captures.get(3).unwrap().as_str();
         let op_value = captures.get(4).unwrap().as_str();
-        let div_by = captures.get(5).unwrap().as_str().parse::<f32>().unwrap();
+        let div_by = captures.get(5).unwrap().as_str().parse::<u128>().unwrap();
         let monkey_id_true = captures.get(6).unwrap().as_str();
         let monkey_id_false = captures.get(7).unwrap().as_str();
 
@@ -63,22 +119,113 @@ pub fn monkeys_to_string(path: &str, rounds: usize) -> usize {
     monkey_loop(monkeys, rounds)
 }
 
+// Reference for both parts in whole numbers. Without relief they are kept
+// modulo the product of the divisors, with it they are exact. None when they
+// outgrow u128 or the monkeys are not numbered 0, 1, 2, ...
+pub fn monkey_business_reference(input: &[String], rounds: usize, relief: bool) -> Option<usize> {
+    let regexp = Regex::new(MONKEY).unwrap();
+    let notes = input
+        .iter()
+        .map(|monkey| regexp.captures(monkey).unwrap())
+        .collect::<Vec<_>>();
+    let number = |c: &regex::Captures, i: usize| c[i].parse::<u128>().unwrap();
+
+    let mut items = notes
+        .iter()
+        .map(|c| {
+            c[2].split(',')
+                .filter(|i| !i.trim().is_empty())
+                .map(|i| i.trim().parse::<u128>().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let modulus = notes.iter().map(|c| number(c, 5)).product::<u128>();
+    if (0..notes.len()).any(|i| number(&notes[i], 1) != i as u128) {
+        return None;
+    }
+    let mut inspected = vec![0; notes.len()];
+
+    for _ in 0..rounds {
+        for (i, c) in notes.iter().enumerate() {
+            for worry in std::mem::take(&mut items[i]) {
+                let value = if &c[4] == "old" { worry } else { number(c, 4) };
+                let worry = match &c[3] {
+                    "+" => worry.checked_add(value)?,
+                    _ => worry.checked_mul(value)?,
+                };
+                let worry = if relief { worry / 3 } else { worry % modulus };
+                let to = if worry % number(c, 5) == 0 { 6 } else { 7 };
+                items.get_mut(number(c, to) as usize)?.push(worry);
+                inspected[i] += 1;
+            }
+        }
+    }
+
+    inspected.sort();
+    inspected.reverse();
+    Some(inspected[0] * inspected.get(1).unwrap_or(&0))
+}
+
+pub fn monkeys_to_string(path: &str, rounds: usize) -> ParseResult<usize> {
+    Ok(monkey_business(&parse_input(path)?, rounds))
+}
+
+// Worry levels overflow u128 without the relief division, so the second part
+// is solved with the remainder arithmetic from day11_2.
+pub struct Day11 {
+    pub rounds: usize,
+    pub rounds_part2: usize,
+}
+
+impl Default for Day11 {
+    fn default() -> Self {
+        Day11 {
+            rounds: 20,
+            rounds_part2: 10000,
+        }
+    }
+}
+
+impl FromParams for Day11 {
+    fn from_params(params: &Params) -> Result<Self, String> {
+        Ok(Day11 {
+            rounds: params.get("rounds", 20)?,
+            rounds_part2: params.get("rounds-part2", 10000)?,
+        })
+    }
+}
+
+impl Solution for Day11 {
+    type Input = Vec<String>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        monkey_business(input, self.rounds).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        crate::day11_2::monkey_business(input, self.rounds_part2).into()
+    }
+}
+
 struct Monkey {
     name: String,
-    items: Vec<f32>,
-    op: Box<dyn Fn(f32) -> f32>,
-    test: Box<dyn Fn(f32) -> String>,
+    items: Vec<u128>,
+    op: Box<dyn Fn(u128) -> u128>,
+    test: Box<dyn Fn(u128) -> String>,
     counter: usize,
 }
 
 impl Monkey {
     fn new(
         name: String,
-        items: Vec<f32>,
-        op: Box<dyn Fn(f32) -> f32>,
-        test: Box<dyn Fn(f32) -> String>,
+        items: Vec<u128>,
+        op: Box<dyn Fn(u128) -> u128>,
+        test: Box<dyn Fn(u128) -> String>,
     ) -> Monkey {
-        let len = items.len();
         Monkey {
             name,
             items,
@@ -90,16 +237,15 @@ impl Monkey {
 
     fn new_from_figures(
         name: String,
-        items: Vec<f32>,
-        div_by: f32,
+        items: Vec<u128>,
+        div_by: u128,
         op: String,
         op_value: String,
         monkey_id_true: String,
         monkey_id_false: String,
     ) -> Monkey {
-        let len = items.len();
-        let test = Box::new(move |i: f32| {
-            if i.round() % div_by == 0.0 {
+        let test = Box::new(move |i: u128| {
+            if i.is_multiple_of(div_by) {
                 monkey_id_true.clone()
             } else {
                 monkey_id_false.clone()
@@ -111,54 +257,49 @@ impl Monkey {
             let value = if op_value == "old" {
                 i
             } else {
-                op_value.parse::<f32>().unwrap()
+                op_value.parse::<u128>().unwrap()
             };
 
             match op_colone.as_str() {
-                "+" => (i + value).round(),
-                "-" => (i - value).round(),
-                "*" => (i * value).round(),
-                "/" => (i / value).round(),
+                "+" => i + value,
+                "-" => i - value,
+                "*" => i * value,
+                "/" => i / value,
                 _ => panic!("Unknown op"),
             }
         });
-        Monkey {
-            name,
-            items,
-            op,
-            test,
-            counter: 0,
-        }
+
+        Monkey::new(name, items, op, test)
     }
 
-    fn process_items(&self) -> Vec<(f32, String)> {
+    fn process_items(&self) -> Vec<(u128, String)> {
         let items = &self.items;
         let result: Vec<_> = items
             .iter()
             .map(|i| {
-                let wl = ((self.op)(*i) / 3.0).floor();
+                let wl = (self.op)(*i) / 3;
                 let next = (self.test)(wl);
                 (wl, next)
             })
             .collect();
 
-        // println!("{}: {:?}", self.name, result);
+        trace!(Trace, "inspected", monkey = self.name, items = result);
         result
     }
 
-    fn throw_items(&mut self) -> Vec<(f32, String)> {
+    fn throw_items(&mut self) -> Vec<(u128, String)> {
         let result = self.process_items();
         self.items = vec![];
         self.counter += result.len();
         result
     }
 
-    fn get_item(&mut self, item: f32) {
+    fn get_item(&mut self, item: u128) {
         self.items.push(item);
     }
 }
 
-fn monkey_loop<'a>(monkeys: HashMap<String, Monkey>, rounds: usize) -> usize {
+fn monkey_loop(monkeys: HashMap<String, Monkey>, rounds: usize) -> usize {
     let mut monkeys = monkeys;
     let mut keys = monkeys
         .keys()
@@ -167,28 +308,26 @@ fn monkey_loop<'a>(monkeys: HashMap<String, Monkey>, rounds: usize) -> usize {
 
     keys.sort();
 
-    for i in 0..rounds {
-        let mut result = vec![];
+    for round in 0..rounds {
         for k in keys.iter() {
-            result = monkeys
-                .get_mut(&String::from(format!("{}", k)))
-                .unwrap()
-                .throw_items();
+            let result = monkeys.get_mut(&format!("{}", k)).unwrap().throw_items();
             for (item, next) in result {
                 monkeys.get_mut(&next).unwrap().get_item(item);
             }
         }
 
-        // for k in &keys {
-        //     println!(
-        //         "round ---> {} {}",
-        //         i,
-        //         monkeys.get_mut(&String::from(format!("{}", k))).unwrap()
-        //     );
-        // }
+        trace!(
+            Debug,
+            "round",
+            round = round + 1,
+            counters = keys
+                .iter()
+                .map(|k| monkeys[&k.to_string()].counter)
+                .collect::<Vec<_>>()
+        );
     }
 
-    let mut two_most_active: Vec<_> = monkeys.iter().map(|(_, monkey)| monkey.counter).collect();
+    let mut two_most_active: Vec<_> = monkeys.values().map(|monkey| monkey.counter).collect();
     // two_most_active.iter().sort();
     two_most_active.sort();
     two_most_active.reverse();
@@ -213,10 +352,10 @@ mod tests {
     fn test_monkey() {
         let mut monkey = Monkey::new(
             String::from("monkey 1"),
-            vec![1.0, 2.0, 3.0],
-            Box::new(|i| i * 3.0),
+            vec![1, 2, 3],
+            Box::new(|i| i * 3),
             Box::new(|i| {
-                if (i as i32) % 2 == 0 {
+                if i % 2 == 0 {
                     String::from("monkey 3")
                 } else {
                     String::from("monkey 4")
@@ -228,9 +367,9 @@ mod tests {
         assert_eq!(
             result,
             vec![
-                (1.0, String::from("monkey 4")),
-                (2.0, String::from("monkey 3")),
-                (3.0, String::from("monkey 4"))
+                (1, String::from("monkey 4")),
+                (2, String::from("monkey 3")),
+                (3, String::from("monkey 4"))
             ]
         );
 
@@ -242,10 +381,10 @@ mod tests {
     fn test_monkey_get_item() {
         let mut monkey = Monkey::new(
             String::from("monkey 1"),
-            vec![1.0, 2.0, 3.0],
-            Box::new(|i| i * 3.0),
+            vec![1, 2, 3],
+            Box::new(|i| i * 3),
             Box::new(|i| {
-                if (i as i32) % 2 == 0 {
+                if i % 2 == 0 {
                     String::from("monkey 3")
                 } else {
                     String::from("monkey 4")
@@ -253,14 +392,45 @@ mod tests {
             }),
         );
 
-        monkey.get_item(30.0);
-        assert_eq!(monkey.items, vec![1.0, 2.0, 3.0, 30.0]);
+        monkey.get_item(30);
+        assert_eq!(monkey.items, vec![1, 2, 3, 30]);
         assert_eq!(monkey.counter, 0);
     }
 
     #[test]
     fn test_monkeys_to_stirng() {
-        let result = monkeys_to_string("src/specs/day11", 20);
+        let result = monkeys_to_string("src/specs/day11", 20).unwrap();
         assert_eq!(result, 10605);
     }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(
+            parse_str("").unwrap_err().to_string(),
+            "<input>:1:1: expected the notes of another monkey, found end of line"
+        );
+        let monkey = |n, to| {
+            format!(
+                "Monkey {}:\n  Starting items: 1\n  Operation: new = old * 2\n  Test: divisible by 2\n    If true: throw to monkey {}\n    If false: throw to monkey 0\n",
+                n, to
+            )
+        };
+        assert_eq!(
+            parse_str(&monkey(0, 0)).unwrap_err().to_string(),
+            "<input>:7:1: expected the notes of another monkey, found end of line"
+        );
+        assert_eq!(
+            parse_str(&format!("{}\n{}", monkey(0, 1), monkey(1, 2)))
+                .unwrap_err()
+                .to_string(),
+            "<input>:12:30: expected a monkey from the notes, found `2`"
+        );
+        assert!(parse_str(&format!("{}\n{}", monkey(0, 1), monkey(1, 0))).is_ok());
+        assert_eq!(
+            parse_str(&format!("{}\n{}", monkey(0, 1), monkey(1, 0)).replace("by 2", "by 0"))
+                .unwrap_err()
+                .to_string(),
+            "<input>:4:22: expected a divisor above 0, found `0`"
+        );
+    }
 }
@@ -1,38 +1,20 @@
+use crate::day11::{parse_input, parse_str, MONKEY};
+use crate::error::ParseResult;
+use crate::solution::{Answer, FromParams, Params, Solution};
+use crate::trace;
 use regex::Regex;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
 
-pub fn parse_input(path: &str) -> Vec<String> {
-    let file = File::open(path).expect("Unable to open file");
-    let mut monkey_strings = vec![String::new()];
+type MonkeyData = (String, Vec<i32>, Op, i32, String, String);
 
-    for i in io::BufReader::new(file).lines() {
-        let line = i.unwrap();
-
-        if line.len() < 3 {
-            monkey_strings.push(String::new());
-        } else {
-            let result = monkey_strings.pop().unwrap();
-
-            monkey_strings.push(format!("{}{}", result, line));
-        }
-    }
-
-    monkey_strings
-}
-
-pub fn monkeys_to_string(path: &str, rounds: usize) -> usize {
-    let input = parse_input(path);
-    let mut parsed_monkey_data: Vec<(String, Vec<i32>, Op, i32, String, String)> = vec![];
+pub fn monkey_business(input: &[String], rounds: usize) -> usize {
+    let mut parsed_monkey_data: Vec<MonkeyData> = vec![];
     let mut monkeys: HashMap<String, Monkey> = HashMap::new();
 
-    let regexp =
-        Regex::new(r"Monkey (\d+):.*Starting items:(.*)Operation:.*new = old (.) (\d+|old).*Test: divisible by (\d+).*If true: throw to monkey (\d+).*If false: throw to monkey (\d+)")
-            .unwrap();
+    let regexp = Regex::new(MONKEY).unwrap();
 
     for monkey_s in input {
-        let captures = regexp.captures(&monkey_s).unwrap();
+        let captures = regexp.captures(monkey_s).unwrap();
         let monkey_id = captures.get(1).unwrap().as_str();
         let items = captures
             .get(2)
@@ -75,18 +57,15 @@ pub fn monkeys_to_string(path: &str, rounds: usize) -> usize {
         .collect::<Vec<_>>();
 
     for (monkey_id, items, op, div_by, true_monkey, false_monkey) in parsed_monkey_data {
-        let monkey = Monkey {
-            name: monkey_id.clone(),
-            items: items
-                .iter()
-                .map(|i| Reminders::new(*i, divisors.clone()))
-                .collect(),
+        let monkey = Monkey::new(
+            monkey_id.clone(),
+            items,
             op,
-            divisor: div_by,
-            counter: 0,
+            div_by,
+            &divisors,
             true_monkey,
             false_monkey,
-        };
+        );
 
         monkeys.insert(monkey_id, monkey);
     }
@@ -94,6 +73,46 @@ pub fn monkeys_to_string(path: &str, rounds: usize) -> usize {
     monkey_loop(monkeys, rounds)
 }
 
+pub fn monkeys_to_string(path: &str, rounds: usize) -> ParseResult<usize> {
+    Ok(monkey_business(&parse_input(path)?, rounds))
+}
+
+// Only knows how to play without the relief division, so part one is left to
+// day11.
+pub struct Day11Part2 {
+    pub rounds: usize,
+}
+
+impl Default for Day11Part2 {
+    fn default() -> Self {
+        Day11Part2 { rounds: 10000 }
+    }
+}
+
+impl FromParams for Day11Part2 {
+    fn from_params(params: &Params) -> Result<Self, String> {
+        Ok(Day11Part2 {
+            rounds: params.get("rounds", 10000)?,
+        })
+    }
+}
+
+impl Solution for Day11Part2 {
+    type Input = Vec<String>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, _input: &Self::Input) -> Answer {
+        Answer::Unsolved
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        monkey_business(input, self.rounds).into()
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Reminder {
     value: i32,
@@ -191,17 +210,15 @@ impl Monkey {
         items: Vec<i32>,
         op: Op,
         divisor: i32,
-        divisors: Vec<i32>,
-        counter: usize,
+        divisors: &[i32],
         true_monkey: String,
         false_monkey: String,
     ) -> Monkey {
-        let len = items.len();
         Monkey {
             name,
             items: items
                 .iter()
-                .map(|i| Reminders::new(*i, divisors.clone()))
+                .map(|i| Reminders::new(*i, divisors.to_vec()))
                 .collect(),
             op,
             divisor,
@@ -239,7 +256,7 @@ impl Monkey {
     }
 }
 
-fn monkey_loop<'a>(monkeys: HashMap<String, Monkey>, rounds: usize) -> usize {
+fn monkey_loop(monkeys: HashMap<String, Monkey>, rounds: usize) -> usize {
     let mut monkeys = monkeys;
     let mut keys = monkeys
         .keys()
@@ -248,28 +265,26 @@ fn monkey_loop<'a>(monkeys: HashMap<String, Monkey>, rounds: usize) -> usize {
 
     keys.sort();
 
-    for i in 0..rounds {
-        let mut result = vec![];
+    for round in 0..rounds {
         for k in keys.iter() {
-            result = monkeys
-                .get_mut(&String::from(format!("{}", k)))
-                .unwrap()
-                .throw_items();
+            let result = monkeys.get_mut(&format!("{}", k)).unwrap().throw_items();
             for (next, item) in result {
                 monkeys.get_mut(&next).unwrap().get_item(item);
             }
         }
 
-        // for k in &keys {
-        //     println!(
-        //         "round ---> {} {}",
-        //         i,
-        //         monkeys.get_mut(&String::from(format!("{}", k))).unwrap()
-        //     );
-        // }
+        trace!(
+            Debug,
+            "round",
+            round = round + 1,
+            counters = keys
+                .iter()
+                .map(|k| monkeys[&k.to_string()].counter)
+                .collect::<Vec<_>>()
+        );
     }
 
-    let mut two_most_active: Vec<_> = monkeys.iter().map(|(_, monkey)| monkey.counter).collect();
+    let mut two_most_active: Vec<_> = monkeys.values().map(|monkey| monkey.counter).collect();
     // two_most_active.iter().sort();
     two_most_active.sort();
     two_most_active.reverse();
@@ -312,7 +327,12 @@ mod tests {
 
     #[test]
     fn test_monkeys_to_stirng() {
-        let result = monkeys_to_string("src/specs/day11", 10000);
+        let result = monkeys_to_string("src/specs/day11", 10000).unwrap();
         assert_eq!(result, 2713310158);
     }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(Day11Part2::default().parse_str("").is_err());
+    }
 }
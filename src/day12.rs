@@ -1,18 +1,9 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{self, BufRead};
-
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
-pub struct GridCell {
-    x: usize,
-    y: usize,
-}
-
-impl GridCell {
-    fn new(x: usize, y: usize) -> GridCell {
-        GridCell { x, y }
-    }
-}
+use crate::error::{from_path, ParseError, ParseResult};
+use crate::geometry::Point;
+use crate::grid::Grid;
+use crate::solution::{Answer, Solution};
+use crate::trace;
+use std::collections::HashMap;
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub struct GridCellData {
@@ -31,239 +22,210 @@ impl GridCellData {
 
 static BIIIG: usize = 1000;
 
-pub fn parse_input(
-    path: &str,
-) -> (
-    HashMap<GridCell, GridCellData>,
-    GridCell,
-    GridCell,
-    usize,
-    usize,
-    Vec<GridCell>,
-) {
-    let file = File::open(path).expect("Unable to open file");
+// cells, start, end and every cell at the lowest elevation
+pub type HeightMap = (Grid<GridCellData>, Point, Point, Vec<Point>);
+
+pub fn parse_str(input: &str) -> ParseResult<HeightMap> {
     let mut elevations = ('a'..='z').zip(1..).collect::<HashMap<_, _>>();
-    let mut vertices: HashMap<GridCell, GridCellData> = HashMap::new();
-    let mut from = GridCell::new(0, 0);
-    let mut to = GridCell::new(0, 0);
+    let mut from = None;
+    let mut to = None;
     elevations.insert('E', *elevations.get(&'z').unwrap());
     elevations.insert('S', *elevations.get(&'a').unwrap());
-    let mut cols = 0;
-    let mut rows = 0;
     let mut starts = Vec::new();
 
-    for (row, line) in io::BufReader::new(file).lines().enumerate() {
-        if row + 1 >= row {
-            rows = row + 1;
+    let vertices = Grid::parse(input, "a height from a to z, S or E", |pos, c| {
+        if c == 'S' {
+            from = Some(pos);
+            starts.push(pos);
         }
-        for (col, c) in line.unwrap().chars().enumerate() {
-            if col + 1 >= cols {
-                cols = col + 1;
-            }
-
-            vertices.insert(
-                GridCell::new(col, row),
-                GridCellData {
-                    elevation: *elevations.get(&c).unwrap(),
-                    distance: BIIIG,
-                },
-            );
-
-            if c == 'S' {
-                from = GridCell::new(col, row);
-                starts.push(GridCell::new(col, row));
-            }
-
-            if c == 'E' {
-                to = GridCell::new(col, row);
-            }
 
-            if c == 'a' {
-                starts.push(GridCell::new(col, row));
-            }
+        if c == 'E' {
+            to = Some(pos);
         }
-    }
-
-    (vertices, from, to, cols, rows, starts)
-}
 
-pub fn get_reachable_cells(
-    from: GridCell,
-    grid: &HashMap<GridCell, GridCellData>,
-    elevation: usize,
-    width: usize,
-    height: usize,
-) -> Vec<(GridCell, GridCellData)> {
-    let mut reachable_cells: Vec<GridCell> = Vec::with_capacity(4);
+        if c == 'a' {
+            starts.push(pos);
+        }
 
-    if from.x > 0 {
-        reachable_cells.push(GridCell::new(from.x - 1, from.y));
-    }
+        let elevation = *elevations.get(&c)?;
+        Some(GridCellData::new(elevation, BIIIG))
+    })?
+    .into_sparse();
 
-    if from.x < width - 1 {
-        reachable_cells.push(GridCell::new(from.x + 1, from.y));
-    }
+    let rows = vertices.bounds().map_or(0, |b| b.height());
+    let missing = |expected| ParseError::syntax(rows as usize + 1, 1, expected, "");
+    let from = from.ok_or_else(|| missing("a start marked S"))?;
+    let to = to.ok_or_else(|| missing("an end marked E"))?;
 
-    if from.y > 0 {
-        reachable_cells.push(GridCell::new(from.x, from.y - 1));
-    }
+    Ok((vertices, from, to, starts))
+}
 
-    if from.y < height - 1 {
-        reachable_cells.push(GridCell::new(from.x, from.y + 1));
-    }
+pub fn parse_input(path: &str) -> ParseResult<HeightMap> {
+    from_path(path, parse_str)
+}
 
-    reachable_cells
-        .iter()
-        .flat_map(|cell| {
-            grid.get(&cell).and_then(|cell_data| {
-                if cell_data.elevation <= elevation + 1 {
-                    Some((*cell, *cell_data))
-                } else {
-                    None
-                }
-            })
-        })
+pub fn get_reachable_cells(
+    from: Point,
+    grid: &Grid<GridCellData>,
+    elevation: usize,
+) -> Vec<(Point, GridCellData)> {
+    grid.neighbours4(from)
+        .filter(|(_, cell_data)| cell_data.elevation <= elevation + 1)
+        .map(|(cell, cell_data)| (cell, *cell_data))
         .collect()
 }
 
-pub fn relax(
-    cell: GridCell,
-    grid: &mut HashMap<GridCell, GridCellData>,
-    width: usize,
-    height: usize,
-) -> (GridCell, GridCellData) {
-    let data = grid.remove(&cell).unwrap();
+pub fn relax(cell: Point, grid: &mut Grid<GridCellData>) -> (Point, GridCellData) {
+    let data = grid.remove(cell).unwrap();
 
-    let reachable_cells = get_reachable_cells(cell, grid, data.elevation, width, height);
-    // println!(
-    //     "reachable cells {:#?} dist: {} for cell {:#?}",
-    //     reachable_cells, data.distance, cell
-    // );
+    let reachable_cells = get_reachable_cells(cell, grid, data.elevation);
+    trace!(
+        Trace,
+        "relax",
+        cell = cell,
+        distance = data.distance,
+        reachable = reachable_cells
+    );
 
     for (reachable_cell, reachable_data) in reachable_cells {
         let distance = data.distance + 1;
         if distance < reachable_data.distance {
-            let new_data = GridCellData {
-                elevation: reachable_data.elevation,
-                distance,
-            };
+            let new_data = GridCellData::new(reachable_data.elevation, distance);
             grid.insert(reachable_cell, new_data);
 
-            // println!("updating cell --> {:#?} {:#?}", reachable_cell, new_data);
+            trace!(Trace, "updating", cell = reachable_cell, data = new_data);
         }
     }
 
     (cell, data)
 }
 
-pub fn extract_min(grid: &mut HashMap<GridCell, GridCellData>) -> Option<(GridCell, GridCellData)> {
-    let item = grid
-        .iter()
+pub fn extract_min(grid: &mut Grid<GridCellData>) -> Option<(Point, GridCellData)> {
+    grid.iter()
         .min_by(|(_, a), (_, b)| a.distance.cmp(&b.distance))
-        .map(|(cell, data)| (*cell, *data));
-
-    match item {
-        Some((cell, data)) => Some((cell, data)),
-        None => None,
-    }
+        .map(|(cell, data)| (cell, *data))
 }
 
-pub fn get_path(path: &str) -> usize {
-    let (mut input, from, to, cols, rows, _) = parse_input(path);
-    // println!("{:#?} {:#?}", cols, rows);
-    let el = input.get_mut(&from).unwrap();
+pub fn shortest_path(height_map: &HeightMap) -> usize {
+    let (input, from, to, _) = height_map;
+    let mut input = input.clone();
+    let el = input.get_mut(*from).unwrap();
     el.distance = 0;
 
-    // println!("{:?} {:?}", from, to);
+    trace!(Debug, "searching", from = from, to = to);
 
     loop {
         let (cell, data) = extract_min(&mut input).unwrap();
-        // println!("{:?} {:?}", cell, data);
-        if cell == to {
+        trace!(Trace, "visiting", cell = cell, distance = data.distance);
+        if cell == *to {
             return data.distance;
         }
 
-        relax(cell, &mut input, cols, rows);
+        relax(cell, &mut input);
     }
-
-    BIIIG
 }
 
 // its better to revert requirements and start from the end :)
 // but I'm too lazy to do it
 
-pub fn get_path_part2(path: &str) -> usize {
-    let (mut input, from, to, cols, rows, starts) = parse_input(path);
-    // println!("{:#?} {:#?}", cols, rows);
-
-    // println!("{:?} {:?}", from, to);
+pub fn shortest_path_from_any(height_map: &HeightMap) -> usize {
+    let (input, _, to, starts) = height_map;
     let mut distances: Vec<usize> = Vec::with_capacity(starts.len());
-    let mut i = 0;
-    let len = starts.len();
 
     for start in starts {
-        i += 1;
-        println!("{i} of {}", len);
         let mut input = input.clone();
-        let el = input.get_mut(&start).unwrap();
+        let el = input.get_mut(*start).unwrap();
         el.distance = 0;
 
         loop {
             let (cell, data) = extract_min(&mut input).unwrap();
-            // println!("{:?} {:?}", cell, data);
-            if cell == to {
+            trace!(Trace, "visiting", cell = cell, distance = data.distance);
+            if cell == *to {
+                trace!(
+                    Debug,
+                    "reached end",
+                    start = start,
+                    distance = data.distance
+                );
                 distances.push(data.distance);
                 break;
             }
 
-            relax(cell, &mut input, cols, rows);
+            relax(cell, &mut input);
         }
     }
 
-    distances.iter().min().unwrap().clone()
+    *distances.iter().min().unwrap()
+}
+
+pub fn get_path(path: &str) -> ParseResult<usize> {
+    Ok(shortest_path(&parse_input(path)?))
+}
+
+pub fn get_path_part2(path: &str) -> ParseResult<usize> {
+    Ok(shortest_path_from_any(&parse_input(path)?))
+}
+
+#[derive(Default)]
+pub struct Day12;
+
+impl Solution for Day12 {
+    type Input = HeightMap;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        shortest_path(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        shortest_path_from_any(input).into()
+    }
 }
 
 #[cfg(test)]
 #[test]
 fn test_reachable_cells() {
-    let input = parse_input("src/specs/day12");
-    let from = GridCell::new(2, 3);
-    let elevation = input.0.get(&from).unwrap().elevation;
-    let reachable_cells = get_reachable_cells(from, &input.0, elevation, input.3, input.4);
+    let input = parse_input("src/specs/day12").unwrap();
+    let from = Point::new(2, 3);
+    let elevation = input.0.get(from).unwrap().elevation;
+    let reachable_cells = get_reachable_cells(from, &input.0, elevation);
     assert_eq!(
         reachable_cells,
         vec![
-            (GridCell::new(1, 3), GridCellData::new(3, BIIIG)),
-            (GridCell::new(2, 2), GridCellData::new(3, BIIIG)),
-            (GridCell::new(2, 4), GridCellData::new(4, BIIIG)),
+            (Point::new(1, 3), GridCellData::new(3, BIIIG)),
+            (Point::new(2, 2), GridCellData::new(3, BIIIG)),
+            (Point::new(2, 4), GridCellData::new(4, BIIIG)),
         ]
     );
 }
 
 #[test]
 fn test_relax() {
-    let mut input = parse_input("src/specs/day12");
-    let from = GridCell::new(0, 0);
+    let mut input = parse_input("src/specs/day12").unwrap();
+    let from = Point::new(0, 0);
     input.0.insert(from, GridCellData::new(19, 0));
-    let (cell, data) = relax(from, &mut input.0, input.3, input.4);
+    let (cell, data) = relax(from, &mut input.0);
 
     assert_eq!(cell, from);
     assert_eq!(data, GridCellData::new(19, 0));
     assert_eq!(
-        input.0.get(&GridCell::new(1, 0)),
+        input.0.get(Point::new(1, 0)),
         Some(&GridCellData::new(1, 1))
     );
 
     assert_eq!(
-        input.0.get(&GridCell::new(0, 1)),
+        input.0.get(Point::new(0, 1)),
         Some(&GridCellData::new(1, 1))
     );
 }
 
 #[test]
 fn test_extract_min() {
-    let mut input = parse_input("src/specs/day12");
-    let from = GridCell::new(0, 0);
+    let mut input = parse_input("src/specs/day12").unwrap();
+    let from = Point::new(0, 0);
     input.0.insert(from, GridCellData::new(19, 0));
     let result = extract_min(&mut input.0).unwrap();
 
@@ -272,12 +234,12 @@ fn test_extract_min() {
 
 #[test]
 fn test_get_path() {
-    let result = get_path("src/specs/day12");
+    let result = get_path("src/specs/day12").unwrap();
     assert_eq!(result, 31);
 }
 
 #[test]
 fn test_get_path_2() {
-    let result = get_path_part2("src/specs/day12");
+    let result = get_path_part2("src/specs/day12").unwrap();
     assert_eq!(result, 29);
 }
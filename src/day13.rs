@@ -1,70 +1,75 @@
+use crate::error::{from_path, Line, ParseResult};
+use crate::input::paragraphs;
+use crate::json::Json;
+use crate::solution::{Answer, Details, Part, Solution};
 use std::cmp::Ordering;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-enum RecList {
-    List(Vec<Box<RecList>>),
+pub enum RecList {
+    List(Vec<RecList>),
     Value(i32),
 }
 
 impl RecList {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self::List(Vec::new())
     }
 
-    fn push(&mut self, item: Self) {
+    pub fn push(&mut self, item: Self) {
         match self {
-            Self::List(l) => l.push(Box::new(item)),
+            Self::List(l) => l.push(item),
             Self::Value(_) => panic!("Cannot push to a value"),
         }
     }
 
-    fn get(&self, index: usize) -> &Self {
+    pub fn get(&self, index: usize) -> &Self {
         match self {
             Self::List(l) => &l[index],
             Self::Value(_) => self,
         }
     }
 
-    fn get_mut(&mut self, index: usize) -> &mut Self {
+    pub fn get_mut(&mut self, index: usize) -> &mut Self {
         match self {
             Self::List(l) => &mut l[index],
             Self::Value(_) => self,
         }
     }
 
-    fn from(i: i32) -> Self {
+    pub fn from(i: i32) -> Self {
         Self::Value(i)
     }
 
-    fn from_vec(vec: Vec<i32>) -> Self {
-        Self::List(vec.iter().map(|i| Box::new(Self::from(*i))).collect())
+    pub fn from_vec(vec: Vec<i32>) -> Self {
+        Self::List(vec.iter().map(|i| Self::from(*i)).collect())
     }
 
-    fn len(&self) -> usize {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
         match self {
             Self::List(l) => l.len(),
             Self::Value(_) => 1,
         }
     }
 
-    fn to_list(val: RecList) -> Self {
+    pub fn to_list(val: RecList) -> Self {
         match val {
             Self::List(_) => val,
-            Self::Value(val) => Self::List(vec![Box::new(Self::Value(val))]),
+            Self::Value(val) => Self::List(vec![Self::Value(val)]),
         }
     }
 
-    fn from_string(source: &str) -> Self {
+    pub fn from_string(source: &str) -> Self {
         let mut stack: Vec<RecList> = Vec::with_capacity(source.len());
         let mut sub_str: Vec<String> = Vec::new();
         for i in source.chars() {
             match i {
                 '[' => {
                     for el in &sub_str {
-                        if el == "" {
+                        if el.is_empty() {
                             continue;
                         }
                         let val = el.parse::<i32>().unwrap();
@@ -105,6 +110,12 @@ impl RecList {
     }
 }
 
+impl Default for RecList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl std::fmt::Display for RecList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -127,117 +138,127 @@ impl std::fmt::Display for RecList {
 
 impl PartialOrd for RecList {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let mut deep = String::from(" ");
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RecList {
+    fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Self::Value(a), Self::Value(b)) => a.partial_cmp(b),
+            (Self::Value(a), Self::Value(b)) => a.cmp(b),
             (a, b) => {
-                deep.push(' ');
                 let list_a = Self::to_list(a.clone());
                 let list_b = Self::to_list(b.clone());
                 let l1 = list_a.len();
                 let l2 = list_b.len();
 
-                let mut i = 0;
-                let mut results: Vec<Option<Ordering>> = vec![];
-                for i in 0..*([l1, l2]).iter().min().unwrap() as usize {
+                let mut results: Vec<Ordering> = vec![];
+                for i in 0..l1.min(l2) {
                     let a = list_a.get(i);
                     let b = list_b.get(i);
-                    let la = list_a.len();
-                    let lb = list_b.len();
 
-                    match a.partial_cmp(b) {
-                        Some(Ordering::Less) => {
-                            results.push(Some(Ordering::Less));
-                            break;
-                        }
-                        Some(Ordering::Equal) => {
-                            results.push(Some(Ordering::Equal));
+                    match a.cmp(b) {
+                        Ordering::Equal => {
+                            results.push(Ordering::Equal);
                             continue;
                         }
-                        Some(Ordering::Greater) => {
-                            results.push(Some(Ordering::Greater));
+                        ordering => {
+                            results.push(ordering);
                             break;
                         }
-                        None => {
-                            results.push(Some(Ordering::Equal));
-                            continue;
-                        }
                     }
                 }
 
-                let is_gt = results.iter().any(|r| match r {
-                    Some(Ordering::Greater) => true,
-                    _ => false,
-                });
-
-                let is_lt = results.iter().any(|r| match r {
-                    Some(Ordering::Less) => true,
-                    _ => false,
-                });
+                let is_gt = results.iter().any(|r| r == &Ordering::Greater);
+                let is_lt = results.iter().any(|r| r == &Ordering::Less);
 
                 if is_gt {
-                    return Some(Ordering::Greater);
+                    return Ordering::Greater;
                 }
 
                 if is_lt {
-                    return Some(Ordering::Less);
+                    return Ordering::Less;
                 }
 
-                if l1 > l2 {
-                    Some(Ordering::Greater)
-                } else if l1 < l2 {
-                    Some(Ordering::Less)
-                } else {
-                    Some(Ordering::Equal)
-                }
+                l1.cmp(&l2)
             }
         }
     }
 }
 
-impl Ord for RecList {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match self.partial_cmp(other) {
-            Some(Ordering::Less) => Ordering::Less,
-            Some(Ordering::Equal) => Ordering::Equal,
-            Some(Ordering::Greater) => Ordering::Greater,
-            None => Ordering::Equal,
+// Packets are compared recursively, deeper ones are refused.
+const MAX_DEPTH: usize = 100;
+
+// `from_string` trusts its input, so packets are checked for stray
+// characters, unbalanced brackets and nesting while reading.
+fn check_packet(line: &Line) -> ParseResult<()> {
+    let mut depth = 0;
+
+    for (i, c) in line.text.char_indices() {
+        let rest = &line.text[i..];
+        match c {
+            '[' if depth == MAX_DEPTH => {
+                return Err(line.error(&rest[..1], "a list at most 100 deep"))
+            }
+            '[' if i == 0 || depth > 0 => depth += 1,
+            ']' if depth > 0 => depth -= 1,
+            ',' | '0'..='9' if depth > 0 => {}
+            _ if depth == 0 => return Err(line.error(rest, "a packet starting with [")),
+            _ => return Err(line.error(rest, "a number, a list or ]")),
         }
     }
+
+    if depth > 0 {
+        return Err(line.error(&line.text[line.text.len()..], "]"));
+    }
+
+    Ok(())
 }
 
-pub fn parse_input(path: &str) -> (usize, usize) {
-    let file = File::open(path).expect("Unable to open file");
-
-    let mut contents = io::BufReader::new(file)
-        .lines()
-        .filter_map(|s| match s {
-            Ok(s) => {
-                if s.len() < 2 {
-                    None
-                } else {
-                    Some(s)
-                }
+// Packets come in pairs, each pair a paragraph of its own.
+pub fn parse_str(input: &str) -> ParseResult<Vec<String>> {
+    let mut packets = vec![];
+
+    for pair in paragraphs(input) {
+        for line in pair.iter() {
+            check_packet(line)?;
+        }
+        match pair[..] {
+            [first, second] => packets.extend([first, second].map(|l| l.text.to_string())),
+            [only] => {
+                return Err(only.error(&only.text[only.text.len()..], "a pair for this packet"))
             }
-            Err(_) => None,
-        })
-        .collect::<Vec<String>>();
+            [_, _, third, ..] => return Err(third.error(third.text, "a blank line between pairs")),
+            [] => unreachable!("paragraphs are never empty"),
+        }
+    }
+
+    Ok(packets)
+}
 
-    let right_order: &usize = &mut contents
+pub fn read_packets(path: &str) -> ParseResult<Vec<String>> {
+    from_path(path, parse_str)
+}
+
+pub fn right_order_sum(contents: &[String]) -> usize {
+    contents
         .chunks(2)
         .enumerate()
         .map(|(i, chunk)| {
             let a = RecList::from_string(&chunk[0]);
             let b = RecList::from_string(&chunk[1]);
 
-            if let Some(Ordering::Less) = a.partial_cmp(&b) {
+            if a < b {
                 return i + 1;
             }
 
             0
         })
-        .sum();
+        .sum()
+}
 
+// One based positions of [[2]] and [[6]] once every packet is sorted.
+pub fn divider_indices(contents: &[String]) -> (usize, usize) {
     let sorted = &mut contents
         .iter()
         .map(|s| RecList::from_string(s))
@@ -254,7 +275,46 @@ pub fn parse_input(path: &str) -> (usize, usize) {
     let i1 = sorted.iter().position(|s| s == &marker1).unwrap();
     let i2 = sorted.iter().position(|s| s == &marker2).unwrap();
 
-    (*right_order, (i1 + 1) * (i2 + 1))
+    (i1 + 1, i2 + 1)
+}
+
+pub fn decoder_key(contents: &[String]) -> usize {
+    let (i1, i2) = divider_indices(contents);
+    i1 * i2
+}
+
+pub fn parse_input(path: &str) -> ParseResult<(usize, usize)> {
+    let contents = read_packets(path)?;
+    Ok((right_order_sum(&contents), decoder_key(&contents)))
+}
+
+#[derive(Default)]
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = Vec<String>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        right_order_sum(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        decoder_key(input).into()
+    }
+
+    fn details(&self, input: &Self::Input, part: Part) -> Details {
+        match part {
+            Part::One => vec![],
+            Part::Two => {
+                let (i1, i2) = divider_indices(input);
+                vec![("dividers", Json::from(vec![i1, i2]))]
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -387,13 +447,38 @@ mod tests {
 
     #[test]
     fn test_parse_input() {
-        let input = parse_input("src/specs/day13");
+        let input = parse_input("src/specs/day13").unwrap();
         assert_eq!(input.0, 13);
     }
 
     #[test]
     fn test_parse_input_2() {
-        let input = parse_input("src/specs/day13");
+        let input = parse_input("src/specs/day13").unwrap();
         assert_eq!(input.1, 140);
     }
+
+    #[test]
+    fn test_pairs() {
+        assert_eq!(parse_str("[1]\n[2]\n\n\n[3]\n[]\n").unwrap().len(), 4);
+        assert_eq!(
+            parse_str("[1]\n[2]\n\n[3]\n").unwrap_err().to_string(),
+            "<input>:4:4: expected a pair for this packet, found end of line"
+        );
+        assert_eq!(
+            parse_str("[1]\n[2]\n[3]\n[4]\n").unwrap_err().to_string(),
+            "<input>:3:1: expected a blank line between pairs, found `[3]`"
+        );
+    }
+
+    #[test]
+    fn test_depth() {
+        let packet = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse_str(&format!("{}\n[]\n", packet(MAX_DEPTH))).is_ok());
+        assert_eq!(
+            parse_str(&format!("[]\n{}\n", packet(100_000)))
+                .unwrap_err()
+                .to_string(),
+            "<input>:2:101: expected a list at most 100 deep, found `[`"
+        );
+    }
 }
@@ -1,10 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use crate::error::{from_path, ParseResult};
+use crate::frames::{Animate, Frame, FrameSink};
+use crate::geometry::Point;
+use crate::grid::Grid;
+use crate::input::lines;
+use crate::simulation::{Simulate, Simulation};
+use crate::solution::{Answer, Part, Solution};
 use std::fmt::{Display, Error, Formatter};
-use std::fs::{read_to_string, File};
-use std::io::{self, BufRead};
+use std::io;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-enum Cell {
+pub enum Cell {
     Sand,
     Rock,
     Empty,
@@ -24,91 +29,44 @@ impl Display for Cell {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
+// Everything outside the bounds of the rocks and the source is abyss.
 #[derive(Debug, Clone)]
-struct SandGrid {
-    grid: HashMap<Point, Cell>,
-    start: i32,
-    end: i32,
-    height: i32,
+pub struct SandGrid {
+    grid: Grid<Cell>,
     source: Point,
 }
 
 impl SandGrid {
-    fn new() -> SandGrid {
+    pub fn new() -> SandGrid {
         let source = Point { x: 500, y: 0 };
         let mut grid = SandGrid {
-            grid: HashMap::new(),
-            start: source.x,
-            end: source.x,
-            height: source.y,
+            grid: Grid::sparse(),
             source,
         };
 
-        grid.set(grid.source, Cell::Source);
-        grid.update_dims(grid.source);
+        grid.grid.insert(source, Cell::Source);
         grid
     }
 
-    fn update_dims(&mut self, point: Point) {
-        let Point { x, y } = point;
-        if y > self.height {
-            self.height = y;
-        }
-
-        if x < self.start {
-            self.start = x;
-        }
-
-        if x > self.end {
-            self.end = x;
-        }
+    pub fn height(&self) -> i64 {
+        self.grid.bounds().map_or(0, |b| b.max.y)
     }
 
-    fn add_rock_path(&mut self, from: Point, to: Point) {
-        let Point { x: xf, y: yf } = from;
-        let Point { x: xt, y: yt } = to;
-
-        self.update_dims(from);
-        self.update_dims(to);
-
-        let delta_x = xt - xf;
-        let delta_y = yt - yf;
-
-        for j in 0..=delta_x.abs() {
-            self.set(
-                Point {
-                    x: xf + j * delta_x.signum(),
-                    y: yf,
-                },
-                Cell::Rock,
-            );
-        }
-
-        for i in 0..=delta_y.abs() {
-            self.set(
-                Point {
-                    y: yf + i * delta_y.signum(),
-                    x: xf,
-                },
-                Cell::Rock,
-            );
+    pub fn add_rock_path(&mut self, from: Point, to: Point) {
+        let step = (to - from).signum();
+        let mut point = from;
+        self.grid.insert(point, Cell::Rock);
+        while point != to {
+            point += step;
+            self.grid.insert(point, Cell::Rock);
         }
     }
 
-    fn drop_pebble(&mut self) -> (Point, Cell) {
+    pub fn drop_pebble(&mut self) -> (Point, Cell) {
         let mut current_point = self.source;
 
         loop {
-            let down_point = Point {
-                x: current_point.x,
-                y: current_point.y + 1,
-            };
+            let down_point = current_point + Point::new(0, 1);
 
             if self.get(down_point) == Cell::Empty {
                 current_point = down_point;
@@ -119,10 +77,7 @@ impl SandGrid {
                 return (current_point, Cell::Abyss);
             }
 
-            let left_point = Point {
-                x: current_point.x - 1,
-                y: current_point.y + 1,
-            };
+            let left_point = current_point + Point::new(-1, 1);
 
             if self.get(left_point) == Cell::Empty {
                 current_point = left_point;
@@ -133,10 +88,7 @@ impl SandGrid {
                 return (current_point, Cell::Abyss);
             }
 
-            let right_point = Point {
-                x: current_point.x + 1,
-                y: current_point.y + 1,
-            };
+            let right_point = current_point + Point::new(1, 1);
 
             if self.get(right_point) == Cell::Empty {
                 current_point = right_point;
@@ -152,7 +104,7 @@ impl SandGrid {
         }
     }
 
-    fn drop_sand(&mut self) -> usize {
+    pub fn drop_sand(&mut self) -> usize {
         let mut pebbles = 0;
         let mut drop = self.drop_pebble();
         while (drop.1 != Cell::Abyss) && !(drop.1 == Cell::Sand && drop.0 == self.source) {
@@ -164,95 +116,215 @@ impl SandGrid {
     }
 
     fn outside(&self, point: Point) -> bool {
-        let Point { x, y } = point;
-
-        x < self.start || x > self.end || y < 0 || y > self.height
+        !self.grid.bounds().is_some_and(|b| b.contains(point))
     }
 
-    fn get(&self, point: Point) -> Cell {
-        let Point { x, y } = point;
+    pub fn get(&self, point: Point) -> Cell {
         if self.outside(point) {
             return Cell::Abyss;
         }
 
-        *self
-            .grid
-            .get(&Point { x, y })
-            .or(Some(&Cell::Empty))
-            .unwrap()
+        *self.grid.get(point).unwrap_or(&Cell::Empty)
     }
 
-    fn set(&mut self, point: Point, cell: Cell) {
-        let Point { x, y } = point;
-
+    pub fn set(&mut self, point: Point, cell: Cell) {
         if self.outside(point) {
             return;
         }
 
-        self.grid.insert(Point { x, y }, cell);
+        self.grid.insert(point, cell);
+    }
+}
+
+impl Default for SandGrid {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Display for SandGrid {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        for y in 0..=self.height {
-            for x in self.start..=self.end {
-                write!(f, "{}", self.get(Point { x, y }))?;
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        write!(f, "{}", self.grid)
     }
 }
 
-pub fn parse_input(path: &str) -> (usize, usize) {
-    let file = File::open(path).expect("Unable to open file");
+pub fn parse_str(input: &str) -> ParseResult<SandGrid> {
     let mut grid = SandGrid::new();
-    io::BufReader::new(file).lines().for_each(|s| {
-        let str = s.unwrap();
-        let mut items = str.split(" -> ").collect::<Vec<_>>();
-        let first = items[0];
-        let last = items.pop().unwrap();
-
-        let middle = items[1..].iter().flat_map(|s| [s, s]).collect::<Vec<_>>();
-        let mut result = vec![first];
-        result.extend(middle);
-        result.push(last);
-        let it = result.chunks(2).for_each(|c| {
-            let p1 = c[0].split(",").collect::<Vec<_>>();
-            let p2 = c[1].split(",").collect::<Vec<_>>();
-            let from = Point {
-                x: p1[0].parse().unwrap(),
-                y: p1[1].parse().unwrap(),
-            };
-
-            let to = Point {
-                x: p2[0].parse().unwrap(),
-                y: p2[1].parse().unwrap(),
-            };
 
+    for line in lines(input) {
+        let points = line
+            .text
+            .split(" -> ")
+            .map(|s| {
+                let (x, y) = s
+                    .split_once(',')
+                    .ok_or_else(|| line.error(s, "a point like 498,4"))?;
+                let point = Point {
+                    x: line.parse(x, "a x coordinate")?,
+                    y: line.parse(y, "a y coordinate")?,
+                };
+                Ok((s, point))
+            })
+            .collect::<ParseResult<Vec<(&str, Point)>>>()?;
+
+        if points.len() == 1 {
+            grid.add_rock_path(points[0].1, points[0].1);
+        }
+
+        for c in points.windows(2) {
+            let ((_, from), (s, to)) = (c[0], c[1]);
+            if from.x != to.x && from.y != to.y {
+                return Err(line.error(s, "a horizontal or vertical segment"));
+            }
             grid.add_rock_path(from, to);
-        });
-    });
+        }
+    }
+
+    Ok(grid)
+}
+
+pub fn read_grid(path: &str) -> ParseResult<SandGrid> {
+    from_path(path, parse_str)
+}
+
+pub fn sand_until_abyss(grid: &SandGrid) -> usize {
+    grid.clone().drop_sand()
+}
 
+// Doesn't count the last pebble which blocks the source.
+pub fn sand_until_blocked(grid: &SandGrid) -> usize {
     let mut grid2 = grid.clone();
     grid2.add_rock_path(
         Point {
             x: -10000,
-            y: grid2.height + 2,
+            y: grid2.height() + 2,
         },
         Point {
             x: 10000,
-            y: grid2.height + 2,
+            y: grid2.height() + 2,
         },
     );
 
-    return (grid.drop_sand(), grid2.drop_sand());
+    grid2.drop_sand()
+}
+
+// The grid a part drops sand into. The floor of part 2 only needs to be as
+// wide as the pile can get, rather than the 20000 cells `sand_until_blocked`
+// uses, so it can be drawn.
+fn grid_for(grid: &SandGrid, part: Part) -> SandGrid {
+    let mut grid = grid.clone();
+    if part == Part::Two {
+        let floor = grid.height() + 2;
+        let x = grid.source.x;
+        grid.add_rock_path(
+            Point::new(x - floor - 1, floor),
+            Point::new(x + floor + 1, floor),
+        );
+    }
+    grid
+}
+
+// One frame per pebble.
+pub fn animate_sand(grid: &SandGrid, part: Part, sink: &mut dyn FrameSink) -> io::Result<()> {
+    let mut grid = grid_for(grid, part);
+
+    sink.frame(&Frame::from_text(&grid.to_string()))?;
+    loop {
+        let (point, cell) = grid.drop_pebble();
+        sink.frame(&Frame::from_text(&grid.to_string()))?;
+        if cell == Cell::Abyss || (cell == Cell::Sand && point == grid.source) {
+            break;
+        }
+    }
+    sink.finish()
+}
+
+// Pebbles dropped one at a time.
+pub struct Pebbles {
+    grid: SandGrid,
+    dropped: usize,
+    over: bool,
+}
+
+impl Pebbles {
+    pub fn new(grid: &SandGrid, part: Part) -> Pebbles {
+        Pebbles {
+            grid: grid_for(grid, part),
+            dropped: 0,
+            over: false,
+        }
+    }
+}
+
+impl Simulation for Pebbles {
+    fn step(&mut self) -> Option<String> {
+        if self.over {
+            return None;
+        }
+        let (point, cell) = self.grid.drop_pebble();
+        self.dropped += 1;
+        Some(if cell == Cell::Abyss {
+            self.over = true;
+            format!(
+                "pebble {} falls into the abyss from {}",
+                self.dropped, point
+            )
+        } else if cell == Cell::Sand && point == self.grid.source {
+            self.over = true;
+            format!("pebble {} blocks the source", self.dropped)
+        } else {
+            format!("pebble {} rests at {}", self.dropped, point)
+        })
+    }
+}
+
+impl Display for Pebbles {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(f, "{} pebbles dropped", self.dropped)?;
+        write!(f, "{}", self.grid)
+    }
+}
+
+pub fn parse_input(path: &str) -> ParseResult<(usize, usize)> {
+    let grid = read_grid(path)?;
+    Ok((sand_until_abyss(&grid), sand_until_blocked(&grid)))
+}
+
+#[derive(Default)]
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = SandGrid;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        sand_until_abyss(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        (sand_until_blocked(input) + 1).into()
+    }
+}
+
+impl Animate for Day14 {
+    fn animate(&self, input: &Self::Input, part: Part, sink: &mut dyn FrameSink) -> io::Result<()> {
+        animate_sand(input, part, sink)
+    }
+}
+
+impl Simulate for Day14 {
+    fn simulate(&self, input: &Self::Input, part: Part) -> Box<dyn Simulation> {
+        Box::new(Pebbles::new(input, part))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs::read_to_string;
 
     #[test]
     fn test_sand_grid() {
@@ -263,7 +335,7 @@ mod tests {
         grid.add_rock_path(Point { x: 502, y: 4 }, Point { x: 502, y: 9 });
         grid.add_rock_path(Point { x: 502, y: 9 }, Point { x: 494, y: 9 });
 
-        for i in 0..=24 {
+        for _ in 0..=24 {
             grid.drop_pebble();
         }
 
@@ -285,9 +357,44 @@ mod tests {
         assert_eq!(count, 24)
     }
 
+    #[test]
+    fn test_animate_sand() {
+        let grid = read_grid("src/specs/day14").unwrap();
+        let mut frames = vec![];
+        animate_sand(&grid, Part::One, &mut frames).unwrap();
+        // the empty cave, 24 pebbles at rest and the one lost to the abyss
+        assert_eq!(frames.len(), 26);
+        let display_str = read_to_string("src/specs/day14_img").unwrap();
+        assert_eq!(frames[25].to_text() + "\n", display_str);
+
+        frames.clear();
+        animate_sand(&grid, Part::Two, &mut frames).unwrap();
+        assert_eq!(frames.len(), 94);
+    }
+
+    #[test]
+    fn test_pebbles() {
+        let grid = read_grid("src/specs/day14").unwrap();
+        let mut pebbles = Pebbles::new(&grid, Part::One);
+        assert_eq!(pebbles.step().unwrap(), "pebble 1 rests at 500,8");
+        for _ in 1..24 {
+            pebbles.step().unwrap();
+        }
+        assert_eq!(
+            pebbles.step().unwrap(),
+            "pebble 25 falls into the abyss from 494,8"
+        );
+        assert_eq!(pebbles.step(), None);
+        let display_str = read_to_string("src/specs/day14_img").unwrap();
+        assert_eq!(
+            pebbles.to_string(),
+            format!("25 pebbles dropped\n{}", display_str)
+        );
+    }
+
     #[test]
     fn test_parse_input() {
-        let count = parse_input("src/specs/day14");
+        let count = parse_input("src/specs/day14").unwrap();
         assert_eq!(count, (24, 92))
     }
 }
@@ -1,27 +1,16 @@
+use crate::error::{from_path, ParseResult};
+use crate::geometry;
+use crate::input::lines;
+use crate::progress::{Progress, TimedOut};
+use crate::solution::{Answer, FromParams, Params, Solution};
+use crate::trace;
 use regex::Regex;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::io::{stdout, Write};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub struct Point {
-    x: i32,
-    y: i32,
-}
-
-impl Point {
-    pub fn new(x: i32, y: i32) -> Point {
-        Point { x, y }
-    }
-
-    fn distance(&self, other: &Point) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
-    }
-}
+pub type Point = geometry::Point<i32>;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-struct BeaconAndSensor {
+pub struct BeaconAndSensor {
     beacon: Point,
     sensor: Point,
 }
@@ -32,11 +21,11 @@ impl BeaconAndSensor {
     }
 
     fn max_distance(&self) -> i32 {
-        self.sensor.distance(&self.beacon)
+        self.sensor.manhattan(&self.beacon)
     }
 
     fn is_inside_scan_area(&self, point: &Point) -> bool {
-        let distance = self.sensor.distance(point);
+        let distance = self.sensor.manhattan(point);
         distance <= self.max_distance()
     }
 
@@ -48,7 +37,7 @@ impl BeaconAndSensor {
         false
     }
 
-    fn bounds(&self) -> (Point, Point) {
+    pub fn bounds(&self) -> (Point, Point) {
         let min_x = self.sensor.x - self.max_distance();
         let min_y = self.sensor.y - self.max_distance();
         let max_x = self.sensor.x + self.max_distance();
@@ -62,20 +51,19 @@ impl BeaconAndSensor {
         let distance = self.max_distance() - (self.sensor.y - y).abs();
         let x = self.sensor.x;
 
-        if distance < 0 {
-            return v;
-        } else {
-            let p1 = x - distance;
-            let p2 = x + distance;
+        let p1 = (x - distance).max(x_min);
+        let p2 = (x + distance).min(x_max);
 
-            v.push((p1.max(x_min), p2.min(x_max)));
+        // out of reach or entirely left or right of the searched columns
+        if distance >= 0 && p1 <= p2 {
+            v.push((p1, p2));
         }
 
         v
     }
 }
 
-fn get_unreachable_cells(beacons_and_sensors: &Vec<BeaconAndSensor>, y_pos: i32) -> HashSet<Point> {
+fn get_unreachable_cells(beacons_and_sensors: &[BeaconAndSensor], y_pos: i32) -> HashSet<Point> {
     let mut unreachable_cells: HashSet<Point> = HashSet::new();
 
     for beacon_and_sensor in beacons_and_sensors {
@@ -84,22 +72,22 @@ fn get_unreachable_cells(beacons_and_sensors: &Vec<BeaconAndSensor>, y_pos: i32)
         let start = beacon_and_sensor.sensor.x - distance + dy;
         let end = beacon_and_sensor.sensor.x + distance - dy;
 
-        // println!(
-        //     "Sensor: {:?} Beacon: {:?} Distance: {} Start: {} End: {}",
-        //     beacon_and_sensor.sensor, beacon_and_sensor.beacon, distance, start, end
-        // );
+        trace!(
+            Trace,
+            "sensor",
+            sensor = beacon_and_sensor.sensor,
+            beacon = beacon_and_sensor.beacon,
+            distance = distance,
+            columns = (start, end)
+        );
 
         for x in start..=end {
             let point = Point::new(x, y_pos);
             if beacon_and_sensor.should_be_empty(&point) {
-                // println!(
-                //     "Adding point: {:?} because of {:?}",
-                //     point, beacon_and_sensor
-                // );
                 unreachable_cells.insert(point);
             }
 
-            if (point == beacon_and_sensor.sensor) {
+            if point == beacon_and_sensor.sensor {
                 unreachable_cells.insert(point);
             }
         }
@@ -114,70 +102,111 @@ fn get_unreachable_cells(beacons_and_sensors: &Vec<BeaconAndSensor>, y_pos: i32)
 }
 
 fn find_distress_beacon(
-    beacons_and_sensors: &Vec<BeaconAndSensor>,
+    beacons_and_sensors: &[BeaconAndSensor],
     p_min: Point,
     p_max: Point,
-) -> Option<Point> {
+    progress: &Progress,
+) -> Result<Option<Point>, TimedOut> {
     let x_min = p_min.x;
     let x_max = p_max.x;
     let y_min = p_min.y;
     let y_max = p_max.y;
 
     for y in y_min..=y_max {
-        // print!("\r y: {}", y);
-        // stdout().flush().unwrap();
+        trace!(Trace, "row", y = y);
+        progress.step((y - y_min) as u64, Some((y_max - y_min + 1) as u64))?;
         let ranges = get_all_ranges(beacons_and_sensors, y, x_min, x_max);
-        if ranges.len() > 1 {
-            // println!("result: {:?}", ranges);
-            for e in ranges {
-                return Some(Point::new(e.1 + 1, y));
-            }
+        // the gap can be between two ranges or at either end of the row
+        let first = ranges.iter().min().copied();
+        let gap = match first {
+            None => Some(x_min),
+            Some((start, _)) if start > x_min => Some(x_min),
+            Some((_, end)) if end < x_max => Some(end + 1),
+            _ => None,
+        };
+        if let Some(x) = gap {
+            trace!(Debug, "gap", y = y, ranges = ranges);
+            return Ok(Some(Point::new(x, y)));
         }
     }
 
-    None
+    Ok(None)
 }
 
-fn parse_input(path: &str) -> Vec<BeaconAndSensor> {
-    let file = File::open(path).expect("Unable to open file");
-    let mut field_start = Point::new(0, 0);
-    let mut field_end = Point::new(0, 0);
-
-    io::BufReader::new(file)
-        .lines()
-        .filter_map(|i| i.ok())
-        .filter_map(|l| {
-            let regexp = Regex::new(r".*x=(-?\d+), y=(-?\d+):.*x=(-?\d+), y=(-?\d+)").unwrap();
-            let captures = regexp.captures(&l).map(|cap| {
-                let sx = cap[1].parse::<i32>().unwrap();
-                let sy = cap[2].parse::<i32>().unwrap();
-
-                let bx = cap[3].parse::<i32>().unwrap();
-                let by = cap[4].parse::<i32>().unwrap();
-
-                BeaconAndSensor::new(Point::new(bx, by), Point::new(sx, sy))
-            });
-            captures
+pub fn parse_str(input: &str) -> ParseResult<Vec<BeaconAndSensor>> {
+    let regexp =
+        Regex::new(r"^Sensor at x=(-?\d+), y=(-?\d+): closest beacon is at x=(-?\d+), y=(-?\d+)$")
+            .unwrap();
+
+    lines(input)
+        .filter(|l| !l.text.is_empty())
+        .map(|l| {
+            let cap = l.captures(&regexp, "Sensor at x=N, y=N: closest beacon is at x=N, y=N")?;
+            let coordinate =
+                |i: usize| l.parse::<i32>(cap.get(i).unwrap().as_str(), "a coordinate");
+
+            let sx = coordinate(1)?;
+            let sy = coordinate(2)?;
+
+            let bx = coordinate(3)?;
+            let by = coordinate(4)?;
+
+            Ok(BeaconAndSensor::new(Point::new(bx, by), Point::new(sx, sy)))
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-pub fn part_1(path: &str) -> usize {
-    let beacons_and_sensors = parse_input(path);
-    get_unreachable_cells(&beacons_and_sensors, 2000000)
-        .iter()
-        .map(|p| p.x)
-        .len()
+pub fn parse_input(path: &str) -> ParseResult<Vec<BeaconAndSensor>> {
+    from_path(path, parse_str)
+}
+
+pub fn count_unreachable(beacons_and_sensors: &[BeaconAndSensor], row: i32) -> usize {
+    get_unreachable_cells(beacons_and_sensors, row).len()
+}
+
+// Reference for `count_unreachable`, checks every column any sensor reaches.
+pub fn count_unreachable_reference(beacons_and_sensors: &[BeaconAndSensor], row: i32) -> usize {
+    let x_min = beacons_and_sensors.iter().map(|b| b.bounds().0.x).min();
+    let x_max = beacons_and_sensors.iter().map(|b| b.bounds().1.x).max();
+    let (Some(x_min), Some(x_max)) = (x_min, x_max) else {
+        return 0;
+    };
+
+    (x_min..=x_max)
+        .map(|x| Point::new(x, row))
+        .filter(|p| beacons_and_sensors.iter().any(|b| b.is_inside_scan_area(p)))
+        .filter(|p| beacons_and_sensors.iter().all(|b| b.beacon != *p))
+        .count()
+}
+
+// Reference for `find_distress_beacon`, every position between `min` and
+// `max` no sensor reaches. Only usable on small areas.
+pub fn uncovered_reference(
+    beacons_and_sensors: &[BeaconAndSensor],
+    min: Point,
+    max: Point,
+) -> Vec<Point> {
+    (min.y..=max.y)
+        .flat_map(|y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+        .filter(|p| {
+            beacons_and_sensors
+                .iter()
+                .all(|b| !b.is_inside_scan_area(p))
+        })
+        .collect()
+}
+
+pub fn part_1(path: &str) -> ParseResult<usize> {
+    Ok(count_unreachable(&parse_input(path)?, 2000000))
 }
 
 fn merge_ranges(r1: (i32, i32), r2: (i32, i32)) -> Vec<(i32, i32)> {
-    let (x_s1, x_e1) = r1;
-    let (x_s2, x_e2) = r2;
     let mut ranges = Vec::with_capacity(2);
 
-    let (first_range, second_range) = if x_s1 < x_s2 { (r1, r2) } else { (r2, r1) };
+    let (first_range, second_range) = if r1.0 < r2.0 { (r1, r2) } else { (r2, r1) };
 
-    if first_range.1 < second_range.0 {
+    // (0, 5) and (6, 9) leave no gap, they merge into (0, 9)
+    if first_range.1 + 1 < second_range.0 {
         ranges.push(first_range);
         ranges.push(second_range);
     } else if first_range.1 <= second_range.1 {
@@ -190,7 +219,7 @@ fn merge_ranges(r1: (i32, i32), r2: (i32, i32)) -> Vec<(i32, i32)> {
 }
 
 fn get_all_ranges(
-    beacons_and_sensors: &Vec<BeaconAndSensor>,
+    beacons_and_sensors: &[BeaconAndSensor],
     y: i32,
     x_min: i32,
     x_max: i32,
@@ -202,9 +231,9 @@ fn get_all_ranges(
         if r.is_empty() {
             continue;
         }
+        // the whole row is covered, whatever the other sensors reach
         if r[0] == (x_min, x_max) {
-            ranges.insert(r[0]);
-            return ranges;
+            return HashSet::from([r[0]]);
         }
 
         ranges.insert(r[0]);
@@ -232,11 +261,71 @@ fn get_all_ranges(
     ranges
 }
 
-pub fn part_2(path: &str, min: Point, max: Point) -> usize {
-    let beacons_and_sensors = parse_input(path);
-    let Point { x, y } = find_distress_beacon(&beacons_and_sensors, min, max).unwrap();
+pub fn tuning_frequency(beacons_and_sensors: &[BeaconAndSensor], min: Point, max: Point) -> i64 {
+    tuning_frequency_with(beacons_and_sensors, min, max, &Progress::none()).unwrap()
+}
+
+// Scans one row per step.
+pub fn tuning_frequency_with(
+    beacons_and_sensors: &[BeaconAndSensor],
+    min: Point,
+    max: Point,
+    progress: &Progress,
+) -> Result<i64, TimedOut> {
+    let Point { x, y } = find_distress_beacon(beacons_and_sensors, min, max, progress)?.unwrap();
 
-    (4000000 * x + y) as usize
+    Ok(4000000 * x as i64 + y as i64)
+}
+
+pub fn part_2(path: &str, min: Point, max: Point) -> ParseResult<usize> {
+    Ok(tuning_frequency(&parse_input(path)?, min, max) as usize)
+}
+
+pub struct Day15 {
+    pub row: i32,
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Default for Day15 {
+    fn default() -> Self {
+        Day15 {
+            row: 2000000,
+            min: Point::new(0, 0),
+            max: Point::new(4000000, 4000000),
+        }
+    }
+}
+
+impl FromParams for Day15 {
+    fn from_params(params: &Params) -> Result<Self, String> {
+        let default = Day15::default();
+        Ok(Day15 {
+            row: params.get("row", default.row)?,
+            min: params.get("min", default.min)?,
+            max: params.get("max", default.max)?,
+        })
+    }
+}
+
+impl Solution for Day15 {
+    type Input = Vec<BeaconAndSensor>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        count_unreachable(input, self.row).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        self.part2_with(input, &Progress::none())
+    }
+
+    fn part2_with(&self, input: &Self::Input, progress: &Progress) -> Answer {
+        tuning_frequency_with(input, self.min, self.max, progress).into()
+    }
 }
 
 #[cfg(test)]
@@ -247,10 +336,10 @@ fn test_beacon_and_sensor_distance() {
 
     let bas = BeaconAndSensor::new(beacon, sensor);
 
-    assert_eq!(bas.is_inside_scan_area(&sensor), true);
-    assert_eq!(bas.is_inside_scan_area(&beacon), true);
-    assert_eq!(bas.is_inside_scan_area(&Point::new(5, 7)), true);
-    assert_eq!(bas.is_inside_scan_area(&Point::new(0, 11)), false);
+    assert!(bas.is_inside_scan_area(&sensor));
+    assert!(bas.is_inside_scan_area(&beacon));
+    assert!(bas.is_inside_scan_area(&Point::new(5, 7)));
+    assert!(!bas.is_inside_scan_area(&Point::new(0, 11)));
 }
 
 #[test]
@@ -260,9 +349,9 @@ fn test_beacon_and_sensor_should_be_empty() {
 
     let bas = BeaconAndSensor::new(beacon, sensor);
 
-    assert_eq!(bas.should_be_empty(&sensor), false);
-    assert_eq!(bas.should_be_empty(&beacon), false);
-    assert_eq!(bas.should_be_empty(&Point::new(8, 9)), true);
+    assert!(!bas.should_be_empty(&sensor));
+    assert!(!bas.should_be_empty(&beacon));
+    assert!(bas.should_be_empty(&Point::new(8, 9)));
 }
 
 #[test]
@@ -290,7 +379,22 @@ fn test_row_range() {
 
 #[test]
 fn test_get_all_ranges() {
-    let beacons_and_sensors = parse_input("src/specs/day15");
+    let beacons_and_sensors = parse_input("src/specs/day15").unwrap();
     let result2 = get_all_ranges(&beacons_and_sensors, 11, -4, 20);
     assert_eq!(result2, HashSet::from_iter(vec![(-3, 13), (15, 20)]));
 }
+
+#[test]
+fn test_timeout() {
+    let beacons_and_sensors = parse_input("src/specs/day15").unwrap();
+    let (min, max) = (Point::new(0, 0), Point::new(20, 20));
+    let progress = Progress::new().with_timeout(std::time::Duration::ZERO);
+    assert_eq!(
+        tuning_frequency_with(&beacons_and_sensors, min, max, &progress),
+        Err(TimedOut)
+    );
+    assert_eq!(
+        tuning_frequency_with(&beacons_and_sensors, min, max, &Progress::none()),
+        Ok(56000011)
+    );
+}
@@ -1,181 +1,91 @@
-use std::collections::{HashMap, HashSet};
+use crate::error::{from_path, Line, ParseResult};
+use crate::frames::{Animate, Frame, FrameSink};
+use crate::geometry::{BBox, Point, Transform};
+use crate::grid::Grid;
+use crate::input::lines;
+use crate::simulation::{Simulate, Simulation};
+use crate::solution::{Answer, Part, Solution};
 use std::fmt::{Display, Error, Formatter};
-use std::fs::read_to_string;
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Transform {
-    tx: i64,
-    ty: i64,
-}
-
-impl Transform {
-    fn new(tx: i64, ty: i64) -> Self {
-        Transform { tx, ty }
-    }
-
-    fn apply(&self, point: Point) -> Point {
-        Point {
-            x: point.x + self.tx,
-            y: point.y + self.ty,
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Point {
-    x: i64,
-    y: i64,
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Bbox {
-    coords: Point,
-    width: i64,
-    height: i64,
-}
+use std::io;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Figure {
-    points: HashSet<Point>,
+pub struct Figure {
+    points: Grid<()>,
 }
 
 impl Figure {
     fn new() -> Self {
         Figure {
-            points: HashSet::new(),
+            points: Grid::sparse(),
         }
     }
 
+    fn from_points(points: &[(i64, i64)]) -> Self {
+        let mut figure = Figure::new();
+        for point in points {
+            figure.points.insert(*point, ());
+        }
+        figure
+    }
+
     fn square() -> Self {
-        let mut points = HashSet::new();
-        points.insert(Point { x: 0, y: 0 });
-        points.insert(Point { x: 1, y: 0 });
-        points.insert(Point { x: 0, y: 1 });
-        points.insert(Point { x: 1, y: 1 });
-        Figure { points }
+        Figure::from_points(&[(0, 0), (1, 0), (0, 1), (1, 1)])
     }
 
     fn minus() -> Self {
-        let mut points = HashSet::new();
-        points.insert(Point { x: 0, y: 0 });
-        points.insert(Point { x: 1, y: 0 });
-        points.insert(Point { x: 2, y: 0 });
-        points.insert(Point { x: 3, y: 0 });
-        Figure { points }
+        Figure::from_points(&[(0, 0), (1, 0), (2, 0), (3, 0)])
     }
 
     fn pipe() -> Self {
-        let mut points = HashSet::new();
-        points.insert(Point { x: 0, y: 0 });
-        points.insert(Point { x: 0, y: 1 });
-        points.insert(Point { x: 0, y: 2 });
-        points.insert(Point { x: 0, y: 3 });
-        Figure { points }
+        Figure::from_points(&[(0, 0), (0, 1), (0, 2), (0, 3)])
     }
 
     fn plus() -> Self {
-        let mut points = HashSet::new();
-        points.insert(Point { x: 1, y: 0 });
-        points.insert(Point { x: 1, y: 1 });
-        points.insert(Point { x: 1, y: 2 });
-        points.insert(Point { x: 0, y: 1 });
-        points.insert(Point { x: 2, y: 1 });
-        Figure { points }
+        Figure::from_points(&[(1, 0), (1, 1), (1, 2), (0, 1), (2, 1)])
     }
 
     fn l() -> Self {
-        let mut points = HashSet::new();
-        points.insert(Point { x: 0, y: 0 });
-        points.insert(Point { x: 1, y: 0 });
-        points.insert(Point { x: 2, y: 0 });
-        points.insert(Point { x: 2, y: 1 });
-        points.insert(Point { x: 2, y: 2 });
-
-        Figure { points }
+        Figure::from_points(&[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)])
     }
 
     fn merge(&mut self, other: Figure) {
-        self.points.extend(other.points);
+        for (point, _) in other.points.iter() {
+            self.points.insert(point, ());
+        }
     }
 
-    fn intersect(&self, p: &HashSet<Point>) -> bool {
-        self.points.intersection(p).count() > 0
+    fn intersect(&self, other: &Figure) -> bool {
+        self.points.iter().any(|(p, _)| other.points.contains(p))
     }
 
     fn intersect_x(&self, x: i64) -> bool {
-        self.points.iter().any(|p| p.x == x)
+        self.points.iter().any(|(p, _)| p.x == x)
     }
 
-    fn bbox(&self) -> Bbox {
-        let mut min_x = i64::MAX;
-        let mut min_y = i64::MAX;
-        let mut max_x = i64::MIN;
-        let mut max_y = i64::MIN;
-
-        for i in self.points.iter() {
-            if i.x < min_x {
-                min_x = i.x;
-            }
-            if i.x > max_x {
-                max_x = i.x;
-            }
-            if i.y < min_y {
-                min_y = i.y;
-            }
-            if i.y > max_y {
-                max_y = i.y;
-            }
-        }
-
-        if self.points.is_empty() {
-            return Bbox {
-                coords: Point { x: 0, y: 0 },
-                width: 0,
-                height: 0,
-            };
-        }
-
-        Bbox {
-            coords: Point { x: min_x, y: min_y },
-            width: max_x - min_x + 1,
-            height: max_y - min_y + 1,
-        }
+    fn bbox(&self) -> Option<BBox> {
+        self.points.bounds()
     }
 
     fn apply_transform(&mut self, transform: Transform) {
-        let transformed = self
-            .points
-            .iter()
-            .map(|p| transform.apply(*p))
-            .collect::<HashSet<Point>>();
-        self.points = transformed;
+        let mut transformed = Figure::new();
+        for (point, _) in self.points.iter() {
+            transformed.points.insert(transform.apply(point), ());
+        }
+        *self = transformed;
     }
 }
 
 impl Display for Figure {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        let bbox = self.bbox();
-
-        for y in (0..bbox.height).rev() {
-            for x in 0..bbox.width {
-                let point = Point {
-                    x: bbox.coords.x + x,
-                    y: bbox.coords.y + y,
-                };
-                if self.points.contains(&point) {
-                    write!(f, "#")?;
-                } else {
-                    write!(f, ".")?;
-                }
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        let drawing = self
+            .points
+            .render(true, |_, point| if point.is_some() { '#' } else { '.' });
+        write!(f, "{}", drawing)
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Field {
+pub struct Field {
     time: i64,
     stable_figure: Figure,
     moving_figure: Figure,
@@ -191,7 +101,7 @@ struct Field {
 }
 
 impl Field {
-    fn new(width: i64, wind_pattern: Vec<i64>) -> Self {
+    pub fn new(width: i64, wind_pattern: Vec<i64>) -> Self {
         Field {
             time: -1,
             stable_figure: Figure::new(),
@@ -214,35 +124,22 @@ impl Field {
         }
     }
 
-    fn floor(&self) -> HashSet<Point> {
-        (0..self.width).map(|x| Point { x, y: -1 }).collect()
-    }
-
-    fn get_figure_position_x(&self) -> Vec<Point> {
-        let positions: Vec<i64> = Vec::with_capacity(self.wind_pattern.len());
-
-        self.stable_figure
-            .points
-            .union(&self.moving_figure.points)
-            .cloned()
-            .collect()
+    fn floor(&self) -> Figure {
+        Figure::from_points(&(0..self.width).map(|x| (x, -1)).collect::<Vec<_>>())
     }
 
     fn add_figure(&mut self, figure: Figure) {
-        let transform = Transform {
-            tx: 2,
-            ty: self.height + 3,
-        };
+        let transform = Transform::translate(Point::new(2, self.height + 3));
 
         let mut mutable_figure = figure;
         mutable_figure.apply_transform(transform);
 
         self.moving_figure = mutable_figure;
 
-        self.height = self.moving_figure.bbox().coords.y;
+        self.height = self.moving_figure.bbox().map_or(0, |b| b.min.y);
     }
 
-    fn start_new_cycle(&mut self) {
+    pub fn start_new_cycle(&mut self) {
         // before merge
 
         self.cycle += 1;
@@ -251,19 +148,19 @@ impl Field {
         // after merge
 
         self.stable_figure.merge(self.moving_figure.clone());
-        self.height = self.stable_figure.bbox().height;
+        self.height = self.tower_height();
 
         if self.loop_after == -1 {
             let next = (
                 self.time,
-                self.moving_figure.bbox().coords.x,
+                self.moving_figure.bbox().map_or(0, |b| b.min.x),
                 self.cycle % 5,
             );
 
             let index = self.cycle_positions.iter().position(|p| p == &next);
 
-            if index.is_some() {
-                self.loop_after = index.unwrap() as i32;
+            if let Some(index) = index {
+                self.loop_after = index as i32;
                 self.loop_period = (self.cycle_positions.len() - self.loop_after as usize) as i32;
                 self.loop_height = self.height;
             }
@@ -274,44 +171,48 @@ impl Field {
         self.add_figure(self.figures[self.cycle as usize % 5].clone());
     }
 
+    pub fn cycle(&self) -> i64 {
+        self.cycle
+    }
+
+    pub fn tower_height(&self) -> i64 {
+        self.stable_figure.bbox().map_or(0, |b| b.height())
+    }
+
     fn get_current_wind(&self) -> i64 {
-        self.wind_pattern[(self.time as usize % self.wind_pattern.len())]
+        self.wind_pattern[self.time as usize % self.wind_pattern.len()]
     }
 
     fn apply_wind(&mut self, figure: Figure) -> Figure {
         let mut figure = figure.clone();
-        figure.apply_transform(Transform {
-            tx: self.get_current_wind(),
-            ty: 0,
-        });
+        figure.apply_transform(Transform::translate(Point::new(self.get_current_wind(), 0)));
 
         if figure.intersect_x(-1) {
-            figure.apply_transform(Transform { tx: 1, ty: 0 });
+            figure.apply_transform(Transform::translate(Point::new(1, 0)));
         }
 
         if figure.intersect_x(self.width) {
-            figure.apply_transform(Transform { tx: -1, ty: 0 });
+            figure.apply_transform(Transform::translate(Point::new(-1, 0)));
         }
 
-        if figure.intersect(&self.stable_figure.points) {
-            figure.apply_transform(Transform {
-                tx: -1 * self.get_current_wind(),
-                ty: 0,
-            });
+        if figure.intersect(&self.stable_figure) {
+            figure.apply_transform(Transform::translate(Point::new(
+                -self.get_current_wind(),
+                0,
+            )));
         }
 
         figure
     }
 
-    fn tick(&mut self) {
+    pub fn tick(&mut self) {
         self.moving_figure = self.apply_wind(self.moving_figure.clone());
 
         let mut next = self.moving_figure.clone();
 
-        next.apply_transform(Transform { tx: 0, ty: -1 });
+        next.apply_transform(Transform::translate(Point::new(0, -1)));
 
-        let should_stop =
-            next.intersect(&self.floor()) || next.intersect(&self.stable_figure.points);
+        let should_stop = next.intersect(&self.floor()) || next.intersect(&self.stable_figure);
 
         if should_stop {
             self.start_new_cycle();
@@ -326,28 +227,47 @@ impl Field {
     }
 }
 
+impl Field {
+    fn cell(&self, point: Point) -> char {
+        if point.x == -1 || point.x == self.width {
+            '|'
+        } else if point.y == -1 {
+            '_'
+        } else if self.moving_figure.points.contains(point) {
+            '@'
+        } else if self.stable_figure.points.contains(point) {
+            '#'
+        } else {
+            '.'
+        }
+    }
+
+    // The top `rows` rows of the chamber including the falling rock, down to
+    // the floor at most.
+    pub fn render(&self, rows: i64) -> String {
+        let top = self
+            .moving_figure
+            .bbox()
+            .map_or(0, |b| b.max.y + 1)
+            .max(self.tower_height());
+        let mut out = String::new();
+        for y in ((top - rows).max(-1)..top).rev() {
+            for x in -1..=self.width {
+                out.push(self.cell(Point::new(x, y)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
 impl Display for Field {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        writeln!(f, "cycle: {}", self.cycle);
+        writeln!(f, "cycle: {}", self.cycle)?;
         for y in (-1..self.height).rev() {
-            write!(f, "{:2} ", y);
+            write!(f, "{:2} ", y)?;
             for x in -1..=self.width {
-                let point = Point { x, y };
-                if x == -1 {
-                    write!(f, "|")?;
-                } else if x == self.width {
-                    write!(f, "|")?;
-                } else if y == -1 {
-                    write!(f, "_")?;
-                } else {
-                    if self.moving_figure.points.contains(&point) {
-                        write!(f, "@")?;
-                    } else if self.stable_figure.points.contains(&point) {
-                        write!(f, "#")?;
-                    } else {
-                        write!(f, ".")?;
-                    }
-                }
+                write!(f, "{}", self.cell(Point::new(x, y)))?;
             }
             writeln!(f)?;
         }
@@ -355,88 +275,188 @@ impl Display for Field {
     }
 }
 
-pub fn part_1(path: &str) -> usize {
-    let content = read_to_string(path).unwrap();
-    let pattern: Vec<i64> = content
-        .trim()
-        .split("")
-        .filter(|c| !c.is_empty())
-        .map(|c| match c {
-            ">" => 1,
-            "<" => -1,
-            _ => 0,
-        })
-        .collect();
+pub fn parse_str(input: &str) -> ParseResult<Vec<i64>> {
+    let mut pattern = vec![];
+
+    for line in lines(input) {
+        for (i, c) in line.text.trim_end().char_indices() {
+            pattern.push(match c {
+                '>' => 1,
+                '<' => -1,
+                _ => return Err(line.error(&line.text[i..], "< or >")),
+            });
+        }
+    }
+    if pattern.is_empty() {
+        return Err(Line::end(input).error("", "a jet pattern of < and >"));
+    }
 
-    // let mut field = Field::new(7, pattern.clone());
-    // field.start_new_cycle();
+    Ok(pattern)
+}
 
-    // while field.loop_after == i32::MAX {
-    //     field.tick();
-    // }
+pub fn read_pattern(path: &str) -> ParseResult<Vec<i64>> {
+    from_path(path, parse_str)
+}
 
-    // println!("Loop after {} cycles", field.loop_after);
-    // println!("Loop height {}", field.loop_height);
+pub fn tower_height(pattern: &[i64], rocks: i64) -> usize {
+    let mut field = Field::new(7, pattern.to_vec());
+    field.start_new_cycle();
 
-    let mut field_2 = Field::new(7, pattern);
+    while field.cycle < rocks {
+        field.tick();
+    }
 
-    // let iterations = 2022;
-    // let remaining_iterations = iterations % (field.loop_after + 1) as usize;
-    // let multiplyer = iterations / (field.loop_after + 1) as usize;
+    field.tower_height() as usize
+}
 
-    // assert_eq!(
-    //     remaining_iterations + multiplyer * (field.loop_after + 1) as usize,
-    //     iterations
-    // );
+// Rows shown in each frame of the animation.
+const VISIBLE_ROWS: i64 = 30;
 
-    // let additional_height = steps * field.loop_height as usize;
+// One frame per tick, showing the top of the tower.
+pub fn animate_tower(pattern: &[i64], rocks: i64, sink: &mut dyn FrameSink) -> io::Result<()> {
+    let mut field = Field::new(7, pattern.to_vec());
+    field.start_new_cycle();
 
-    let iter_count = 2022;
-    field_2.start_new_cycle();
-    while field_2.loop_after == -1 {
-        field_2.tick();
+    sink.frame(&Frame::from_text(&field.render(VISIBLE_ROWS)))?;
+    while field.cycle < rocks {
+        field.tick();
+        sink.frame(&Frame::from_text(&field.render(VISIBLE_ROWS)))?;
     }
+    sink.finish()
+}
+
+// The tower tick by tick, until `rocks` rocks came to rest.
+pub struct Tower {
+    field: Field,
+    rocks: i64,
+}
 
-    for _ in 0..=field_2.loop_period {
-        field_2.tick();
+impl Tower {
+    pub fn new(pattern: &[i64], rocks: i64) -> Tower {
+        let mut field = Field::new(7, pattern.to_vec());
+        field.start_new_cycle();
+        Tower { field, rocks }
     }
+}
 
-    let height_2 = field_2.stable_figure.bbox().height as usize;
-    let cycle_2 = field_2.cycle;
+impl Simulation for Tower {
+    fn step(&mut self) -> Option<String> {
+        if self.field.cycle >= self.rocks {
+            return None;
+        }
+        let cycle = self.field.cycle;
+        self.field.tick();
+        Some(if self.field.cycle > cycle {
+            format!(
+                "rock {} comes to rest, the tower is {} high",
+                self.field.cycle,
+                self.field.tower_height()
+            )
+        } else {
+            format!("rock {} falls", self.field.cycle + 1)
+        })
+    }
+}
 
-    // for _ in 0..=field_2.loop_period {
-    //     field_2.tick();
-    // }
+// Only the top of the tower, all of it gets long quickly.
+impl Display for Tower {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(
+            f,
+            "{} rocks at rest, the tower is {} high",
+            self.field.cycle,
+            self.field.tower_height()
+        )?;
+        write!(f, "{}", self.field.render(VISIBLE_ROWS))
+    }
+}
 
-    for _ in 0..20 {
-        let prev = field_2.stable_figure.bbox().height as usize;
-        let prev_cycle = field_2.cycle;
+// Reference for `tower_height`, the chamber as one bit mask per row.
+pub fn tower_height_reference(pattern: &[i64], rocks: i64) -> usize {
+    let shapes: [&[(usize, usize)]; 5] = [
+        &[(0, 0), (1, 0), (2, 0), (3, 0)],
+        &[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)],
+        &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)],
+        &[(0, 0), (0, 1), (0, 2), (0, 3)],
+        &[(0, 0), (1, 0), (0, 1), (1, 1)],
+    ];
+    let mut rows: Vec<u8> = vec![];
+    let mut jet = 0;
+
+    for rock in 0..rocks as usize {
+        let shape = shapes[rock % 5];
+        let (mut x, mut y) = (2, rows.len() + 3);
+        let fits = |rows: &Vec<u8>, x: usize, y: usize| {
+            shape.iter().all(|(dx, dy)| {
+                x + dx < 7
+                    && rows
+                        .get(y + dy)
+                        .is_none_or(|row| row & (1 << (x + dx)) == 0)
+            })
+        };
 
-        for _ in 0..3 * (field_2.loop_period) {
-            field_2.tick();
+        loop {
+            let pushed = x as i64 + pattern[jet % pattern.len()];
+            jet += 1;
+            if pushed >= 0 && fits(&rows, pushed as usize, y) {
+                x = pushed as usize;
+            }
+            if y == 0 || !fits(&rows, x, y - 1) {
+                break;
+            }
+            y -= 1;
         }
 
-        let next = field_2.stable_figure.bbox().height as usize;
-        let next_cycle = field_2.cycle;
-
-        println!("{} {}", next - prev, next_cycle - prev_cycle)
+        for (dx, dy) in shape {
+            if rows.len() <= y + dy {
+                rows.resize(y + dy + 1, 0);
+            }
+            rows[y + dy] |= 1 << (x + dx);
+        }
     }
 
-    // let height_3 = field_2.stable_figure.bbox().height as usize;
-    // let cycle_3 = field_2.cycle;
+    rows.len()
+}
 
-    // let delta_h = height_3 - height_2;
-    // let delta_c = cycle_3 - cycle_2;
+pub fn part_1(path: &str) -> ParseResult<usize> {
+    Ok(tower_height(&read_pattern(path)?, 2022))
+}
+
+#[derive(Default)]
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Vec<i64>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        tower_height(input, 2022).into()
+    }
 
-    // let remaining = cycle_3 + (iter_count - cycle_3) % delta_c;
+    fn part2(&self, _input: &Self::Input) -> Answer {
+        Answer::Unsolved
+    }
+}
 
-    // while field_2.cycle <= remaining + 1 {
-    //     field_2.tick();
-    // }
+// Part 2 has no solution to replay, both parts show the first 2022 rocks.
+impl Animate for Day17 {
+    fn animate(
+        &self,
+        input: &Self::Input,
+        _part: Part,
+        sink: &mut dyn FrameSink,
+    ) -> io::Result<()> {
+        animate_tower(input, 2022, sink)
+    }
+}
 
-    // field_2.stable_figure.bbox().height as usize
-    //     + delta_h * ((iter_count - cycle_3) / delta_c) as usize
-    5
+impl Simulate for Day17 {
+    fn simulate(&self, input: &Self::Input, _part: Part) -> Box<dyn Simulation> {
+        Box::new(Tower::new(input, 2022))
+    }
 }
 
 #[cfg(test)]
@@ -455,10 +475,33 @@ mod tests {
         assert_eq!(minus_str, "####\n");
     }
 
-    // #[test]
-    // fn test_part_1() {
-    //     let result = part_1("src/specs/day17");
+    #[test]
+    fn test_animate_tower() {
+        let pattern = read_pattern("src/specs/day17").unwrap();
+        let mut frames = vec![];
+        animate_tower(&pattern, 1, &mut frames).unwrap();
+        assert_eq!(
+            frames[0].to_text(),
+            "|..@@@@.|\n|.......|\n|.......|\n|.......|\n|_______|"
+        );
+        assert_eq!(
+            frames.last().unwrap().to_text(),
+            "|...@...|\n|..@@@..|\n|...@...|\n|.......|\n|.......|\n|.......|\n|..####.|\n|_______|"
+        );
+    }
 
-    //     assert_eq!(result, 3068);
-    // }
+    #[test]
+    fn test_part_1() {
+        let result = part_1("src/specs/day17").unwrap();
+
+        assert_eq!(result, 3068);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(
+            parse_str("").unwrap_err().to_string(),
+            "<input>:1:1: expected a jet pattern of < and >, found end of line"
+        );
+    }
 }
@@ -1,26 +1,31 @@
+use crate::error::{from_path, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
+use crate::trace;
 use std::collections::HashSet;
 use std::fmt::{Display, Error, Formatter};
-use std::fs::File;
-use std::io::{self, BufRead};
-
-pub fn parse_input(path: &str) -> HashSet<(i32, i32, i32)> {
-    let file = File::open(path).expect("Unable to open file");
-    io::BufReader::new(file)
-        .lines()
-        .into_iter()
-        .map(|s| {
-            let vec = s
-                .unwrap()
-                .split(",")
-                .map(|x| x.parse::<i32>().unwrap())
-                .collect::<Vec<i32>>();
-            (vec[0], vec[1], vec[2])
+
+pub fn parse_str(input: &str) -> ParseResult<HashSet<(i32, i32, i32)>> {
+    lines(input)
+        .map(|l| {
+            let vec = l
+                .text
+                .split(',')
+                .map(|x| l.parse::<i32>(x, "a coordinate"))
+                .collect::<ParseResult<Vec<i32>>>()?;
+            if vec.len() != 3 {
+                return Err(l.error(l.text, "three coordinates like 1,2,3"));
+            }
+            Ok((vec[0], vec[1], vec[2]))
         })
-        .collect::<HashSet<(i32, i32, i32)>>()
+        .collect::<ParseResult<HashSet<(i32, i32, i32)>>>()
+}
+
+pub fn parse_input(path: &str) -> ParseResult<HashSet<(i32, i32, i32)>> {
+    from_path(path, parse_str)
 }
 
-pub fn part_1(path: &str) -> usize {
-    let result = parse_input(path);
+pub fn surface_area(result: &HashSet<(i32, i32, i32)>) -> usize {
     let mut count = 0;
 
     for (x, y, z) in result.iter() {
@@ -33,7 +38,7 @@ pub fn part_1(path: &str) -> usize {
 
         let mut count_per_cube = 6;
 
-        for (x, y, z) in vec![left, right, top, bottom, front, back] {
+        for (x, y, z) in [left, right, top, bottom, front, back] {
             if result.contains(&(x, y, z)) {
                 count_per_cube -= 1;
             }
@@ -45,6 +50,10 @@ pub fn part_1(path: &str) -> usize {
     count
 }
 
+pub fn part_1(path: &str) -> ParseResult<usize> {
+    Ok(surface_area(&parse_input(path)?))
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 enum Direction {
     X,
@@ -77,8 +86,8 @@ impl Face {
                     Face::new(Direction::Z, (x - 1, y, z)),
                 ]);
 
-                let Cube = Cube::new((x, y, z));
-                let mut cube_faces = Cube.get_faces();
+                let cube = Cube::new((x, y, z));
+                let mut cube_faces = cube.get_faces();
                 cube_faces.remove(self);
                 cube_faces.remove(&Face::new(Direction::Z, (x, y, z - 1)));
 
@@ -101,8 +110,8 @@ impl Face {
                     Face::new(Direction::Y, (x - 1, y, z)),
                 ]);
 
-                let Cube = Cube::new((x, y, z));
-                let mut cube_faces = Cube.get_faces();
+                let cube = Cube::new((x, y, z));
+                let mut cube_faces = cube.get_faces();
                 cube_faces.remove(self);
                 cube_faces.remove(&Face::new(Direction::Y, (x, y - 1, z)));
 
@@ -125,8 +134,8 @@ impl Face {
                     Face::new(Direction::X, (x, y - 1, z)),
                 ]);
 
-                let Cube = Cube::new((x, y, z));
-                let mut cube_faces = Cube.get_faces();
+                let cube = Cube::new((x, y, z));
+                let mut cube_faces = cube.get_faces();
                 cube_faces.remove(self);
                 cube_faces.remove(&Face::new(Direction::X, (x - 1, y, z)));
 
@@ -220,6 +229,7 @@ impl Cluster {
         cluster
     }
 
+    #[cfg(test)]
     fn total_faces(&self) -> usize {
         self.faces.len()
     }
@@ -235,6 +245,7 @@ impl Cluster {
         self.cubes.insert(c);
     }
 
+    #[cfg(test)]
     fn can_push(&mut self, c: Cube) -> bool {
         let faces = c.get_faces();
         for c in faces {
@@ -261,26 +272,23 @@ impl Cluster {
         let mut to_visit: Vec<Face> = Vec::new();
 
         let mut current_faces = self.faces.clone();
-        let min_x_face = current_faces
+        let min_x_face = *current_faces
             .iter()
             .filter(|x| x.direction == Direction::X)
             .min_by(|x, y| x.position.0.cmp(&y.position.0))
-            .unwrap()
-            .clone();
+            .unwrap();
 
         current_faces.remove(&min_x_face);
 
         to_visit.push(min_x_face);
 
-        while !to_visit.is_empty() {
-            let current = to_visit.pop().unwrap();
-
+        while let Some(current) = to_visit.pop() {
             let neighbors = current.get_neighbors();
             for a in neighbors {
-                // println!("Neighbor: {:?}", a);
+                trace!(Trace, "neighbour", face = a);
                 if current_faces.contains(&a) && !outer.contains(&a) {
                     current_faces.remove(&a);
-                    outer.insert(a.clone());
+                    outer.insert(a);
                     to_visit.push(a);
                 }
             }
@@ -302,9 +310,7 @@ impl Display for Cluster {
     }
 }
 
-pub fn part_2(path: &str) -> usize {
-    let result = parse_input(path);
-
+pub fn exterior_surface_area(result: &HashSet<(i32, i32, i32)>) -> usize {
     let mut initial_clusters = result
         .iter()
         .map(|x| Cluster::from(Cube::new(*x)))
@@ -327,35 +333,50 @@ pub fn part_2(path: &str) -> usize {
         break;
     }
 
-    for c in &initial_clusters {
-        let outer_edges = c.get_outer_faces();
-        println!(
-            "inner edges: {:?}",
-            c.faces.difference(&c.get_outer_faces())
-        );
-    }
-
     initial_clusters
         .iter()
-        .map(|x| x.get_outer_faces().iter().count())
+        .map(|x| x.get_outer_faces().len())
         .sum::<usize>()
 }
 
+pub fn part_2(path: &str) -> ParseResult<usize> {
+    Ok(exterior_surface_area(&parse_input(path)?))
+}
+
+#[derive(Default)]
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input = HashSet<(i32, i32, i32)>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        surface_area(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        exterior_surface_area(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_part_1() {
-        let input = part_1("src/specs/day18");
+        let input = part_1("src/specs/day18").unwrap();
         assert_eq!(input, 64);
     }
 
-    // #[test]
-    // fn test_part_2() {
-    //     let input = part_2("src/specs/day18");
-    //     assert_eq!(input, 51);
-    // }
+    #[test]
+    fn test_part_2() {
+        let input = part_2("src/specs/day18").unwrap();
+        assert_eq!(input, 58);
+    }
 
     #[test]
     fn cluster() {
@@ -367,9 +388,9 @@ mod tests {
 
         assert_eq!(cluster.total_faces(), 10);
 
-        assert_eq!(cluster.can_push(Cube::new((4, 3, 1))), false);
-        assert_eq!(cluster.can_push(Cube::new((2, 2, 1))), false);
-        assert_eq!(cluster.can_push(Cube::new((2, 2, 1))), false);
-        assert_eq!(cluster.can_push(Cube::new((2, 0, 0))), true);
+        assert!(!cluster.can_push(Cube::new((4, 3, 1))));
+        assert!(!cluster.can_push(Cube::new((2, 2, 1))));
+        assert!(!cluster.can_push(Cube::new((2, 2, 1))));
+        assert!(cluster.can_push(Cube::new((2, 0, 0))));
     }
 }
@@ -1,16 +1,17 @@
+use crate::error::{from_path, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
 use std::collections::HashMap;
-use std::env;
-use std::fs::read_to_string;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
-enum Gestures {
+pub enum Gestures {
     Rock,
     Paper,
     Scissors,
 }
 
-#[derive(PartialEq, Eq, Debug)]
-enum Result {
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Result {
     Win,
     Lose,
     Draw,
@@ -46,7 +47,7 @@ impl Gestures {
     ) -> Option<&'a Gestures> {
         [Gestures::Rock, Gestures::Paper, Gestures::Scissors]
             .iter()
-            .find(|gesture| first_payer_gesture.beats(&gesture) == *expected_result)
+            .find(|gesture| first_payer_gesture.beats(gesture) == *expected_result)
     }
 
     fn score(&self) -> usize {
@@ -58,7 +59,7 @@ impl Gestures {
     }
 }
 
-pub fn calculate_win_score(path: &str) -> usize {
+pub fn parse_str(input: &str) -> ParseResult<Vec<(Gestures, Result)>> {
     let decoder_gestures = HashMap::from([
         ("A", Gestures::Rock),
         ("B", Gestures::Paper),
@@ -68,19 +69,57 @@ pub fn calculate_win_score(path: &str) -> usize {
     let decoder_expected_result =
         HashMap::from([("X", Result::Lose), ("Y", Result::Draw), ("Z", Result::Win)]);
 
-    let file = read_to_string(path).expect("Unable to read file");
-    file.split("\n").into_iter().fold(0, |acc, s| {
-        let split = s.split(" ").collect::<Vec<&str>>();
-        if split.len() != 2 {
-            return acc;
-        };
-        let first_gesture = decoder_gestures.get(split[0]).unwrap();
-        let result = decoder_expected_result.get(split[1]).unwrap();
+    lines(input)
+        .filter(|line| !line.text.is_empty())
+        .map(|line| {
+            let mut split = line.text.split(' ');
+            let first = split.next().unwrap_or(line.text);
+            let first_gesture = *decoder_gestures
+                .get(first)
+                .ok_or_else(|| line.error(first, "A, B or C"))?;
+            let second = split.next().unwrap_or(&line.text[line.text.len()..]);
+            let result = *decoder_expected_result
+                .get(second)
+                .ok_or_else(|| line.error(second, "X, Y or Z"))?;
+            Ok((first_gesture, result))
+        })
+        .collect()
+}
+
+pub fn parse_input(path: &str) -> ParseResult<Vec<(Gestures, Result)>> {
+    from_path(path, parse_str)
+}
+
+pub fn win_score(rounds: &[(Gestures, Result)]) -> usize {
+    rounds.iter().fold(0, |acc, (first_gesture, result)| {
         let second_gesture = Gestures::get_gesture(result, first_gesture).unwrap();
         acc + first_gesture.beats(second_gesture).score() + second_gesture.score()
     })
 }
 
+pub fn calculate_win_score(path: &str) -> ParseResult<usize> {
+    Ok(win_score(&parse_input(path)?))
+}
+
+#[derive(Default)]
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<(Gestures, Result)>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, _input: &Self::Input) -> Answer {
+        Answer::Unsolved
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        win_score(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -88,7 +127,7 @@ mod test {
     #[test]
     fn test_day2() {
         let file = "src/specs/day2";
-        let result = calculate_win_score(file);
+        let result = calculate_win_score(file).unwrap();
         assert_eq!(result, 12);
     }
 }
@@ -1,46 +1,51 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::fmt::{Display, Error, Formatter};
-use std::fs::File;
-use std::io::{self, BufRead};
-
-pub fn parse_input(path: &str) -> Vec<(i64, bool)> {
-    let file = File::open(path).unwrap();
-    let mut i = 0;
-    let mut result = io::BufReader::new(file)
-        .lines()
-        .map(|line| (line.unwrap().parse::<i64>().unwrap(), false))
-        .collect::<Vec<(i64, bool)>>();
-
-    result
+use crate::error::{from_path, Line, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
+use crate::trace;
+
+// The grove coordinates are counted from the 0, so there has to be one.
+pub fn parse_str(input: &str) -> ParseResult<Vec<(i64, bool)>> {
+    let numbers = lines(input)
+        .map(|line| Ok((line.parse::<i64>(line.text, "a number")?, false)))
+        .collect::<ParseResult<Vec<_>>>()?;
+    if !numbers.iter().any(|(number, _)| *number == 0) {
+        return Err(Line::end(input).error("", "a 0 among the numbers"));
+    }
+    Ok(numbers)
 }
 
+pub fn parse_input(path: &str) -> ParseResult<Vec<(i64, bool)>> {
+    from_path(path, parse_str)
+}
 
-pub fn part_1(path: &str) -> i64 {
-    let mut result = parse_input(path);
+pub fn mix(input: &[(i64, bool)]) -> i64 {
+    let mut result = input.to_vec();
     let mut pos = 0;
 
     while pos < result.len() {
-        let idx = result.iter().position(|e| e.1 == false).unwrap();
+        let idx = result.iter().position(|e| !e.1).unwrap();
         let value = result[idx].0;
 
-        // println!("moving value: {}", value);
         let mut next_position = idx as i64 + value;
         if next_position > (result.len() - 1) as i64 {
-            next_position = next_position % (result.len() - 1) as i64;
+            next_position %= (result.len() - 1) as i64;
         }
 
         if next_position <= 0 && value != 0 {
             next_position = result.len() as i64 + (next_position % (result.len() - 1) as i64) - 1;
         }
 
-        // println!("next position: {}", next_position);
+        trace!(
+            Trace,
+            "moving",
+            value = value,
+            from = idx,
+            to = next_position
+        );
 
         result.remove(idx);
         result.insert(next_position as usize, (value, true));
         pos += 1;
-
-        // println!("result: {:?}", &result);
     }
 
     let zero_pos = result.iter().position(|e| e.0 == 0).unwrap();
@@ -48,43 +53,55 @@ pub fn part_1(path: &str) -> i64 {
     let pos_2000 = (zero_pos + 2000) % result.len();
     let pos_3000 = (zero_pos + 3000) % result.len();
 
-    // println!("final result: {:?}", result);
+    trace!(Debug, "zero", position = zero_pos, len = result.len());
     [pos_1000, pos_2000, pos_3000]
         .iter()
         .map(|p| result[*p].0)
         .sum()
 }
 
-const KEY: i64 = 811589153;
+pub const KEY: i64 = 811589153;
 
-pub fn part_2(path: &str) -> i64 {
-    let mut result = parse_input(path).iter().enumerate().map(|(index, v)| (v.0 * KEY, index)).collect::<Vec<(i64, usize)>>();
+pub fn mix_with_key(input: &[(i64, bool)]) -> i64 {
+    let mut result = input
+        .iter()
+        .enumerate()
+        .map(|(index, v)| (v.0 * KEY, index))
+        .collect::<Vec<(i64, usize)>>();
 
-    for i in 0..10 {
+    for round in 0..10 {
         for i in 0..result.len() {
             let idx = result.iter().position(|e| e.1 == i).unwrap();
             let value = result[idx].0;
 
-            // println!("moving value: {}", value);
             let mut next_position = idx as i64 + value;
             if next_position > (result.len() - 1) as i64 {
-                next_position = next_position % (result.len() - 1) as i64;
+                next_position %= (result.len() - 1) as i64;
             }
 
             if next_position <= 0 && value != 0 {
-                next_position = result.len() as i64 + (next_position % (result.len() - 1) as i64) - 1;
+                next_position =
+                    result.len() as i64 + (next_position % (result.len() - 1) as i64) - 1;
             }
 
-            // println!("next position: {}", next_position);
+            trace!(
+                Trace,
+                "moving",
+                value = value,
+                from = idx,
+                to = next_position
+            );
 
             result.remove(idx);
             result.insert(next_position as usize, (value, i));
         }
 
-        // println!("");
-        // println!("round {i}");
-        // println!("result: {:?}", &result.iter().map(|r| r.0).collect::<Vec<_>>());
-
+        trace!(
+            Debug,
+            "mixed",
+            round = round,
+            result = result.iter().map(|r| r.0).collect::<Vec<_>>()
+        );
     }
 
     let zero_pos = result.iter().position(|e| e.0 == 0).unwrap();
@@ -92,22 +109,88 @@ pub fn part_2(path: &str) -> i64 {
     let pos_2000 = (zero_pos + 2000) % result.len();
     let pos_3000 = (zero_pos + 3000) % result.len();
 
-    // println!("final result: {:?}", result);
+    trace!(Debug, "zero", position = zero_pos, len = result.len());
     [pos_1000, pos_2000, pos_3000]
         .iter()
         .map(|p| result[*p].0)
         .sum()
 }
 
+// Reference for `mix` and `mix_with_key`: moves every number one place at a
+// time, which is slow but leaves no index arithmetic to get wrong.
+pub fn mix_reference(input: &[(i64, bool)], key: i64, rounds: usize) -> i64 {
+    let values = input.iter().map(|v| v.0 * key).collect::<Vec<_>>();
+    let len = values.len();
+    // original index of the number at each position
+    let mut order = (0..len).collect::<Vec<_>>();
+
+    for _ in 0..rounds {
+        for (i, value) in values.iter().enumerate() {
+            let mut pos = order.iter().position(|&j| j == i).unwrap();
+            // going round the other numbers once gets back to the same place
+            for _ in 0..value.rem_euclid(len as i64 - 1) {
+                let next = (pos + 1) % len;
+                order.swap(pos, next);
+                pos = next;
+            }
+        }
+    }
+
+    let zero_pos = order.iter().position(|&j| values[j] == 0).unwrap();
+    [1000, 2000, 3000]
+        .iter()
+        .map(|offset| values[order[(zero_pos + offset) % len]])
+        .sum()
+}
+
+pub fn part_1(path: &str) -> ParseResult<i64> {
+    Ok(mix(&parse_input(path)?))
+}
+
+pub fn part_2(path: &str) -> ParseResult<i64> {
+    Ok(mix_with_key(&parse_input(path)?))
+}
+
+#[derive(Default)]
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input = Vec<(i64, bool)>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        mix(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        mix_with_key(input).into()
+    }
+}
+
 #[cfg(test)]
 #[test]
 fn test_part_1() {
-    let input = part_1("src/specs/day20");
+    let input = part_1("src/specs/day20").unwrap();
     assert_eq!(input, 3);
 }
 
 #[test]
 fn test_part_2() {
-    let input = part_2("src/specs/day20");
+    let input = part_2("src/specs/day20").unwrap();
     assert_eq!(input, 1623178306);
 }
+
+#[test]
+fn test_empty_input() {
+    assert_eq!(
+        parse_str("").unwrap_err().to_string(),
+        "<input>:1:1: expected a 0 among the numbers, found end of line"
+    );
+    assert_eq!(
+        parse_str("1\n2\n").unwrap_err().to_string(),
+        "<input>:3:1: expected a 0 among the numbers, found end of line"
+    );
+}
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::io::{stdout, Write};
+use crate::error::{from_path, Line, ParseError, ParseResult};
+use crate::input::lines;
+use crate::progress::{Progress, TimedOut};
+use crate::solution::{Answer, Solution};
+use crate::trace;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum Expression {
+pub enum Expression {
     Value(f64),
     Variable(String),
     Add(Box<Expression>, Box<Expression>),
@@ -16,113 +18,237 @@ pub(crate) enum Expression {
 
 use Expression::*;
 
-pub(crate) fn parse_input(path: &str) -> Expression {
-    let file = File::open(path).unwrap();
-    let mut i = 0;
-    let mut result = io::BufReader::new(file)
-        .lines()
-        .map(|s| {
-            let regex = regex::Regex::new(r"(\w+): (\w+) (\+|-|/|\*) (\w+)").unwrap();
-            let value_regex = regex::Regex::new(r"(\w+): (\d+)").unwrap();
-
-            let line = s.unwrap();
-
-            if let Some(x) = regex.captures(&line) {
-                let v1 = Box::new(Variable(x[2].to_string()));
-                let v2 = Box::new(Variable(x[4].to_string()));
-                let name = x[1].to_string();
-                if name == "root" {
-                    return (name.clone(), Compare(v1, v2));
-                }
-                let op = match x[3].to_string().as_str() {
-                    "+" => Add(v1, v2),
-                    "-" => Sub(v1, v2),
-                    "*" => Mul(v1, v2),
-                    "/" => Div(v1, v2),
-                    _ => panic!("unknown op"),
-                };
-
-                return (name.clone(), op);
-            } else {
-                let x = value_regex.captures(&line).unwrap();
-                let name = x[1].to_string();
-                let value = x[2].parse::<f64>().unwrap();
-
-                return (name.clone(), Value(value));
-            }
-        })
-        .collect::<HashMap<String, Expression>>();
+// Solving walks the jobs recursively, deeper ones are refused.
+const MAX_DEPTH: usize = 1000;
+
+pub fn parse_str(input: &str) -> ParseResult<HashMap<String, Expression>> {
+    let regex = regex::Regex::new(r"^(\w+): (\w+) (\+|-|/|\*) (\w+)$").unwrap();
+    let value_regex = regex::Regex::new(r"^(\w+): (\d+)$").unwrap();
+    let mut jobs = HashMap::new();
+    let mut defined = HashMap::new();
+    let mut waits = HashMap::new();
+    let mut references = vec![];
+
+    for line in lines(input).filter(|l| !l.text.is_empty()) {
+        if let Some(x) = regex.captures(line.text) {
+            let v1 = Box::new(Variable(x[2].to_string()));
+            let v2 = Box::new(Variable(x[4].to_string()));
+            let name = x[1].to_string();
+            let op = match &x[3] {
+                "+" => Add(v1, v2),
+                "-" => Sub(v1, v2),
+                "*" => Mul(v1, v2),
+                _ => Div(v1, v2),
+            };
+
+            let waited = [x.get(2).unwrap().as_str(), x.get(4).unwrap().as_str()];
+            references.push((line, waited[0]));
+            references.push((line, waited[1]));
+            defined.insert(x.get(1).unwrap().as_str(), line);
+            waits.insert(x.get(1).unwrap().as_str(), waited);
+            jobs.insert(name, op);
+        } else {
+            let x = line.captures(&value_regex, "name: N or name: aaaa + bbbb")?;
+            let name = x[1].to_string();
+            let value = line.parse::<f64>(x.get(2).unwrap().as_str(), "a number")?;
+
+            defined.insert(x.get(1).unwrap().as_str(), line);
+            jobs.insert(name, Value(value));
+        }
+    }
+
+    if let Some((line, name)) = references.iter().find(|(_, n)| !jobs.contains_key(*n)) {
+        return Err(line.error(name, "the name of another monkey"));
+    }
+
+    if !matches!(
+        jobs.get("root"),
+        Some(Add(..) | Sub(..) | Mul(..) | Div(..))
+    ) {
+        return Err(ParseError::syntax(
+            1,
+            1,
+            "a root monkey doing an operation",
+            "",
+        ));
+    }
+
+    let mut order: Vec<_> = waits.keys().copied().collect();
+    order.sort_by_key(|name| defined[name].number);
+    let mut done = HashSet::new();
+    for name in order {
+        check_waits(name, &waits, &defined, &mut done)?;
+    }
+
+    // Solving copies a job into every job waiting on it, a monkey waited on
+    // twice would double the work at each level.
+    let mut waited_on = HashSet::new();
+    if let Some((line, name)) = references.iter().find(|(_, n)| !waited_on.insert(*n)) {
+        return Err(line.error(name, "a monkey no other monkey waits on"));
+    }
+
+    let mut reached = vec!["root"];
+    let mut found = HashSet::new();
+    while let Some(name) = reached.pop() {
+        found.insert(name);
+        reached.extend(waits.get(name).into_iter().flatten());
+    }
+
+    match defined.get("humn") {
+        Some(line) if !found.contains("humn") => {
+            Err(line.error(&line.text[..4], "a humn monkey root waits on"))
+        }
+        Some(_) => Ok(jobs),
+        None => Err(Line::end(input).error("", "a humn monkey root waits on")),
+    }
+}
+
+// Walks the monkeys a job waits on without recursing, so a cycle or a very
+// deep job is an error rather than an overflowed stack.
+fn check_waits<'a>(
+    name: &'a str,
+    waits: &HashMap<&'a str, [&'a str; 2]>,
+    defined: &HashMap<&'a str, Line>,
+    done: &mut HashSet<&'a str>,
+) -> ParseResult<()> {
+    let mut path = vec![(name, 0)];
+
+    while let Some((current, next)) = path.last().copied() {
+        let Some(waited) = waits.get(current).filter(|_| next < 2) else {
+            done.insert(current);
+            path.pop();
+            continue;
+        };
+
+        path.last_mut().unwrap().1 += 1;
+        let waited = waited[next];
+        if done.contains(waited) {
+            continue;
+        }
+
+        let line = &defined[current];
+        if path.iter().any(|(n, _)| *n == waited) {
+            return Err(line.error(waited, "a monkey that does not wait on itself"));
+        }
+        if path.len() == MAX_DEPTH {
+            return Err(line.error(waited, "a job at most 1000 monkeys deep"));
+        }
+        path.push((waited, 0));
+    }
+
+    Ok(())
+}
+
+pub fn read_jobs(path: &str) -> ParseResult<HashMap<String, Expression>> {
+    from_path(path, parse_str)
+}
+
+pub fn root_value(jobs: &HashMap<String, Expression>) -> f64 {
+    let root = substitute_variable(jobs.get("root").unwrap().clone(), jobs, &vec![]);
+    match root.reduce() {
+        Value(v) => v,
+        e => panic!("root did not reduce to a value: {:?}", e),
+    }
+}
+
+pub fn find_humn(jobs: &HashMap<String, Expression>) -> Option<f64> {
+    find_humn_with(jobs, &Progress::none()).unwrap()
+}
+
+// One step per round of reducing the jobs. None when humn cannot be worked
+// out by undoing root's operations one at a time.
+pub fn find_humn_with(
+    jobs: &HashMap<String, Expression>,
+    progress: &Progress,
+) -> Result<Option<f64>, TimedOut> {
+    let mut result = jobs.clone();
     result.remove("humn");
-    let mut rootOp = result.remove("root").unwrap();
+    let root_op = match result.remove("root") {
+        Some(Add(a, b) | Sub(a, b) | Mul(a, b) | Div(a, b) | Compare(a, b)) => Compare(a, b),
+        _ => return Ok(None),
+    };
 
     let mut prev = result.clone();
+    let mut rounds = 0;
 
     loop {
+        progress.step(rounds, None)?;
         reduce(&mut result);
-
-        if prev != result {
-            prev = result.clone();
-        }
+        rounds += 1;
+        trace!(Debug, "reduced", round = rounds, jobs = result.len());
 
         if prev == result {
             break;
         }
+        prev = result.clone();
     }
 
-    rootOp = substitute_variable(rootOp, &result, &vec!["humn".to_string()]);
-
-    let (left, right) = match rootOp {
-        Compare(a, b) => (a, b),
-        _ => panic!("root op is not compare"),
+    let (left, right) = match substitute_variable(root_op, &result, &vec!["humn".to_string()]) {
+        Compare(a, b) => (a.reduce(), b.reduce()),
+        _ => return Ok(None),
     };
 
-    // println!("{:#?}, {:#?}", left, right);
+    trace!(Debug, "solving", left = left, right = right);
 
-    let left_unboxed = *left.clone();
-    let right_unboxed = *right.clone();
-    let mut hm: HashMap<String, Expression> = HashMap::new();
-    let mut i: f64 = 3441100000000.0;
+    Ok(match (left, right) {
+        (Value(target), e) | (e, Value(target)) => solve(&e, target),
+        _ => None,
+    })
+}
 
-    let mut di = 100 as f64;
+// Walks down the side of the equation that holds humn, undoing one
+// operation at a time until only the variable is left.
+fn solve(e: &Expression, target: f64) -> Option<f64> {
+    match e {
+        Variable(_) => Some(target),
+        Add(a, b) => match (a.as_ref(), b.as_ref()) {
+            (Value(v), x) | (x, Value(v)) => solve(x, target - v),
+            _ => None,
+        },
+        Mul(a, b) => match (a.as_ref(), b.as_ref()) {
+            (Value(v), x) | (x, Value(v)) => solve(x, target / v),
+            _ => None,
+        },
+        Sub(a, b) => match (a.as_ref(), b.as_ref()) {
+            (Value(v), x) => solve(x, v - target),
+            (x, Value(v)) => solve(x, target + v),
+            _ => None,
+        },
+        Div(a, b) => match (a.as_ref(), b.as_ref()) {
+            (Value(v), x) => solve(x, v / target),
+            (x, Value(v)) => solve(x, target * v),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
-    loop {
-        hm.insert("humn".to_string(), Value(i));
-        let mut test = substitute_variable(left_unboxed.clone(), &hm, &vec![]);
-        test = test.reduce();
+pub fn parse_input(path: &str) -> ParseResult<Option<Expression>> {
+    Ok(find_humn(&read_jobs(path)?).map(Value))
+}
 
-        if test.clone() == right_unboxed.clone() {
-            println!("found: {:?}, for {:?}", i, test);
-            return Value(i);
-        }
+#[derive(Default)]
+pub struct Day21;
 
-        if di == 100.0 {
-            match (test, right_unboxed.clone()) {
-                (Value(k), Value(j)) => {
-                    if k == j {
-                        println!("found: {:?}, for {:?} {:?}", i, k, j);
-                        return Value(i);
-                    }
-                    if k <= j {
-                        println!("greater, tried: {:?}, for {:?} {:?}", i, k, j);
-                        di = -1.0;
-                    } else {
-                        println!("less, tried: {:?}, for {:?} {:?}", i, k, j);
-                        i += di as f64;
-                        continue;
-                    }
-                }
+impl Solution for Day21 {
+    type Input = HashMap<String, Expression>;
 
-                _ => {
-                    i += di as f64;
-                    continue;
-                }
-            };
-        } else {
-            println!("di is: {:?}", di);
-            println!("tried: {:?} {:?} {:?}", i, test, right_unboxed.clone());
-            i += di as f64;
-        }
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        (root_value(input) as i64).into()
+    }
 
+    fn part2(&self, input: &Self::Input) -> Answer {
+        self.part2_with(input, &Progress::none())
+    }
+
+    fn part2_with(&self, input: &Self::Input, progress: &Progress) -> Answer {
+        find_humn_with(input, progress)
+            .map(|humn| humn.map_or(Answer::Unsolved, |humn| (humn as i64).into()))
+            .into()
     }
 }
 
@@ -135,9 +261,9 @@ fn replace_variables(hash: &mut HashMap<String, Expression>) {
 
 fn reduce(hash: &mut HashMap<String, Expression>) {
     replace_variables(hash);
-    for (_, v) in hash.iter_mut() {
+    for (name, v) in hash.iter_mut() {
         *v = v.reduce();
-        // println!("reduced {:#?}", v.clone())
+        trace!(Trace, "reduced", name = name, job = v);
     }
 }
 
@@ -189,7 +315,7 @@ fn substitute_variable(
 }
 
 impl Expression {
-    fn reduce(&self) -> Expression {
+    pub fn reduce(&self) -> Expression {
         match self {
             Sub(a, b) => match (a.reduce(), b.reduce()) {
                 (Value(a), Value(b)) => Value(a - b),
@@ -262,7 +388,7 @@ impl Expression {
 
 #[cfg(test)]
 mod tests {
-    use super::Expression::*;
+
     use super::*;
 
     #[test]
@@ -326,9 +452,61 @@ mod tests {
         assert_eq!(a.reduce(), Value(4.5));
     }
 
+    #[test]
+    fn test_root_value() {
+        let jobs = read_jobs("src/specs/day21").unwrap();
+        assert_eq!(root_value(&jobs), 152.0)
+    }
+
     #[test]
     fn test_parse_input() {
-        let inp = parse_input("src/specs/day21");
-        assert_eq!(inp, Value(301.0))
+        let inp = parse_input("src/specs/day21").unwrap();
+        assert_eq!(inp, Some(Value(301.0)))
+    }
+
+    #[test]
+    fn test_invalid_jobs() {
+        let error = |input: &str| parse_str(input).unwrap_err().to_string();
+
+        assert_eq!(
+            error("root: aaaa + humn\naaaa: bbbb * cccc\nbbbb: aaaa + dddd\ncccc: 2\ndddd: 2\nhumn: 1"),
+            "<input>:3:7: expected a monkey that does not wait on itself, found `aaaa`"
+        );
+        assert_eq!(
+            error("root: aaaa + humn\naaaa: cccc * cccc\ncccc: 2\nhumn: 1"),
+            "<input>:2:14: expected a monkey no other monkey waits on, found `cccc`"
+        );
+        assert_eq!(
+            error("root: aaaa + bbbb\naaaa: 2\nbbbb: 3\nhumn: 1"),
+            "<input>:4:1: expected a humn monkey root waits on, found `humn`"
+        );
+        assert_eq!(
+            error("root: aaaa + bbbb\naaaa: 2\nbbbb: 3"),
+            "<input>:4:1: expected a humn monkey root waits on, found end of line"
+        );
+
+        let chain: String = (0..MAX_DEPTH)
+            .map(|n| format!("m{}: m{} + one\n", n, n + 1))
+            .collect();
+        let deep = format!(
+            "root: m0 + humn\n{}m{}: 1\none: 1\nhumn: 1",
+            chain, MAX_DEPTH
+        );
+        assert_eq!(
+            error(&deep),
+            "<input>:1000:7: expected a job at most 1000 monkeys deep, found `m999`"
+        );
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let mut jobs =
+            parse_str("root: aaaa + bbbb\naaaa: humn * cccc\nbbbb: 4\ncccc: 2\nhumn: 1").unwrap();
+        assert_eq!(find_humn(&jobs), Some(2.0));
+
+        let humn = || Box::new(Variable("humn".to_string()));
+        jobs.insert("aaaa".to_string(), Mul(humn(), humn()));
+        assert_eq!(find_humn(&jobs), None);
+        assert_eq!(Day21.part2(&jobs), Answer::Unsolved);
     }
 }
@@ -1,6 +1,7 @@
+use crate::error::{from_path, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
 use std::collections::{HashMap, HashSet};
-use std::env;
-use std::fs::read_to_string;
 
 fn get_priorities() -> HashMap<char, usize> {
     let mut items_with_priorities = (b'a'..=b'z')
@@ -16,15 +17,33 @@ fn get_priorities() -> HashMap<char, usize> {
     items_with_priorities
 }
 
-pub fn calculate_priorities_part1(path: &str) -> usize {
+pub fn parse_str(input: &str) -> ParseResult<Vec<String>> {
+    lines(input)
+        .map(|line| {
+            match line
+                .text
+                .char_indices()
+                .find(|(_, c)| !c.is_ascii_alphabetic())
+            {
+                Some((i, _)) => Err(line.error(&line.text[i..], "an item letter")),
+                None => Ok(line.text.to_string()),
+            }
+        })
+        .collect()
+}
+
+pub fn parse_input(path: &str) -> ParseResult<Vec<String>> {
+    from_path(path, parse_str)
+}
+
+pub fn priorities_part1(rucksacks: &[String]) -> usize {
     let items_with_priorities = get_priorities();
 
-    let file = read_to_string(path).expect("Unable to read file");
-    file.split("\n").into_iter().fold(0, |acc, s| {
+    rucksacks.iter().fold(0, |acc, s| {
         let len = s.len() / 2;
         let hs1: HashSet<char> = HashSet::from_iter(s[0..len].chars());
         let hs2: HashSet<char> = HashSet::from_iter(s[len..].chars());
-        let sum_per_compartment = hs1.intersection(&hs2).into_iter().fold(0, |acc, c| {
+        let sum_per_compartment = hs1.intersection(&hs2).fold(0, |acc, c| {
             *items_with_priorities.get(c).unwrap_or(&0) + acc
         });
 
@@ -32,19 +51,18 @@ pub fn calculate_priorities_part1(path: &str) -> usize {
     })
 }
 
-pub fn calculate_priorities_part2(path: &str) -> usize {
+pub fn priorities_part2(rucksacks: &[String]) -> usize {
     let items_with_priorities = get_priorities();
 
-    let file = read_to_string(path).expect("Unable to read file");
-    let hash_vec = file
-        .split('\n')
+    let hash_vec = rucksacks
+        .iter()
         .map(|s| HashSet::<char>::from_iter(s.chars()))
         .collect::<Vec<HashSet<char>>>();
 
     hash_vec
         .chunks(3)
         .flat_map(|chunk| {
-            chunk.into_iter().fold(HashSet::<char>::new(), |acc, hs| {
+            chunk.iter().fold(HashSet::<char>::new(), |acc, hs| {
                 if acc.is_empty() {
                     hs.clone()
                 } else {
@@ -53,7 +71,34 @@ pub fn calculate_priorities_part2(path: &str) -> usize {
             })
         })
         .map(|c| *items_with_priorities.get(&c).unwrap_or(&0))
-        .fold(0, |acc, i| acc + i)
+        .sum()
+}
+
+pub fn calculate_priorities_part1(path: &str) -> ParseResult<usize> {
+    Ok(priorities_part1(&parse_input(path)?))
+}
+
+pub fn calculate_priorities_part2(path: &str) -> ParseResult<usize> {
+    Ok(priorities_part2(&parse_input(path)?))
+}
+
+#[derive(Default)]
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input = Vec<String>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        priorities_part1(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        priorities_part2(input).into()
+    }
 }
 
 #[cfg(test)]
@@ -63,14 +108,14 @@ mod test {
     #[test]
     fn test_day3_part1() {
         let file = "src/specs/day3";
-        let result = calculate_priorities_part1(file);
+        let result = calculate_priorities_part1(file).unwrap();
         assert_eq!(result, 157);
     }
 
     #[test]
     fn test_day3_part2() {
         let file = "src/specs/day3";
-        let result = calculate_priorities_part2(file);
+        let result = calculate_priorities_part2(file).unwrap();
         assert_eq!(result, 70);
     }
 }
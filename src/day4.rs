@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
-use std::env;
-use std::fs::read_to_string;
+use crate::error::{from_path, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
 
 pub fn fully_overlaps(split: Vec<usize>) -> bool {
     let l1 = split[1] - split[0] + 1;
@@ -28,28 +28,67 @@ pub fn partially_overlaps(split: Vec<usize>) -> bool {
         return true;
     }
 
-    return false;
+    false
 }
 
-pub fn get_intersect_ranges<F>(path: &str, mut checker: F) -> usize
-where
-    F: FnMut(Vec<usize>) -> bool,
-{
-    let file = read_to_string(path).expect("Unable to read file");
-    file.split('\n')
-        .map(|str| {
-            let split: Vec<usize> = str
+pub fn parse_str(input: &str) -> ParseResult<Vec<Vec<usize>>> {
+    lines(input)
+        .map(|line| {
+            let split = line
+                .text
                 .split(',')
                 .flat_map(|s| s.split('-'))
-                .map(|s| s.parse::<usize>().unwrap())
-                .collect();
-
-            checker(split)
+                .map(|s| line.parse::<usize>(s, "a section id"))
+                .collect::<ParseResult<Vec<usize>>>()?;
+            if split.len() != 4 {
+                return Err(line.error(line.text, "two ranges like 2-4,6-8"));
+            }
+            Ok(split)
         })
+        .collect()
+}
+
+pub fn parse_input(path: &str) -> ParseResult<Vec<Vec<usize>>> {
+    from_path(path, parse_str)
+}
+
+pub fn count_overlaps<F>(pairs: &[Vec<usize>], mut checker: F) -> usize
+where
+    F: FnMut(Vec<usize>) -> bool,
+{
+    pairs
+        .iter()
+        .map(|split| checker(split.clone()))
         .filter(|s| *s)
         .count()
 }
 
+pub fn get_intersect_ranges<F>(path: &str, checker: F) -> ParseResult<usize>
+where
+    F: FnMut(Vec<usize>) -> bool,
+{
+    Ok(count_overlaps(&parse_input(path)?, checker))
+}
+
+#[derive(Default)]
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<Vec<usize>>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        count_overlaps(input, fully_overlaps).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        count_overlaps(input, partially_overlaps).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -57,13 +96,14 @@ mod test {
     #[test]
     fn test_day4_part1() {
         let file = "src/specs/day4";
-        let result = get_intersect_ranges(file, fully_overlaps);
+        let result = get_intersect_ranges(file, fully_overlaps).unwrap();
         assert_eq!(result, 2);
     }
 
+    #[test]
     fn test_day4_part2() {
         let file = "src/specs/day4";
-        let result = get_intersect_ranges(file, partially_overlaps);
+        let result = get_intersect_ranges(file, partially_overlaps).unwrap();
         assert_eq!(result, 4);
     }
 }
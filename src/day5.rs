@@ -1,55 +1,71 @@
+use crate::error::{from_path, ParseError, ParseResult};
+use crate::input::paragraphs;
+use crate::solution::{Answer, Solution};
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::env;
-use std::fs::read_to_string;
+use std::collections::HashMap;
 use std::iter;
 
-pub fn get_marker(path: &str, uniq_size: usize) -> i32 {
-    let file = read_to_string(path).expect("Unable to read file");
-    for i in 0..file.len() - uniq_size {
-        let substr = &file[i..i + uniq_size];
-        let u_len = HashSet::<char>::from_iter(substr.chars()).len();
-        if u_len == uniq_size {
-            return (i + uniq_size) as i32;
-        }
-    }
-
-    -10
+#[derive(Debug, Clone)]
+pub struct Crates {
+    pub stacks: HashMap<usize, Vec<char>>,
+    pub moves: Vec<(usize, usize, usize)>,
 }
 
-pub fn parse_input(path: &str, should_reverse: bool) -> String {
-    let file = read_to_string(path).expect("Unable to read file");
-    let (first, second) = file.split_at(file.find("\n\n").unwrap());
-    let mut first_lines = first.split('\n');
-    let numbers = first_lines
-        .next_back()
-        .unwrap()
+pub fn parse_str(input: &str) -> ParseResult<Crates> {
+    let paragraphs = paragraphs(input);
+    let mut first_lines = paragraphs.first().cloned().unwrap_or_default();
+    let numbers_line = first_lines
+        .pop()
+        .ok_or_else(|| ParseError::syntax(1, 1, "a crate diagram", ""))?;
+    let numbers = numbers_line
+        .text
         .split(' ')
-        .filter_map(|s| s.parse::<usize>().ok());
-    let mut crates: HashMap<usize, Vec<char>> =
-        HashMap::from_iter(numbers.zip(iter::repeat(vec![])).into_iter());
+        .filter(|s| !s.is_empty())
+        .map(|s| numbers_line.parse::<usize>(s, "a stack number"))
+        .collect::<ParseResult<Vec<usize>>>()?;
+    let mut stacks: HashMap<usize, Vec<char>> =
+        HashMap::from_iter(numbers.into_iter().zip(iter::repeat(vec![])));
 
     for l in first_lines {
-        for (num, chars) in l.chars().collect::<Vec<char>>().chunks(4).enumerate() {
-            let char = chars[1];
+        for (num, chars) in l.text.chars().collect::<Vec<char>>().chunks(4).enumerate() {
+            let char = *chars.get(1).unwrap_or(&' ');
             if char != ' ' {
-                crates.entry(num + 1).and_modify(|v| v.insert(0, char));
+                stacks.entry(num + 1).and_modify(|v| v.insert(0, char));
             }
         }
     }
 
     let regexp = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
 
-    let commands = second.split('\n').filter_map(|s| {
-        let caps = regexp.captures(s)?;
-        let count = caps.get(1).unwrap().as_str().parse::<usize>().unwrap();
-        let from = caps.get(2).unwrap().as_str().parse::<usize>().unwrap();
-        let to = caps.get(3).unwrap().as_str().parse::<usize>().unwrap();
-        Some((count, from, to))
-    });
+    let moves = paragraphs
+        .iter()
+        .skip(1)
+        .flatten()
+        .map(|l| {
+            let caps = l.captures(&regexp, "move N from A to B")?;
+            let count = l.parse::<usize>(caps.get(1).unwrap().as_str(), "a crate count")?;
+            let stack = |i: usize| {
+                let s = caps.get(i).unwrap().as_str();
+                match l.parse::<usize>(s, "a stack number")? {
+                    n if stacks.contains_key(&n) => Ok(n),
+                    _ => Err(l.error(s, "a stack number from the diagram")),
+                }
+            };
+            Ok((count, stack(2)?, stack(3)?))
+        })
+        .collect::<ParseResult<Vec<_>>>()?;
+
+    Ok(Crates { stacks, moves })
+}
+
+pub fn read_crates(path: &str) -> ParseResult<Crates> {
+    from_path(path, parse_str)
+}
+
+pub fn rearrange(input: &Crates, should_reverse: bool) -> String {
+    let mut crates = input.stacks.clone();
 
-    for c in commands {
-        let (count, from, to) = c;
+    for (count, from, to) in input.moves.iter().cloned() {
         let stack = crates.get(&from).unwrap().clone();
         let (remaining, moved) = stack.split_at(stack.len() - count);
         crates.insert(from, remaining.to_vec());
@@ -68,6 +84,29 @@ pub fn parse_input(path: &str, should_reverse: bool) -> String {
         .join("")
 }
 
+pub fn parse_input(path: &str, should_reverse: bool) -> ParseResult<String> {
+    Ok(rearrange(&read_crates(path)?, should_reverse))
+}
+
+#[derive(Default)]
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Crates;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        rearrange(input, true).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        rearrange(input, false).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -75,7 +114,7 @@ mod test {
     #[test]
     fn test_day5() {
         let file = "src/specs/day5";
-        let result = parse_input(file, true);
+        let result = parse_input(file, true).unwrap();
         assert_eq!(result, "CMZ");
     }
 }
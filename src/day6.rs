@@ -1,18 +1,71 @@
-use std::collections::{HashMap, HashSet};
-use std::env;
-use std::fs::read_to_string;
-
-pub fn get_marker(path: &str, uniq_size: usize) -> i32 {
-    let file = read_to_string(path).expect("Unable to read file");
-    for i in 0..file.len() - uniq_size {
-        let substr = &file[i..i + uniq_size];
-        let u_len = HashSet::<char>::from_iter(substr.chars()).len();
-        if u_len == uniq_size {
-            return (i + uniq_size) as i32;
+use crate::error::{from_path, ParseResult};
+use crate::solution::{Answer, FromParams, Params, Solution};
+use std::collections::HashSet;
+
+// The datastream is the one line of the input.
+pub fn parse_str(input: &str) -> ParseResult<String> {
+    Ok(input.trim_end_matches('\n').to_string())
+}
+
+pub fn parse_input(path: &str) -> ParseResult<String> {
+    from_path(path, parse_str)
+}
+
+// Characters read up to the end of the first `uniq_size` in a row that are
+// all different, None when there are no such characters.
+pub fn find_marker(file: &str, uniq_size: usize) -> Option<usize> {
+    let chars = file.chars().collect::<Vec<_>>();
+    chars
+        .windows(uniq_size)
+        .position(|window| HashSet::<&char>::from_iter(window).len() == uniq_size)
+        .map(|i| i + uniq_size)
+}
+
+pub fn get_marker(path: &str, uniq_size: usize) -> ParseResult<Option<usize>> {
+    Ok(find_marker(&parse_input(path)?, uniq_size))
+}
+
+pub struct Day6 {
+    pub window: usize,
+    pub window_part2: usize,
+}
+
+impl Default for Day6 {
+    fn default() -> Self {
+        Day6 {
+            window: 4,
+            window_part2: 14,
+        }
+    }
+}
+
+impl FromParams for Day6 {
+    fn from_params(params: &Params) -> Result<Self, String> {
+        let day6 = Day6 {
+            window: params.get("window", 4)?,
+            window_part2: params.get("window-part2", 14)?,
+        };
+        if day6.window == 0 || day6.window_part2 == 0 {
+            return Err("a marker is at least 1 character long".to_string());
         }
+        Ok(day6)
     }
+}
+
+impl Solution for Day6 {
+    type Input = String;
 
-    -10
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        find_marker(input, self.window).map_or(Answer::Unsolved, Answer::from)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        find_marker(input, self.window_part2).map_or(Answer::Unsolved, Answer::from)
+    }
 }
 
 #[cfg(test)]
@@ -22,7 +75,22 @@ mod test {
     #[test]
     fn test_day6() {
         let file = "src/specs/day6";
-        let result = get_marker(file, 14);
-        assert_eq!(result, 23);
+        let result = get_marker(file, 14).unwrap();
+        assert_eq!(result, Some(23));
+    }
+
+    #[test]
+    fn test_markers() {
+        // the last window counts, and characters are not bytes
+        assert_eq!(find_marker("aabc", 3), Some(4));
+        assert_eq!(find_marker("ééab", 3), Some(4));
+        assert_eq!(find_marker("aaaa", 2), None);
+        assert_eq!(find_marker("ab", 4), None);
+        assert_eq!(find_marker("", 1), None);
+
+        let mut params = Params::new();
+        params.set("window-part2", "0");
+        assert!(Day6::from_params(&params).is_err());
+        assert_eq!(Day6::default().part1(&"abab".to_string()), Answer::Unsolved);
     }
 }
@@ -1,8 +1,8 @@
+use crate::error::{from_path, Line, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
+use crate::trace;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::env;
-use std::fs::read_to_string;
-use std::iter;
 
 #[derive(Debug, Clone)]
 struct File<'a> {
@@ -11,7 +11,7 @@ struct File<'a> {
 }
 
 impl<'a> File<'a> {
-    fn new(size: usize, name: &str) -> File {
+    fn new(size: usize, name: &'a str) -> File<'a> {
         File { size, name }
     }
 }
@@ -23,12 +23,24 @@ struct Directory<'a> {
     directories: Vec<Directory<'a>>,
 }
 
+impl std::fmt::Display for File<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.size, self.name)
+    }
+}
+
 impl std::fmt::Display for Directory<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "\nDirectory {{ name: {}, files: {:?}, directories: {:?} }}",
-            self.name, self.files, self.directories
+            "\nDirectory {{ name: {}, files: [{}], directories: {:?} }}",
+            self.name,
+            self.files
+                .iter()
+                .map(|file| file.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.directories
         )
     }
 }
@@ -57,74 +69,109 @@ impl<'a> Directory<'a> {
     }
 }
 
-pub fn parse_input(path: &str) -> Vec<usize> {
-    let content = read_to_string(path).unwrap();
-    let lines = content.lines().collect::<Vec<_>>();
+const MAX_DEPTH: usize = 1000;
+
+pub fn parse_str(input: &str) -> ParseResult<Vec<usize>> {
     let mut dirs: Vec<Directory> = vec![];
     let mut sizes: Vec<_> = vec![];
-    for l in &lines {
-        let re = Regex::new(r"\$ (\w+) (.*)").unwrap();
-        let caps = re.captures(l);
+    let re = Regex::new(r"^\$ (\w+) ?(.*)$").unwrap();
+    let file_re = Regex::new(r"^(\d+) (.+)$").unwrap();
+
+    for l in lines(input) {
+        let caps = re.captures(l.text);
+
         match caps {
             Some(c) => {
                 let cmd = c.get(1).unwrap().as_str();
                 let args = c.get(2).unwrap().as_str();
-                if cmd == "cd" && args != ".." {
-                    let dir = Directory::new(args);
-                    dirs.push(dir);
-                }
-                if cmd == "cd" && args == ".." {
-                    let prev = dirs.pop().unwrap();
-                    let size = prev.size();
-                    dirs.last_mut().unwrap().add_directory(prev);
-                    sizes.push(size);
+                match (cmd, args) {
+                    ("ls", _) => {}
+                    ("cd", "..") if dirs.len() > 1 => {
+                        let prev = dirs.pop().unwrap();
+                        let size = prev.size();
+                        dirs.last_mut().unwrap().add_directory(prev);
+                        sizes.push(size);
+                    }
+                    ("cd", "..") => return Err(l.error(args, "a directory to leave")),
+                    // sizes add up recursively, deeper trees are refused
+                    ("cd", _) if dirs.len() == MAX_DEPTH => {
+                        return Err(l.error(args, "a directory at most 1000 deep"))
+                    }
+                    ("cd", _) => dirs.push(Directory::new(args)),
+                    _ => return Err(l.error(cmd, "cd or ls")),
                 }
             }
+            None if l.text.starts_with("dir ") => {}
             None => {
-                let file = Regex::new(r"(\d+) ").unwrap();
-                let caps = file.captures(l);
-                match caps {
-                    Some(c) => {
-                        let size = c.get(1).unwrap().as_str().parse::<usize>().unwrap();
-                        let file = File::new(size, "test");
-                        let prev = dirs.last_mut().unwrap();
-                        let name = prev.name;
-                        prev.add_file(file);
-                    }
-                    None => {}
-                }
+                let c = l.captures(&file_re, "a command or a directory listing")?;
+                let size = l.parse::<usize>(c.get(1).unwrap().as_str(), "a file size")?;
+                let file = File::new(size, c.get(2).unwrap().as_str());
+                dirs.last_mut()
+                    .ok_or_else(|| l.error(l.text, "cd before listing files"))?
+                    .add_file(file);
             }
         }
     }
 
-    while dirs.len() > 0 {
-        let prev = dirs.pop().unwrap();
+    if dirs.is_empty() {
+        return Err(Line::end(input).error("", "a `$ cd` into the first directory"));
+    }
+    while let Some(prev) = dirs.pop() {
         let prev_size = prev.size();
         sizes.push(prev_size);
 
-        if dirs.len() > 0 {
+        trace!(Debug, "directory", name = prev.name, size = prev_size);
+
+        if !dirs.is_empty() {
             dirs.last_mut().unwrap().add_directory(prev);
         }
-
-        // println!("dir {} has size {} and dirs {:?}", name, prev_size, names);
     }
 
-    sizes
+    Ok(sizes)
 }
 
-pub fn get_smallest_sum(file: &str) -> usize {
-    let sizes = parse_input(file);
+pub fn parse_input(path: &str) -> ParseResult<Vec<usize>> {
+    from_path(path, parse_str)
+}
+
+pub fn smallest_sum(sizes: &[usize]) -> usize {
     sizes.iter().filter(|&x| *x < 100000).sum()
 }
 
-pub fn get_biggest_sum(file: &str) -> usize {
-    let sizes = parse_input(file);
-    let total_space = 70000000;
-    let min_amount = 30000000;
+pub fn biggest_sum(sizes: &[usize]) -> usize {
+    let total_space: usize = 70000000;
+    let min_amount: usize = 30000000;
     let root_size = sizes.last().unwrap();
-    let remaining_space = total_space - root_size;
-    let delta = min_amount - remaining_space;
-    sizes.iter().filter(|&x| *x >= delta).min().unwrap().clone()
+    let remaining_space = total_space.saturating_sub(*root_size);
+    let delta = min_amount.saturating_sub(remaining_space);
+    *sizes.iter().filter(|&x| *x >= delta).min().unwrap()
+}
+
+pub fn get_smallest_sum(file: &str) -> ParseResult<usize> {
+    Ok(smallest_sum(&parse_input(file)?))
+}
+
+pub fn get_biggest_sum(file: &str) -> ParseResult<usize> {
+    Ok(biggest_sum(&parse_input(file)?))
+}
+
+#[derive(Default)]
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = Vec<usize>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        smallest_sum(input).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        biggest_sum(input).into()
+    }
 }
 
 #[cfg(test)]
@@ -151,13 +198,35 @@ mod test {
     #[test]
     fn test_day5_part1() {
         let file = "src/specs/day7";
-        let result = get_smallest_sum(file);
+        let result = get_smallest_sum(file).unwrap();
         assert_eq!(result, 95437);
     }
 
+    #[test]
     fn test_day5_part2() {
         let file = "src/specs/day7";
-        let result = get_biggest_sum(file);
+        let result = get_biggest_sum(file).unwrap();
         assert_eq!(result, 24933642);
     }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(
+            parse_str("").unwrap_err().to_string(),
+            "<input>:1:1: expected a `$ cd` into the first directory, found end of line"
+        );
+        // plenty of space already
+        let sizes = parse_str("$ cd /\n$ ls\n100 a\n").unwrap();
+        assert_eq!(biggest_sum(&sizes), 100);
+    }
+
+    #[test]
+    fn test_depth() {
+        let deep = |depth| "$ cd a\n".repeat(depth) + "$ ls\n100 a\n";
+        assert_eq!(parse_str(&deep(MAX_DEPTH)).unwrap().len(), MAX_DEPTH);
+        assert_eq!(
+            parse_str(&deep(100_000)).unwrap_err().to_string(),
+            "<input>:1001:6: expected a directory at most 1000 deep, found `a`"
+        );
+    }
 }
@@ -1,183 +1,85 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
-
-pub fn parse_input(path: &str) -> Vec<Vec<usize>> {
-    let file = File::open(path).expect("Unable to open file");
-    io::BufReader::new(file)
-        .lines()
-        .map(|s| {
-            s.unwrap()
-                .split("")
-                .into_iter()
-                .filter_map(|s| s.parse::<usize>().ok())
-                .collect()
-        })
-        .collect()
-}
-
-pub fn get_score(forest: &Vec<Vec<usize>>, (i, j): (usize, usize)) -> usize {
-    let place_for_the_house = forest[i][j];
-    let width = forest[0].len();
-    let height = forest.len();
-
-    let mut ts = 0;
-    let mut bs = 0;
-    let mut ls = 0;
-    let mut rs = 0;
-
-    // top score
-    let mut row = i as i32 - 1;
-
-    loop {
-        if row < 0 {
-            break;
-        }
-
-        let current = forest[row as usize][j];
-        if current < place_for_the_house {
-            ts += 1;
-        } else {
-            ts += 1;
-            break;
-        }
-
-        row -= 1;
-    }
-
-    row = i as i32 + 1;
+use crate::error::{from_path, ParseResult};
+use crate::geometry::Point;
+use crate::grid::{Grid, NEIGHBOURS4};
+use crate::solution::{Answer, Solution};
 
-    loop {
-        if row > height as i32 - 1 {
-            break;
-        }
-
-        let current = forest[row as usize][j];
-        if current < place_for_the_house {
-            bs += 1;
-        } else {
-            bs += 1;
-            break;
-        }
-
-        row += 1;
-    }
-
-    // left score
-    let mut col = j as i32 - 1;
-    loop {
-        if col < 0 {
-            break;
-        }
-
-        let current = forest[i][col as usize];
-        if current < place_for_the_house {
-            ls += 1;
-        } else {
-            ls += 1;
-            break;
-        }
-
-        col -= 1;
-    }
+pub type Forest = Grid<usize>;
 
-    // right score
-
-    col = j as i32 + 1;
-    loop {
-        if col > width as i32 - 1 {
-            break;
-        }
-
-        let current = forest[i][col as usize];
-        // println!("current: {}, i, col: ({}, {})", current, i, col);
-        if current < place_for_the_house {
-            rs += 1;
-        } else {
-            rs += 1;
-            break;
-        }
-
-        col += 1;
-    }
+pub fn parse_str(input: &str) -> ParseResult<Forest> {
+    Grid::parse(input, "a tree height", |_, c| {
+        c.to_digit(10).map(|d| d as usize)
+    })
+}
 
-    ts * bs * ls * rs
+pub fn parse_input(path: &str) -> ParseResult<Forest> {
+    from_path(path, parse_str)
 }
 
-pub fn count_edge_trees(path: &str) -> usize {
-    let forest = parse_input(path);
-    let mut visible_top = (0..forest[0].len()).map(|i| (0, i)).collect::<Vec<_>>();
-    let mut visible_left = (0..forest.len()).map(|i| (i, 0)).collect::<Vec<_>>();
-    let mut visible_right = (0..forest.len())
-        .map(|i| (i, forest[0].len() - 1))
-        .collect::<Vec<_>>();
-    let mut visible_bottom = (0..forest[0].len())
-        .map(|i| (forest.len() - 1, i))
-        .collect::<Vec<_>>();
-
-    let mut highest_top = forest[0].clone();
-    let mut highest_left = forest.iter().map(|v| v[0]).collect::<Vec<_>>();
-    let mut highest_right = forest
+pub fn get_score(forest: &Forest, (i, j): (usize, usize)) -> usize {
+    let place_for_the_house = Point::new(j as i64, i as i64);
+    let height = forest[place_for_the_house];
+
+    // trees seen in each direction, up to and including the first one that
+    // is not lower than the house
+    NEIGHBOURS4
         .iter()
-        .map(|v| v[forest[0].len() - 1])
-        .collect::<Vec<_>>();
-    let mut highest_bottom = forest.last().unwrap().clone();
-
-    for i in 1..forest.len() - 1 {
-        for j in 1..forest[0].len() - 1 {
-            let current = forest[i][j];
-            if current > highest_top[j] {
-                visible_top.push((i, j));
-                highest_top[j] = current
+        .map(|step| {
+            let mut seen = 0;
+            for (_, tree) in forest.ray(place_for_the_house, *step) {
+                seen += 1;
+                if *tree >= height {
+                    break;
+                }
             }
+            seen
+        })
+        .product()
+}
 
-            if current > highest_left[i] {
-                visible_left.push((i, j));
-                highest_left[i] = current;
-            }
-        }
-    }
+pub fn visible_trees(forest: &Forest) -> usize {
+    forest
+        .iter()
+        .filter(|(pos, height)| {
+            NEIGHBOURS4
+                .iter()
+                .any(|step| forest.ray(*pos, *step).all(|(_, tree)| tree < height))
+        })
+        .count()
+}
 
-    for i in (1..forest.len() - 1).into_iter().rev() {
-        for j in (1..forest[0].len() - 1).into_iter().rev() {
-            let current = forest[i][j];
-            if current > highest_bottom[j] {
-                visible_bottom.push((i, j));
+pub fn scenic_score(forest: &Forest) -> usize {
+    forest
+        .iter()
+        .map(|(pos, _)| get_score(forest, (pos.y as usize, pos.x as usize)))
+        .max()
+        .unwrap_or(0)
+}
 
-                highest_bottom[j] = current
-            }
+pub fn count_edge_trees(path: &str) -> ParseResult<usize> {
+    Ok(visible_trees(&parse_input(path)?))
+}
 
-            if current > highest_right[i] {
-                visible_right.push((i, j));
-                highest_right[i] = current;
-            }
-        }
-    }
+pub fn get_scenic_score(path: &str) -> ParseResult<usize> {
+    Ok(scenic_score(&parse_input(path)?))
+}
 
-    let mut visible = vec![];
-    visible.append(&mut visible_top);
-    visible.append(&mut visible_left);
-    visible.append(&mut visible_right);
-    visible.append(&mut visible_bottom);
+#[derive(Default)]
+pub struct Day8;
 
-    let result = HashSet::<(usize, usize)>::from_iter(visible);
+impl Solution for Day8 {
+    type Input = Forest;
 
-    result.len()
-}
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
 
-pub fn get_scenic_score(path: &str) -> usize {
-    let mut max = 0;
-    let parsed = parse_input(path);
-    for i in 1..parsed.len() - 1 {
-        for j in 1..parsed[0].len() - 1 {
-            let score = get_score(&parsed, (i, j));
-            if score > max {
-                max = score;
-            }
-        }
+    fn part1(&self, input: &Self::Input) -> Answer {
+        visible_trees(input).into()
     }
 
-    max
+    fn part2(&self, input: &Self::Input) -> Answer {
+        scenic_score(input).into()
+    }
 }
 
 #[cfg(test)]
@@ -186,14 +88,14 @@ mod test {
     #[test]
     fn test_day8_part1() {
         let file = "src/specs/day8";
-        let result = count_edge_trees(file);
+        let result = count_edge_trees(file).unwrap();
         assert_eq!(result, 21);
     }
 
     #[test]
     fn test_get_score() {
         let file = "src/specs/day8";
-        let result = parse_input(file);
+        let result = parse_input(file).unwrap();
         assert_eq!(get_score(&result, (1, 1)), 1);
         assert_eq!(get_score(&result, (1, 2)), 4);
         assert_eq!(get_score(&result, (3, 2)), 8);
@@ -203,7 +105,24 @@ mod test {
     #[test]
     fn test_day8_part2() {
         let file = "src/specs/day8";
-        let result = get_scenic_score(file);
+        let result = get_scenic_score(file).unwrap();
         assert_eq!(result, 8);
     }
+
+    #[test]
+    fn test_parse_str_and_reader() {
+        let spec = std::fs::read_to_string("src/specs/day8").unwrap();
+        let solution = Day8;
+        let from_str = solution.parse_str(&spec).unwrap();
+        let from_reader = solution.parse_reader(spec.as_bytes()).unwrap();
+        assert_eq!(from_str, parse_input("src/specs/day8").unwrap());
+        assert_eq!(from_str, from_reader);
+        assert_eq!(solution.part1(&from_str), Answer::Number(21));
+
+        let error = solution.parse_str("303\n2x5\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "<input>:2:2: expected a tree height, found `x5`"
+        );
+    }
 }
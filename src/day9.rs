@@ -1,18 +1,15 @@
+use crate::error::{from_path, ParseResult};
+use crate::frames::{Animate, Frame, FrameSink};
+use crate::geometry::{self, BBox};
+use crate::input::lines;
+use crate::simulation::{Simulate, Simulation};
+use crate::solution::{Answer, Part, Solution};
+use crate::trace;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fmt::{Display, Error, Formatter};
+use std::io;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
-impl Point {
-    fn new(x: i32, y: i32) -> Point {
-        Point { x, y }
-    }
-}
+type Point = geometry::Point<i32>;
 
 struct Rope {
     head: Point,
@@ -36,21 +33,18 @@ impl Rope {
         } else {
             self.tail[segment - 1]
         };
-        let dx = prev.x - current.x;
-        let dy = prev.y - current.y;
-
-        if dx.abs() > 1 || dy.abs() > 1 || dx.abs() + dy.abs() > 2 {
-            let x_step = dx.signum();
-            let y_step = dy.signum();
-
-            self.tail[segment].x += x_step;
-            self.tail[segment].y += y_step;
+        // a segment only moves once it no longer touches the previous one
+        if prev.chebyshev(&current) > 1 {
+            self.tail[segment] += (prev - current).signum();
         }
 
-        // println!("current: {:?}", current);
-        // println!("prev: {:?}", prev);
-        // println!("segments {:?}", self.tail);
-        // println!("");
+        trace!(
+            Trace,
+            "segment",
+            index = segment,
+            prev = prev,
+            moved_to = self.tail[segment]
+        );
     }
 
     fn adjust_tail(&mut self) {
@@ -62,11 +56,34 @@ impl Rope {
     fn log_tail(&mut self) {
         let last = self.tail_path.last().unwrap();
         let last_segment = self.tail.last().unwrap();
-        if (last.x != last_segment.x) || (last.y != last_segment.y) {
-            self.tail_path.push(last_segment.clone());
+        if last != last_segment {
+            self.tail_path.push(*last_segment);
         };
     }
 
+    // y grows up, H is the head, then the segments from 1 (T with a single
+    // segment), s the start and # where the tail has been.
+    fn render(&self, start: Point, bbox: BBox<i32>) -> String {
+        let visited = self.tail_path.iter().collect::<HashSet<_>>();
+        let mut out = String::new();
+        for y in (bbox.min.y..=bbox.max.y).rev() {
+            for x in bbox.min.x..=bbox.max.x {
+                let point = Point::new(x, y);
+                let segment = self.tail.iter().position(|s| *s == point);
+                out.push(match segment {
+                    _ if point == self.head => 'H',
+                    Some(_) if self.tail.len() == 1 => 'T',
+                    Some(i) => char::from_digit(i as u32 + 1, 36).unwrap(),
+                    None if point == start => 's',
+                    None if visited.contains(&point) => '#',
+                    None => '.',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
     fn move_head(&mut self, (dx, dy): (i32, i32)) {
         let x_step = dx.signum();
         let y_step = dy.signum();
@@ -85,8 +102,7 @@ impl Rope {
 
             steps += 1;
 
-            // println!("head: {:?}", self.head);
-            // println!("tail: {:?}", self.tail);
+            trace!(Debug, "moved head", head = self.head, tail = self.tail);
         }
 
         steps = 0;
@@ -102,52 +118,161 @@ impl Rope {
 
             steps += 1;
 
-            // println!("head: {:?}", self.head);
-            // println!("tail: {:?}", self.tail);
+            trace!(Debug, "moved head", head = self.head, tail = self.tail);
         }
     }
 }
 
-pub fn parse_input(path: &str, segments: usize) -> usize {
-    let file = File::open(path).expect("Unable to open file");
-    let mut rope = Rope::new(Point::new(0, 0), segments);
+pub fn parse_str(input: &str) -> ParseResult<Vec<(i32, i32)>> {
+    lines(input)
+        .map(|line| {
+            let (direction, distance) = line
+                .text
+                .split_once(' ')
+                .ok_or_else(|| line.error(line.text, "a direction and a distance"))?;
+            let distance = line.parse::<i32>(distance.trim(), "a distance")?;
+
+            match direction {
+                "U" => Ok((0, distance)),
+                "D" => Ok((0, -distance)),
+                "L" => Ok((-distance, 0)),
+                "R" => Ok((distance, 0)),
+                _ => Err(line.error(direction, "U, D, L or R")),
+            }
+        })
+        .collect()
+}
 
-    for l in io::BufReader::new(file).lines() {
-        let line = l.unwrap();
-        let line = line.split("").collect::<Vec<&str>>();
-        let direction = line[1];
-        let distance = line[3..].join("").trim().parse::<i32>().unwrap();
-
-        // println!("direction: {}", direction);
-        // println!("distance: {}", distance);
-
-        let (dx, dy) = match direction {
-            "U" => (0, distance),
-            "D" => (0, -distance),
-            "L" => (-distance, 0),
-            "R" => (distance, 0),
-            _ => panic!("Unknown direction"),
-        };
+pub fn read_moves(path: &str) -> ParseResult<Vec<(i32, i32)>> {
+    from_path(path, parse_str)
+}
+
+pub fn tail_positions(moves: &[(i32, i32)], segments: usize) -> usize {
+    let mut rope = Rope::new(Point::new(0, 0), segments);
 
-        rope.move_head((dx, dy));
+    for m in moves {
+        rope.move_head(*m);
     }
 
-    // println!("tail: {:?}", rope.tail_path);
     let positions = rope.tail_path.iter().collect::<HashSet<_>>();
-    // for i in (0..=100).rev() {
-    //     for j in 0..=100 {
-    //         if positions.contains(&Point::new(j, i)) {
-    //             print!("#");
-    //         } else {
-    //             print!(".");
-    //         }
-    //     }
-
-    //     println!("");
-    // }
     positions.len()
 }
 
+// One frame per move, the view grows to fit everywhere the head has been.
+pub fn animate_rope(
+    moves: &[(i32, i32)],
+    segments: usize,
+    sink: &mut dyn FrameSink,
+) -> io::Result<()> {
+    let start = Point::new(0, 0);
+    let mut rope = Rope::new(start, segments);
+    let mut bbox = BBox::around(start);
+
+    for m in moves {
+        rope.move_head(*m);
+        bbox = bbox.extend(rope.head);
+        sink.frame(&Frame::from_text(&rope.render(start, bbox)))?;
+    }
+    sink.finish()
+}
+
+// The rope one move at a time, the view grows like the animation's.
+pub struct RopeWalk {
+    rope: Rope,
+    moves: Vec<(i32, i32)>,
+    done: usize,
+    start: Point,
+    bbox: BBox<i32>,
+}
+
+impl RopeWalk {
+    pub fn new(moves: &[(i32, i32)], segments: usize) -> RopeWalk {
+        let start = Point::new(0, 0);
+        RopeWalk {
+            rope: Rope::new(start, segments),
+            moves: moves.to_vec(),
+            done: 0,
+            start,
+            bbox: BBox::around(start),
+        }
+    }
+}
+
+impl Simulation for RopeWalk {
+    fn step(&mut self) -> Option<String> {
+        let m = *self.moves.get(self.done)?;
+        self.rope.move_head(m);
+        self.bbox = self.bbox.extend(self.rope.head);
+        self.done += 1;
+        Some(format!(
+            "move {} of {} by {},{}: head at {}, tail at {}",
+            self.done,
+            self.moves.len(),
+            m.0,
+            m.1,
+            self.rope.head,
+            self.rope.tail.last().unwrap()
+        ))
+    }
+}
+
+impl Display for RopeWalk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let visited = self.rope.tail_path.iter().collect::<HashSet<_>>();
+        writeln!(
+            f,
+            "{} of {} moves, the tail visited {} positions",
+            self.done,
+            self.moves.len(),
+            visited.len()
+        )?;
+        write!(f, "{}", self.rope.render(self.start, self.bbox))
+    }
+}
+
+pub fn parse_input(path: &str, segments: usize) -> ParseResult<usize> {
+    Ok(tail_positions(&read_moves(path)?, segments))
+}
+
+#[derive(Default)]
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<(i32, i32)>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer {
+        tail_positions(input, 1).into()
+    }
+
+    fn part2(&self, input: &Self::Input) -> Answer {
+        tail_positions(input, 9).into()
+    }
+}
+
+impl Animate for Day9 {
+    fn animate(&self, input: &Self::Input, part: Part, sink: &mut dyn FrameSink) -> io::Result<()> {
+        let segments = match part {
+            Part::One => 1,
+            Part::Two => 9,
+        };
+        animate_rope(input, segments, sink)
+    }
+}
+
+impl Simulate for Day9 {
+    fn simulate(&self, input: &Self::Input, part: Part) -> Box<dyn Simulation> {
+        let segments = match part {
+            Part::One => 1,
+            Part::Two => 9,
+        };
+        Box::new(RopeWalk::new(input, segments))
+    }
+}
+
 #[cfg(test)]
 #[test]
 fn test_move_up() {
@@ -192,10 +317,22 @@ fn test_move_left() {
 
 #[test]
 fn test_parse_input_part1() {
-    assert_eq!(parse_input("src/specs/day9", 1), 13);
+    assert_eq!(parse_input("src/specs/day9", 1).unwrap(), 13);
 }
 
 #[test]
 fn test_parse_input_part2() {
-    assert_eq!(parse_input("src/specs/day9_1", 9), 36);
+    assert_eq!(parse_input("src/specs/day9_1", 9).unwrap(), 36);
+}
+
+#[test]
+fn test_animate_rope() {
+    let moves = read_moves("src/specs/day9").unwrap();
+    let mut frames = vec![];
+    animate_rope(&moves, 1, &mut frames).unwrap();
+    assert_eq!(frames.len(), moves.len());
+    assert_eq!(
+        frames.last().unwrap().to_text(),
+        "..##..\n...##.\n.TH##.\n....#.\ns###.."
+    );
 }
@@ -0,0 +1,295 @@
+use crate::generate::{self, Generator, Rng};
+use crate::parallel::panic_message;
+use crate::{day11, day11_2, day15, day17, day20};
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+// Differential testing of the optimised solvers against the brute force
+// references kept next to them. Each property generates inputs, runs both
+// and when they disagree shrinks the input down to a small one that still
+// shows the difference.
+//
+// Not covered: day17's loop detection. It only records where the tower
+// starts repeating, no answer uses it while part 2 is unsolved, so there is
+// nothing to diff yet. The day17 property checks `tower_height` alone.
+
+pub struct Property {
+    pub name: &'static str,
+    pub generate: Generator,
+    // None when both agree. Inputs outside of what the puzzle promises
+    // (no zero for day20, say) also give None, so shrinking skips them.
+    pub check: fn(&str) -> Option<String>,
+}
+
+fn differ<T: PartialEq + Debug>(what: &str, got: T, expected: T) -> Option<String> {
+    if got == expected {
+        None
+    } else {
+        Some(format!("{}: got {:?}, reference {:?}", what, got, expected))
+    }
+}
+
+// Side of the square day15 searches, small enough to check every position.
+const AREA: i32 = 40;
+
+fn sensors(rng: &mut Rng, size: usize) -> String {
+    generate::sensors(rng, size, AREA as i64).0
+}
+
+fn day11(input: &str) -> Option<String> {
+    let monkeys = day11::parse_str(input).ok()?;
+    let expected = day11::monkey_business_reference(&monkeys, 20, true)?;
+    differ("part 1", day11::monkey_business(&monkeys, 20), expected)
+}
+
+fn day11_2(input: &str) -> Option<String> {
+    let monkeys = day11::parse_str(input).ok()?;
+    let expected = day11::monkey_business_reference(&monkeys, 1000, false)?;
+    differ(
+        "1000 rounds",
+        day11_2::monkey_business(&monkeys, 1000),
+        expected,
+    )
+}
+
+fn day15_row(input: &str) -> Option<String> {
+    let sensors = day15::parse_str(input).ok()?;
+    (-5..AREA + 5).find_map(|row| {
+        differ(
+            &format!("row {}", row),
+            day15::count_unreachable(&sensors, row),
+            day15::count_unreachable_reference(&sensors, row),
+        )
+    })
+}
+
+fn day15_distress(input: &str) -> Option<String> {
+    let sensors = day15::parse_str(input).ok()?;
+    let (min, max) = (day15::Point::new(0, 0), day15::Point::new(AREA, AREA));
+    let uncovered = day15::uncovered_reference(&sensors, min, max);
+    if uncovered.is_empty() {
+        return None;
+    }
+    let frequency = day15::tuning_frequency(&sensors, min, max);
+    let found = day15::Point::new((frequency / 4000000) as i32, (frequency % 4000000) as i32);
+    if uncovered.contains(&found) {
+        None
+    } else {
+        Some(format!(
+            "got {} which is covered, {} positions are not, the first at {}",
+            found,
+            uncovered.len(),
+            uncovered[0]
+        ))
+    }
+}
+
+// Heights only, see the note at the top about the loop detection.
+fn day17(input: &str) -> Option<String> {
+    let pattern = day17::parse_str(input).ok()?;
+    if pattern.is_empty() {
+        return None;
+    }
+    [1, 5, 20, 100, 300].iter().find_map(|rocks| {
+        differ(
+            &format!("{} rocks", rocks),
+            day17::tower_height(&pattern, *rocks),
+            day17::tower_height_reference(&pattern, *rocks),
+        )
+    })
+}
+
+fn day20(input: &str) -> Option<String> {
+    let numbers = day20::parse_str(input).ok()?;
+    if numbers.len() < 2 || numbers.iter().filter(|n| n.0 == 0).count() != 1 {
+        return None;
+    }
+    differ(
+        "part 1",
+        day20::mix(&numbers),
+        day20::mix_reference(&numbers, 1, 1),
+    )
+    .or_else(|| {
+        differ(
+            "part 2",
+            day20::mix_with_key(&numbers),
+            day20::mix_reference(&numbers, day20::KEY, 10),
+        )
+    })
+}
+
+pub const PROPERTIES: [Property; 6] = [
+    Property {
+        name: "day11",
+        generate: generate::day11,
+        check: day11,
+    },
+    Property {
+        name: "day11_2",
+        generate: generate::day11,
+        check: day11_2,
+    },
+    Property {
+        name: "day15_row",
+        generate: sensors,
+        check: day15_row,
+    },
+    Property {
+        name: "day15_distress",
+        generate: sensors,
+        check: day15_distress,
+    },
+    Property {
+        name: "day17",
+        generate: generate::day17,
+        check: day17,
+    },
+    Property {
+        name: "day20",
+        generate: generate::day20,
+        check: day20,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Property> {
+    PROPERTIES.iter().find(|p| p.name == name)
+}
+
+// A panicking solver disagrees as much as a wrong one.
+fn failure(check: fn(&str) -> Option<String>, input: &str) -> Option<String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| check(input))) {
+        Ok(result) => result,
+        Err(e) => Some(format!("panicked: {}", panic_message(e.as_ref()))),
+    }
+}
+
+// Drops one piece at a time for as long as the input keeps failing.
+fn drop_pieces(
+    check: fn(&str) -> Option<String>,
+    mut pieces: Vec<String>,
+    join: impl Fn(&[String]) -> String,
+) -> Vec<String> {
+    let mut i = 0;
+    while i < pieces.len() {
+        let mut candidate = pieces.clone();
+        candidate.remove(i);
+        if failure(check, &join(&candidate)).is_some() {
+            pieces = candidate;
+        } else {
+            i += 1;
+        }
+    }
+    pieces
+}
+
+// Smallest input still failing `check` found by removing paragraphs, then
+// lines, then characters of single line inputs.
+pub fn shrink(check: fn(&str) -> Option<String>, input: &str) -> String {
+    let paragraphs = input.split("\n\n").map(|p| p.to_string()).collect();
+    let input = drop_pieces(check, paragraphs, |p| p.join("\n\n")).join("\n\n");
+
+    let lines = input.lines().map(|l| format!("{}\n", l)).collect();
+    let input = drop_pieces(check, lines, |l| l.concat()).concat();
+
+    if input.lines().count() != 1 {
+        return input;
+    }
+    let chars = input.trim_end().chars().map(|c| c.to_string()).collect();
+    format!(
+        "{}\n",
+        drop_pieces(check, chars, |c| format!("{}\n", c.concat())).concat()
+    )
+}
+
+#[derive(Debug)]
+pub struct Failure {
+    pub property: &'static str,
+    pub seed: u64,
+    pub size: usize,
+    pub detail: String,
+    pub input: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "{} disagrees with its reference (seed {}, size {}), {}\nsmallest input:\n{}",
+            self.property, self.seed, self.size, self.detail, self.input
+        )
+    }
+}
+
+// Tries every seed at every size, stopping at the first disagreement.
+pub fn run(property: &Property, seeds: Range<u64>, sizes: &[usize]) -> Result<(), Failure> {
+    for seed in seeds {
+        for size in sizes {
+            let input = (property.generate)(&mut Rng::new(seed), *size);
+            if failure(property.check, &input).is_none() {
+                continue;
+            }
+            let input = shrink(property.check, &input);
+            return Err(Failure {
+                property: property.name,
+                seed,
+                size: *size,
+                detail: failure(property.check, &input).unwrap(),
+                input,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_agrees(name: &str, seeds: Range<u64>, sizes: &[usize]) {
+        if let Err(failure) = run(find(name).unwrap(), seeds, sizes) {
+            panic!("{}", failure);
+        }
+    }
+
+    #[test]
+    fn test_shrink() {
+        // fails as long as there is a 7 left and at least two numbers
+        let check = |input: &str| {
+            let has_seven = input.lines().any(|l| l.contains('7'));
+            (has_seven && input.lines().count() > 1).then(|| "seven".to_string())
+        };
+        assert_eq!(shrink(check, "1\n27\n3\n\n4\n5\n"), "27\n3\n");
+
+        let property = Property {
+            name: "sevens",
+            generate: generate::day20,
+            check,
+        };
+        let failure = run(&property, 0..1, &[100]).unwrap_err();
+        assert_eq!(failure.input.lines().count(), 2);
+        assert_eq!(failure.detail, "seven");
+    }
+
+    #[test]
+    fn test_day11() {
+        assert_agrees("day11", 0..20, &[4, 6, 8]);
+        assert_agrees("day11_2", 0..10, &[4, 6, 8]);
+    }
+
+    #[test]
+    fn test_day15() {
+        assert_agrees("day15_row", 0..20, &[3, 10, 30]);
+        assert_agrees("day15_distress", 0..20, &[3, 10, 30]);
+    }
+
+    #[test]
+    fn test_day17() {
+        assert_agrees("day17", 0..10, &[1, 3, 10, 40]);
+    }
+
+    #[test]
+    fn test_day20() {
+        assert_agrees("day20", 0..30, &[2, 3, 5, 10, 40]);
+    }
+}
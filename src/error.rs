@@ -0,0 +1,198 @@
+use crate::input::normalise;
+use regex::{Captures, Regex};
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io {
+        file: String,
+        error: io::Error,
+    },
+    Syntax {
+        file: String,
+        line: usize,
+        column: usize,
+        expected: String,
+        found: String,
+    },
+}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+// Name used for input that did not come from a file, path based wrappers
+// replace it with the real path.
+pub const INPUT: &str = "<input>";
+
+impl ParseError {
+    pub fn syntax(line: usize, column: usize, expected: &str, found: &str) -> Self {
+        ParseError::Syntax {
+            file: INPUT.to_string(),
+            line,
+            column,
+            expected: expected.to_string(),
+            found: found.to_string(),
+        }
+    }
+
+    pub fn in_file(self, name: &str) -> Self {
+        match self {
+            ParseError::Io { error, .. } => ParseError::Io {
+                file: name.to_string(),
+                error,
+            },
+            ParseError::Syntax {
+                line,
+                column,
+                expected,
+                found,
+                ..
+            } => ParseError::Syntax {
+                file: name.to_string(),
+                line,
+                column,
+                expected,
+                found,
+            },
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Io { file, error } => {
+                write!(f, "{}: unable to read input: {}", file, error)
+            }
+            ParseError::Syntax {
+                file,
+                line,
+                column,
+                expected,
+                found,
+            } => {
+                write!(f, "{}:{}:{}: expected {}, ", file, line, column, expected)?;
+                if found.is_empty() {
+                    write!(f, "found end of line")
+                } else {
+                    write!(f, "found `{}`", found)
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn read_input(path: &str) -> ParseResult<String> {
+    read_to_string(path)
+        .map(|content| normalise(&content))
+        .map_err(|error| ParseError::Io {
+            file: path.to_string(),
+            error,
+        })
+}
+
+pub fn read_reader(mut reader: impl BufRead) -> ParseResult<String> {
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .map_err(|error| ParseError::Io {
+            file: INPUT.to_string(),
+            error,
+        })?;
+    Ok(normalise(&content))
+}
+
+// Runs a string parser on a file, errors point at the file instead of INPUT.
+pub fn from_path<T>(path: &str, parse: impl FnOnce(&str) -> ParseResult<T>) -> ParseResult<T> {
+    parse(&read_input(path)?).map_err(|e| e.in_file(path))
+}
+
+pub fn from_reader<T>(
+    reader: impl BufRead,
+    parse: impl FnOnce(&str) -> ParseResult<T>,
+) -> ParseResult<T> {
+    parse(&read_reader(reader)?)
+}
+
+// A line of input together with its number, so errors can point at the
+// offending text.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    // The empty line after the last one, for errors about input that ends
+    // too early.
+    pub fn end(content: &str) -> Line<'a> {
+        Line {
+            number: content.lines().count() + 1,
+            text: "",
+        }
+    }
+
+    // 1-based column of `part`, which has to be a slice of this line (a
+    // regex capture or a split piece), otherwise the start of the line.
+    pub fn column_of(&self, part: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let offset = (part.as_ptr() as usize).wrapping_sub(start);
+        if offset <= self.text.len() {
+            self.text[..offset].chars().count() + 1
+        } else {
+            1
+        }
+    }
+
+    pub fn error(&self, part: &str, expected: &str) -> ParseError {
+        ParseError::syntax(self.number, self.column_of(part), expected, part)
+    }
+
+    pub fn parse<T: FromStr>(&self, part: &str, expected: &str) -> ParseResult<T> {
+        part.parse::<T>().map_err(|_| self.error(part, expected))
+    }
+
+    pub fn captures(&self, regex: &Regex, expected: &str) -> ParseResult<Captures<'a>> {
+        regex
+            .captures(self.text)
+            .ok_or_else(|| self.error(self.text, expected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::lines;
+
+    #[test]
+    fn test_line_errors() {
+        let content = "10 20\n30 x0";
+        let line = lines(content).nth(1).unwrap();
+        let part = line.text.split(' ').nth(1).unwrap();
+
+        assert_eq!(
+            line.parse::<i32>(line.text.split(' ').next().unwrap(), "a number")
+                .unwrap(),
+            30
+        );
+        let error = line.parse::<i32>(part, "a number").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "<input>:2:4: expected a number, found `x0`"
+        );
+        assert_eq!(
+            error.in_file("test").to_string(),
+            "test:2:4: expected a number, found `x0`"
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let error = read_input("src/specs/missing").unwrap_err();
+        assert!(matches!(error, ParseError::Io { .. }));
+    }
+}
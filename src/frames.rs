@@ -0,0 +1,325 @@
+use crate::solution::{Part, Solution};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+// A picture of a simulation at one point in time, one char per cell. Rows
+// shorter than the widest one are padded with spaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<char>,
+}
+
+impl Frame {
+    pub fn from_text(text: &str) -> Frame {
+        let rows = text.lines().collect::<Vec<_>>();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for row in rows.iter() {
+            let len = cells.len();
+            cells.extend(row.chars());
+            cells.resize(len + width, ' ');
+        }
+        Frame {
+            width,
+            height: rows.len(),
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> char {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn to_text(&self) -> String {
+        self.cells
+            .chunks(self.width.max(1))
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Where simulations send their frames.
+pub trait FrameSink {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()>;
+
+    // Called once the simulation is over.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl FrameSink for Vec<Frame> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.push(frame.clone());
+        Ok(())
+    }
+}
+
+// Implemented by the days that can replay their simulation as frames.
+pub trait Animate: Solution {
+    fn animate(&self, input: &Self::Input, part: Part, sink: &mut dyn FrameSink) -> io::Result<()>;
+}
+
+// Redraws every frame in place, waiting `delay` between frames.
+pub struct Terminal<W: Write> {
+    out: W,
+    delay: Duration,
+}
+
+impl Terminal<io::Stdout> {
+    pub fn stdout(delay: Duration) -> Terminal<io::Stdout> {
+        Terminal::new(io::stdout(), delay)
+    }
+}
+
+impl<W: Write> Terminal<W> {
+    pub fn new(out: W, delay: Duration) -> Terminal<W> {
+        Terminal { out, delay }
+    }
+}
+
+impl<W: Write> FrameSink for Terminal<W> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        // move home and clear the screen
+        writeln!(self.out, "\x1b[H\x1b[2J{}", frame.to_text())?;
+        self.out.flush()?;
+        thread::sleep(self.delay);
+        Ok(())
+    }
+}
+
+// Every frame in one text file, each one after a `frame N` line.
+pub struct TextFile<W: Write> {
+    out: W,
+    count: usize,
+}
+
+impl TextFile<BufWriter<File>> {
+    pub fn create(path: &str) -> io::Result<TextFile<BufWriter<File>>> {
+        Ok(TextFile::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> TextFile<W> {
+    pub fn new(out: W) -> TextFile<W> {
+        TextFile { out, count: 0 }
+    }
+}
+
+impl<W: Write> FrameSink for TextFile<W> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.count += 1;
+        writeln!(self.out, "frame {}\n{}\n", self.count, frame.to_text())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    // grey scale
+    Pgm,
+    // colour
+    Ppm,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Pgm => "pgm",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+// Colour of a cell, empty cells are white and rock or walls black.
+fn colour(c: char) -> [u8; 3] {
+    match c {
+        ' ' | '.' => [255, 255, 255],
+        '#' | '|' | '-' | '_' => [0, 0, 0],
+        'o' => [230, 190, 80],
+        '@' => [200, 40, 40],
+        '+' | 's' => [40, 160, 40],
+        '~' => [60, 90, 200],
+        'H' => [200, 40, 40],
+        'T' => [40, 40, 200],
+        _ => {
+            // anything else gets a stable, fairly dark colour
+            let h = (c as u32).wrapping_mul(2654435761);
+            [(h >> 24) as u8 / 2, (h >> 16) as u8 / 2, (h >> 8) as u8 / 2]
+        }
+    }
+}
+
+fn grey([r, g, b]: [u8; 3]) -> u8 {
+    ((r as u32 * 30 + g as u32 * 59 + b as u32 * 11) / 100) as u8
+}
+
+// Binary PGM or PPM, each cell drawn as a `scale` x `scale` square.
+pub fn encode(frame: &Frame, format: ImageFormat, scale: usize) -> Vec<u8> {
+    let (width, height) = (frame.width() * scale, frame.height() * scale);
+    let magic = match format {
+        ImageFormat::Pgm => "P5",
+        ImageFormat::Ppm => "P6",
+    };
+    let mut out = format!("{}\n{} {}\n255\n", magic, width, height).into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let rgb = colour(frame.get(x / scale, y / scale));
+            match format {
+                ImageFormat::Pgm => out.push(grey(rgb)),
+                ImageFormat::Ppm => out.extend(rgb),
+            }
+        }
+    }
+    out
+}
+
+// One image per frame, `out/day14.ppm` is written as `out/day14_00001.ppm`,
+// `out/day14_00002.ppm` and so on.
+pub struct Images {
+    dir: PathBuf,
+    stem: String,
+    format: ImageFormat,
+    scale: usize,
+    count: usize,
+}
+
+impl Images {
+    pub fn new(path: &str, format: ImageFormat, scale: usize) -> io::Result<Images> {
+        let path = Path::new(path);
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(&dir)?;
+        }
+        let stem = path
+            .file_stem()
+            .map_or("frame".to_string(), |s| s.to_string_lossy().to_string());
+        Ok(Images {
+            dir,
+            stem,
+            format,
+            scale: scale.max(1),
+            count: 0,
+        })
+    }
+
+    pub fn path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!(
+            "{}_{:05}.{}",
+            self.stem,
+            index,
+            self.format.extension()
+        ))
+    }
+}
+
+impl FrameSink for Images {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.count += 1;
+        fs::write(
+            self.path(self.count),
+            encode(frame, self.format, self.scale),
+        )
+    }
+}
+
+// Forwards every `every`th frame, plus the last one so the end state is
+// always shown.
+pub struct Every<'a> {
+    inner: &'a mut dyn FrameSink,
+    every: usize,
+    seen: usize,
+    skipped: Option<Frame>,
+}
+
+impl<'a> Every<'a> {
+    pub fn new(inner: &'a mut dyn FrameSink, every: usize) -> Every<'a> {
+        Every {
+            inner,
+            every: every.max(1),
+            seen: 0,
+            skipped: None,
+        }
+    }
+}
+
+impl FrameSink for Every<'_> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.seen += 1;
+        if (self.seen - 1).is_multiple_of(self.every) {
+            self.skipped = None;
+            self.inner.frame(frame)
+        } else {
+            self.skipped = Some(frame.clone());
+            Ok(())
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(frame) = self.skipped.take() {
+            self.inner.frame(&frame)?;
+        }
+        self.inner.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame() {
+        let frame = Frame::from_text("#..\n.#\n");
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        assert_eq!(frame.get(2, 1), ' ');
+        assert_eq!(frame.to_text(), "#..\n.# ");
+    }
+
+    #[test]
+    fn test_sinks() {
+        let frames = ["#.", ".#", "##"].map(Frame::from_text);
+
+        let mut text = TextFile::new(vec![]);
+        let mut sampled = vec![];
+        {
+            let mut every = Every::new(&mut sampled, 2);
+            for frame in frames.iter() {
+                text.frame(frame).unwrap();
+                every.frame(frame).unwrap();
+            }
+            every.finish().unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(text.out).unwrap(),
+            "frame 1\n#.\n\nframe 2\n.#\n\nframe 3\n##\n\n"
+        );
+        assert_eq!(sampled, [frames[0].clone(), frames[2].clone()]);
+
+        let pgm = encode(&frames[0], ImageFormat::Pgm, 2);
+        assert_eq!(&pgm[..11], b"P5\n4 2\n255\n");
+        assert_eq!(&pgm[11..], [0, 0, 255, 255, 0, 0, 255, 255]);
+        let ppm = encode(&frames[0], ImageFormat::Ppm, 1);
+        assert_eq!(&ppm[11..], [0, 0, 0, 255, 255, 255]);
+    }
+}
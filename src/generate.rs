@@ -0,0 +1,812 @@
+use crate::day11;
+use crate::geometry::Point;
+use std::collections::{HashMap, HashSet};
+
+// Random puzzle inputs in the formats the days parse, for stress testing the
+// solvers. The same seed and size always give the same input. What `size`
+// counts depends on the day: elves, lines, monkeys, grid width, packet
+// pairs, rock paths, sensors, valves, cubes, blueprints or numbers.
+pub type Generator = fn(&mut Rng, usize) -> String;
+
+// splitmix64, good enough for test data and needs no dependency.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // in lo..=hi
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i64
+    }
+
+    // in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn percent(&mut self, p: u64) -> bool {
+        self.next_u64() % 100 < p
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    pub fn letters(&mut self, alphabet: &str, len: usize) -> String {
+        let alphabet = alphabet.chars().collect::<Vec<_>>();
+        (0..len).map(|_| *self.pick(&alphabet)).collect()
+    }
+}
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn unique_names(rng: &mut Rng, alphabet: &str, len: usize, count: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut names = vec![];
+    while names.len() < count {
+        let name = rng.letters(alphabet, len);
+        if seen.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+// Elves with 1 to 8 snacks each, at least 3 elves for the top three.
+pub fn day1(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(3))
+        .map(|_| {
+            (0..rng.range(1, 8))
+                .map(|_| format!("{}\n", rng.range(1000, 20000)))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn day2(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            format!(
+                "{} {}\n",
+                rng.pick(&['A', 'B', 'C']),
+                rng.pick(&['X', 'Y', 'Z'])
+            )
+        })
+        .collect()
+}
+
+// Groups of three rucksacks. Each rucksack draws from its own third of the
+// letters, so the badge is the only item the group shares, and its halves
+// draw from disjoint parts of that third apart from one misplaced item.
+pub fn day3(rng: &mut Rng, size: usize) -> String {
+    let letters = format!("{}{}", LOWERCASE, UPPERCASE);
+    let mut out = String::new();
+    for _ in 0..(size / 3).max(1) {
+        let mut pool = letters.chars().collect::<Vec<_>>();
+        rng.shuffle(&mut pool);
+        let badge = pool.pop().unwrap();
+        for own in pool.chunks(17) {
+            let shared = own[0];
+            let (left, right) = own[1..].split_at(8);
+            let half = rng.range(2, 16) as usize;
+            let mut first = vec![shared, badge];
+            first.extend((2..half).map(|_| *rng.pick(left)));
+            let mut second = vec![shared];
+            second.extend((1..half).map(|_| *rng.pick(right)));
+            rng.shuffle(&mut first);
+            rng.shuffle(&mut second);
+            out.extend(first);
+            out.extend(second);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub fn day4(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let a = rng.range(1, 90);
+            let b = rng.range(a, 99);
+            let c = rng.range(1, 90);
+            let d = rng.range(c, 99);
+            format!("{}-{},{}-{}\n", a, b, c, d)
+        })
+        .collect()
+}
+
+// Up to 9 stacks, only moving crates that are there.
+pub fn day5(rng: &mut Rng, size: usize) -> String {
+    let count = rng.range(3, 9) as usize;
+    let mut stacks = (0..count)
+        .map(|_| {
+            {
+                let height = rng.range(1, 8) as usize;
+                rng.letters(UPPERCASE, height)
+            }
+            .chars()
+            .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let height = stacks.iter().map(|s| s.len()).max().unwrap();
+    let mut out = String::new();
+    for level in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .map(|s| {
+                s.get(level)
+                    .map_or("   ".to_string(), |c| format!("[{}]", c))
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    let numbers = (1..=count).map(|n| format!(" {} ", n)).collect::<Vec<_>>();
+    out.push_str(&format!("{}\n\n", numbers.join(" ")));
+
+    for _ in 0..size.max(1) {
+        let from = loop {
+            let from = rng.below(count);
+            if !stacks[from].is_empty() {
+                break from;
+            }
+        };
+        let to = (from + 1 + rng.below(count - 1)) % count;
+        let moved = rng.range(1, stacks[from].len() as i64) as usize;
+        let at = stacks[from].len() - moved;
+        let crates = stacks[from].split_off(at);
+        stacks[to].extend(crates);
+        out.push_str(&format!("move {} from {} to {}\n", moved, from + 1, to + 1));
+    }
+    out
+}
+
+// A datastream whose markers show up around a third and two thirds of the
+// way in: three letters can't make a start of packet marker and thirteen
+// can't make a start of message one.
+pub fn day6(rng: &mut Rng, size: usize) -> String {
+    let len = size.max(40);
+    let packet = len / 3;
+    let message = (2 * len / 3).min(len - 20);
+    let mut distinct = |count: usize| {
+        let mut letters = LOWERCASE.chars().collect::<Vec<_>>();
+        rng.shuffle(&mut letters);
+        letters[..count].iter().collect::<String>()
+    };
+    let (first, second) = (distinct(4), distinct(14));
+    let mut out = rng.letters("abc", packet);
+    out.push_str(&first);
+    out.push_str(&rng.letters("abcdefghijklm", message - packet - 4));
+    out.push_str(&second);
+    out.push_str(&rng.letters(LOWERCASE, len - message - 14));
+    out.push('\n');
+    out
+}
+
+// A terminal session listing every directory of a random tree once.
+pub fn day7(rng: &mut Rng, size: usize) -> String {
+    let count = size.max(1);
+    let mut children: Vec<Vec<(String, usize)>> = vec![vec![]; count];
+    for dir in 1..count {
+        let parent = rng.below(dir);
+        let taken = children[parent].len();
+        children[parent].push((format!("{}{}", rng.letters(LOWERCASE, 3), taken), dir));
+    }
+    let mut files = (0..count)
+        .map(|_| {
+            (0..rng.range(0, 4))
+                .map(|i| {
+                    let extension = *rng.pick(&["", ".txt", ".dat", ".log", ".lst"]);
+                    let name = format!("{}{}{}", rng.letters(LOWERCASE, 4), i, extension);
+                    format!("{} {}", rng.range(1000, 300000), name)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // the disk has to be full enough for part 2 to have something to delete
+    let used = files
+        .iter()
+        .flatten()
+        .map(|f| f.split_once(' ').unwrap().0.parse::<i64>().unwrap())
+        .sum::<i64>();
+    let big = rng.range(41_000_000, 60_000_000) - used;
+    if big > 0 {
+        files[rng.below(count)].push(format!("{} big.img", big));
+    }
+
+    let mut out = String::new();
+    let mut stack = vec![(0, "/".to_string(), false)];
+    while let Some((dir, name, listed)) = stack.pop() {
+        if listed {
+            out.push_str("$ cd ..\n");
+            continue;
+        }
+        out.push_str(&format!("$ cd {}\n$ ls\n", name));
+        let mut entries = children[dir]
+            .iter()
+            .map(|(name, _)| format!("dir {}", name))
+            .collect::<Vec<_>>();
+        entries.extend(files[dir].iter().cloned());
+        rng.shuffle(&mut entries);
+        for entry in entries {
+            out.push_str(&entry);
+            out.push('\n');
+        }
+        if dir != 0 {
+            stack.push((dir, name, true));
+        }
+        for (name, child) in children[dir].iter().rev() {
+            stack.push((*child, name.clone(), false));
+        }
+    }
+    out
+}
+
+// A square forest `size` trees wide.
+pub fn day8(rng: &mut Rng, size: usize) -> String {
+    let side = size.clamp(2, 200);
+    (0..side)
+        .map(|_| format!("{}\n", rng.letters("0123456789", side)))
+        .collect()
+}
+
+pub fn day9(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| format!("{} {}\n", rng.pick(&['U', 'D', 'L', 'R']), rng.range(1, 20)))
+        .collect()
+}
+
+// At least 240 cycles so the whole CRT is drawn, keeping X on the screen.
+pub fn day10(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    let (mut cycles, mut x) = (0, 1);
+    while cycles < size.max(240) {
+        if rng.percent(30) {
+            out.push_str("noop\n");
+            cycles += 1;
+        } else {
+            let next = rng.range(0, 39);
+            out.push_str(&format!("addx {}\n", next - x));
+            x = next;
+            cycles += 2;
+        }
+    }
+    out
+}
+
+// `size` monkeys, at most 10 as each one needs its own prime to test with.
+// Like the puzzle's own notes, worry levels fit in whole numbers for the 20
+// rounds of part 1. Squaring them too often overflows any integer, which
+// happens every time with fewer than 4 monkeys.
+pub fn day11(rng: &mut Rng, size: usize) -> String {
+    loop {
+        let notes = monkey_notes(rng, size.clamp(4, 10));
+        let monkeys = day11::parse_str(&notes).unwrap();
+        if day11::monkey_business_reference(&monkeys, 20, true).is_some() {
+            return notes;
+        }
+    }
+}
+
+fn monkey_notes(rng: &mut Rng, count: usize) -> String {
+    let mut primes = vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
+    rng.shuffle(&mut primes);
+    let squared = rng.below(count);
+    (0..count)
+        .map(|monkey| {
+            let items = (0..rng.range(1, 6))
+                .map(|_| rng.range(50, 99).to_string())
+                .collect::<Vec<_>>();
+            let operation = match monkey {
+                _ if monkey == squared => "* old".to_string(),
+                _ if rng.percent(50) => format!("* {}", rng.range(2, 19)),
+                _ => format!("+ {}", rng.range(1, 8)),
+            };
+            let mut other = || (monkey + 1 + rng.below(count - 1)) % count;
+            let (if_true, if_false) = (other(), other());
+            format!(
+                "Monkey {}:\n  Starting items: {}\n  Operation: new = old {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+                monkey,
+                items.join(", "),
+                operation,
+                primes[monkey],
+                if_true,
+                if_false
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// A heightmap `size` cells wide, with at most 1000 cells as the solver
+// treats longer distances as unreachable. A randomised depth first search
+// from S lays out a path to E that climbs at most one step at a time.
+pub fn day12(rng: &mut Rng, size: usize) -> String {
+    let width = size.clamp(8, 40) as i64;
+    let height = (size / 2).clamp(5, 25) as i64;
+    let inside = |p: Point| p.x >= 0 && p.y >= 0 && p.x < width && p.y < height;
+
+    let mut path = vec![];
+    for _ in 0..20 {
+        let start = Point::new(rng.range(0, width - 1), rng.range(0, height - 1));
+        let mut parents = HashMap::from([(start, start)]);
+        let mut stack = vec![(start, 0)];
+        let mut deepest = (start, 0);
+        while let Some((point, depth)) = stack.pop() {
+            if depth > deepest.1 {
+                deepest = (point, depth);
+            }
+            let mut next = crate::grid::NEIGHBOURS4.map(|step| point + step);
+            rng.shuffle(&mut next);
+            for n in next {
+                if inside(n) && !parents.contains_key(&n) {
+                    parents.insert(n, point);
+                    stack.push((n, depth + 1));
+                }
+            }
+        }
+        if deepest.1 >= 25 {
+            let mut point = deepest.0;
+            path = vec![point];
+            while point != start {
+                point = parents[&point];
+                path.push(point);
+            }
+            path.reverse();
+            break;
+        }
+    }
+    if path.is_empty() {
+        // snake through every row
+        for y in 0..height {
+            for i in 0..width {
+                path.push(Point::new(if y % 2 == 0 { i } else { width - 1 - i }, y));
+            }
+        }
+    }
+
+    let mut cells = (0..height)
+        .map(|_| {
+            rng.letters(LOWERCASE, width as usize)
+                .chars()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let last = path.len() - 1;
+    for (i, point) in path.iter().enumerate() {
+        let c = match i {
+            0 => 'S',
+            _ if i == last => 'E',
+            _ => (b'a' + (i * 25 / last) as u8) as char,
+        };
+        cells[point.y as usize][point.x as usize] = c;
+    }
+    cells
+        .iter()
+        .map(|row| format!("{}\n", row.iter().collect::<String>()))
+        .collect()
+}
+
+fn packet(rng: &mut Rng, depth: usize) -> String {
+    let items = (0..rng.range(0, 5))
+        .map(|_| {
+            if depth < 4 && rng.percent(30) {
+                packet(rng, depth + 1)
+            } else {
+                rng.range(0, 10).to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", items.join(","))
+}
+
+pub fn day13(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| format!("{}\n{}\n", packet(rng, 0), packet(rng, 0)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Rock paths around the sand source at 500,0, never on its row.
+pub fn day14(rng: &mut Rng, size: usize) -> String {
+    let spread = 20 + size as i64;
+    let depth = 10 + size as i64 / 2;
+    (0..size.max(1))
+        .map(|_| {
+            let mut point = Point::new(rng.range(500 - spread, 500 + spread), rng.range(2, depth));
+            let mut points = vec![point.to_string()];
+            for i in 0..rng.range(1, 4) {
+                let step = rng.range(1, 6) * if rng.percent(50) { 1 } else { -1 };
+                if i % 2 == 0 {
+                    point.x += step;
+                } else {
+                    point.y = (point.y + step).max(2);
+                }
+                points.push(point.to_string());
+            }
+            format!("{}\n", points.join(" -> "))
+        })
+        .collect()
+}
+
+// Sensors covering every position of the 0..=max square but one, which is
+// returned along with the report.
+//
+// Turned by 45 degrees, u = x + y and v = x - y, a sensor covers a square
+// and the distress beacon is the one lattice point left out of a grid of
+// overlapping squares. The squares around it are shrunk to leave it out and
+// the gap is closed by four larger squares that just miss it on each side.
+pub fn sensors(rng: &mut Rng, count: usize, max: i64) -> (String, Point) {
+    let beacon = Point::new(rng.range(0, max), rng.range(0, max));
+    let to_xy = |u: i64, v: i64| Point::new((u + v) / 2, (u - v) / 2);
+    let radius = ((max as f64 / (2.0 * count.max(1) as f64).sqrt()) as i64).max(2);
+
+    let mut sensors = vec![];
+    let v_start = if max % 2 == 0 { -max } else { -max - 1 };
+    let mut u = 0;
+    while u - radius <= 2 * max {
+        let mut v = v_start;
+        while v - radius <= max {
+            let center = to_xy(u, v);
+            let closest = Point::new(center.x.clamp(0, max), center.y.clamp(0, max));
+            if center.manhattan(&closest) <= radius {
+                let wider = radius + rng.range(0, radius / 4);
+                sensors.push((center, wider.min(center.manhattan(&beacon) - 1)));
+            }
+            v += 2 * radius;
+        }
+        u += 2 * radius;
+    }
+
+    let (ub, vb) = (beacon.x + beacon.y, beacon.x - beacon.y);
+    // shrunk squares lose at most 2.5 r around it, odd so the centers are
+    // on the lattice
+    let around = (3 * radius + 1) | 1;
+    for (du, dv) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let center = to_xy(ub + du * (around + 1), vb + dv * (around + 1));
+        sensors.push((center, around));
+    }
+    rng.shuffle(&mut sensors);
+
+    let report = sensors
+        .iter()
+        .filter(|(_, r)| *r >= 0)
+        .map(|(sensor, r)| {
+            let dx = rng.range(-r, *r);
+            let dy = (r - dx.abs()) * if rng.percent(50) { 1 } else { -1 };
+            let closest = *sensor + Point::new(dx, dy);
+            format!(
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}\n",
+                sensor.x, sensor.y, closest.x, closest.y
+            )
+        })
+        .collect();
+    (report, beacon)
+}
+
+// For the puzzle's own search area, 0..=4000000 on both axes.
+pub fn day15(rng: &mut Rng, size: usize) -> String {
+    sensors(rng, size, 4000000).0
+}
+
+// A connected cave of `size` valves, about half of them with a flow rate.
+pub fn day16(rng: &mut Rng, size: usize) -> String {
+    let count = size.clamp(2, 600);
+    let mut names = vec!["AA".to_string()];
+    names.extend(
+        unique_names(rng, UPPERCASE, 2, count + 1)
+            .into_iter()
+            .filter(|n| n != "AA")
+            .take(count - 1),
+    );
+
+    let mut tunnels = vec![HashSet::new(); count];
+    let mut connect = |a: usize, b: usize| {
+        if a != b {
+            tunnels[a].insert(b);
+            tunnels[b].insert(a);
+        }
+    };
+    for valve in 1..count {
+        connect(valve, rng.below(valve));
+    }
+    for _ in 0..count / 3 {
+        connect(rng.below(count), rng.below(count));
+    }
+
+    (0..count)
+        .map(|valve| {
+            let flow = match valve {
+                0 => 0,
+                _ if rng.percent(50) => rng.range(1, 25),
+                _ => 0,
+            };
+            let mut to = tunnels[valve]
+                .iter()
+                .map(|t| names[*t].as_str())
+                .collect::<Vec<_>>();
+            rng.shuffle(&mut to);
+            let lead = if to.len() == 1 {
+                "tunnel leads to valve"
+            } else {
+                "tunnels lead to valves"
+            };
+            format!(
+                "Valve {} has flow rate={}; {} {}\n",
+                names[valve],
+                flow,
+                lead,
+                to.join(", ")
+            )
+        })
+        .collect()
+}
+
+pub fn day17(rng: &mut Rng, size: usize) -> String {
+    format!("{}\n", rng.letters("<>", size.max(1)))
+}
+
+// A lump of `size` cubes grown from a single one, with air pockets.
+pub fn day18(rng: &mut Rng, size: usize) -> String {
+    let start = (10, 10, 10);
+    let mut cubes = vec![start];
+    let mut seen = HashSet::from([start]);
+    while cubes.len() < size.max(1) {
+        let (x, y, z) = *rng.pick(&cubes);
+        let next = *rng.pick(&[
+            (x + 1, y, z),
+            (x - 1, y, z),
+            (x, y + 1, z),
+            (x, y - 1, z),
+            (x, y, z + 1),
+            (x, y, z - 1),
+        ]);
+        if next.0 >= 0 && next.1 >= 0 && next.2 >= 0 && seen.insert(next) {
+            cubes.push(next);
+        }
+    }
+    rng.shuffle(&mut cubes);
+    cubes
+        .iter()
+        .map(|(x, y, z)| format!("{},{},{}\n", x, y, z))
+        .collect()
+}
+
+pub fn day19(rng: &mut Rng, size: usize) -> String {
+    (1..=size.clamp(1, 30))
+        .map(|id| {
+            format!(
+                "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.\n",
+                id,
+                rng.range(2, 4),
+                rng.range(2, 4),
+                rng.range(2, 4),
+                rng.range(5, 20),
+                rng.range(2, 4),
+                rng.range(5, 20)
+            )
+        })
+        .collect()
+}
+
+// `size` numbers, exactly one of them 0.
+pub fn day20(rng: &mut Rng, size: usize) -> String {
+    let count = size.max(2);
+    let zero = rng.below(count);
+    (0..count)
+        .map(|i| match i {
+            _ if i == zero => "0\n".to_string(),
+            _ => {
+                let n = rng.range(1, 10000);
+                format!("{}\n", if rng.percent(50) { n } else { -n })
+            }
+        })
+        .collect()
+}
+
+// Values stay well inside the range f64 holds exactly, the solver uses them.
+const LIMIT: i64 = 1_000_000_000_000;
+
+struct Jobs {
+    names: Vec<String>,
+    lines: Vec<String>,
+}
+
+impl Jobs {
+    fn name(&mut self) -> String {
+        self.names.pop().unwrap()
+    }
+
+    fn number(&mut self, value: i64) -> String {
+        let name = self.name();
+        self.lines.push(format!("{}: {}", name, value));
+        name
+    }
+
+    fn operation(&mut self, a: &str, op: char, b: &str) -> String {
+        let name = self.name();
+        self.lines.push(format!("{}: {} {} {}", name, a, op, b));
+        name
+    }
+
+    // A tree of jobs without humn, divisions always come out even.
+    fn subtree(&mut self, rng: &mut Rng, depth: usize) -> (String, i64) {
+        if depth == 0 || rng.percent(30) {
+            let value = rng.range(1, 20);
+            return (self.number(value), value);
+        }
+        let (a, x) = self.subtree(rng, depth - 1);
+        let (b, y) = self.subtree(rng, depth - 1);
+        let (op, value) = match *rng.pick(&['+', '-', '*', '/']) {
+            '-' => ('-', x - y),
+            '*' if (x * y).abs() < LIMIT => ('*', x * y),
+            '/' if y != 0 && x % y == 0 => ('/', x / y),
+            _ => ('+', x + y),
+        };
+        (self.operation(&a, op, &b), value)
+    }
+}
+
+// An acyclic tree of monkey jobs of about `size` lines. humn only appears
+// once, on one side of root, and only goes through +, - and *, so both
+// parts have whole answers: the value shouting humn would need is chosen
+// first and the other side of root is built to match it.
+pub fn day21(rng: &mut Rng, size: usize) -> String {
+    monkeys(rng, size).0
+}
+
+// The jobs, along with the number humn has to shout for part 2.
+pub fn monkeys(rng: &mut Rng, size: usize) -> (String, i64) {
+    let mut jobs = Jobs {
+        names: unique_names(rng, LOWERCASE, 4, 2 * size.max(8) + 200)
+            .into_iter()
+            .filter(|n| n != "root" && n != "humn")
+            .collect(),
+        lines: vec![],
+    };
+
+    let listed = rng.range(1, 1000);
+    let needed = rng.range(1, 1000);
+    jobs.lines.push(format!("humn: {}", listed));
+    let (mut side, mut values) = ("humn".to_string(), (listed, needed));
+    for _ in 0..(size / 8).clamp(1, 40) {
+        let depth = rng.range(0, 3) as usize;
+        let (other, value) = jobs.subtree(rng, depth);
+        let humn_first = rng.percent(50);
+        let (op, next) = match *rng.pick(&['+', '-', '*']) {
+            '*' if value != 0
+                && (values.0 * value).abs() < LIMIT
+                && (values.1 * value).abs() < LIMIT =>
+            {
+                ('*', (values.0 * value, values.1 * value))
+            }
+            '-' if humn_first => ('-', (values.0 - value, values.1 - value)),
+            '-' => ('-', (value - values.0, value - values.1)),
+            _ => ('+', (values.0 + value, values.1 + value)),
+        };
+        side = if humn_first {
+            jobs.operation(&side, op, &other)
+        } else {
+            jobs.operation(&other, op, &side)
+        };
+        values = next;
+    }
+
+    // the other side of root is worth what the humn side is with `needed`
+    let (other, value) = jobs.subtree(rng, 3);
+    let target = if value <= values.1 {
+        let rest = jobs.number(values.1 - value);
+        jobs.operation(&other, '+', &rest)
+    } else {
+        let rest = jobs.number(value - values.1);
+        jobs.operation(&other, '-', &rest)
+    };
+
+    let op = *rng.pick(&['+', '-']);
+    jobs.lines.push(if rng.percent(50) {
+        format!("root: {} {} {}", side, op, target)
+    } else {
+        format!("root: {} {} {}", target, op, side)
+    });
+    rng.shuffle(&mut jobs.lines);
+    let report = jobs
+        .lines
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+    (report, needed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{find, DAYS};
+    use crate::solution::{Answer, Params, Part};
+    use crate::{day15, day19};
+
+    #[test]
+    fn test_rng() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        assert_eq!(day21(&mut a, 50), day21(&mut b, 50));
+        assert_ne!(day21(&mut a, 50), day21(&mut b, 51));
+        assert!((0..1000).all(|_| (3..=5).contains(&a.range(3, 5))));
+    }
+
+    // Every day solves what its generator writes. day15 searches its full
+    // area and day19 is slow, those two are only parsed.
+    #[test]
+    fn test_generated_inputs() {
+        for seed in 0..3 {
+            for entry in DAYS.iter() {
+                let input = (entry.generate)(&mut Rng::new(seed), 5);
+                let result = match entry.name {
+                    "day15" => day15::parse_str(&input).map(|_| ()),
+                    "day19" => day19::parse_str(&input).map(|_| ()),
+                    _ => {
+                        let solver = entry.build(&Params::new()).unwrap();
+                        solver.solve_all_str(&input).map(|_| ())
+                    }
+                };
+                assert!(result.is_ok(), "{} seed {}: {:?}", entry.name, seed, result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_monkeys() {
+        for seed in 0..10 {
+            let (jobs, needed) = monkeys(&mut Rng::new(seed), 80);
+            let solver = find("day21").unwrap().build(&Params::new()).unwrap();
+            assert_eq!(
+                solver.solve_str(&jobs, Part::Two).unwrap(),
+                Answer::Number(needed)
+            );
+        }
+    }
+
+    // Checked by brute force, not with day15's own search.
+    #[test]
+    fn test_sensors() {
+        for seed in 0..5 {
+            let (report, beacon) = sensors(&mut Rng::new(seed), 10, 20);
+            let sensors = report
+                .lines()
+                .map(|line| {
+                    let n = line
+                        .split(|c: char| c != '-' && !c.is_ascii_digit())
+                        .filter_map(|n| n.parse().ok())
+                        .collect::<Vec<i64>>();
+                    let sensor = Point::new(n[0], n[1]);
+                    (sensor, sensor.manhattan(&Point::new(n[2], n[3])))
+                })
+                .collect::<Vec<_>>();
+            let uncovered = (0..=20)
+                .flat_map(|y| (0..=20).map(move |x| Point::new(x, y)))
+                .filter(|p| sensors.iter().all(|(s, r)| s.manhattan(p) > *r))
+                .collect::<Vec<_>>();
+            assert_eq!(uncovered, [beacon]);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use std::fmt::{Display, Error, Formatter};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+// Signed integer types usable as coordinates.
+pub trait Coord:
+    Copy + Ord + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+
+    fn signum(self) -> Self;
+}
+
+macro_rules! coord {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                const ZERO: $t = 0;
+                const ONE: $t = 1;
+
+                fn abs(self) -> $t {
+                    <$t>::abs(self)
+                }
+
+                fn signum(self) -> $t {
+                    <$t>::signum(self)
+                }
+            }
+        )*
+    };
+}
+
+coord!(i8, i16, i32, i64, isize);
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point<T = i64> {
+    pub x: T,
+    pub y: T,
+}
+
+// Points double as offsets between points.
+pub type Vector<T = i64> = Point<T>;
+
+impl<T> Point<T> {
+    pub const fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+}
+
+impl<T: Coord> Point<T> {
+    pub fn origin() -> Point<T> {
+        Point::new(T::ZERO, T::ZERO)
+    }
+
+    pub fn manhattan(&self, other: &Point<T>) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn chebyshev(&self, other: &Point<T>) -> T {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    // Unit step in the direction of the vector along each axis, e.g. how a
+    // rope knot follows the one in front of it.
+    pub fn signum(&self) -> Point<T> {
+        Point::new(self.x.signum(), self.y.signum())
+    }
+}
+
+impl<T> From<(T, T)> for Point<T> {
+    fn from((x, y): (T, T)) -> Point<T> {
+        Point::new(x, y)
+    }
+}
+
+impl<T: Coord> Add for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, other: Point<T>) -> Point<T> {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Coord> Sub for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, other: Point<T>) -> Point<T> {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Coord> Neg for Point<T> {
+    type Output = Point<T>;
+
+    fn neg(self) -> Point<T> {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Coord> Mul<T> for Point<T> {
+    type Output = Point<T>;
+
+    fn mul(self, k: T) -> Point<T> {
+        Point::new(self.x * k, self.y * k)
+    }
+}
+
+impl<T: Coord> AddAssign for Point<T> {
+    fn add_assign(&mut self, other: Point<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Coord> SubAssign for Point<T> {
+    fn sub_assign(&mut self, other: Point<T>) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Display> Display for Point<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+// Parses "x,y", e.g. the search bounds of day15 on the command line.
+impl<T: FromStr> FromStr for Point<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s.split_once(',').unwrap_or((s, ""));
+        Ok(Point::new(x.trim().parse()?, y.trim().parse()?))
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3<T = i64> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Point3<T> {
+    pub const fn new(x: T, y: T, z: T) -> Point3<T> {
+        Point3 { x, y, z }
+    }
+}
+
+impl<T: Coord> Point3<T> {
+    pub fn origin() -> Point3<T> {
+        Point3::new(T::ZERO, T::ZERO, T::ZERO)
+    }
+
+    pub fn manhattan(&self, other: &Point3<T>) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+
+    pub fn chebyshev(&self, other: &Point3<T>) -> T {
+        (self.x - other.x)
+            .abs()
+            .max((self.y - other.y).abs())
+            .max((self.z - other.z).abs())
+    }
+
+    // The six points sharing a face with this one.
+    pub fn neighbours6(&self) -> [Point3<T>; 6] {
+        let (o, z) = (T::ONE, T::ZERO);
+        [
+            *self + Point3::new(-o, z, z),
+            *self + Point3::new(o, z, z),
+            *self + Point3::new(z, -o, z),
+            *self + Point3::new(z, o, z),
+            *self + Point3::new(z, z, -o),
+            *self + Point3::new(z, z, o),
+        ]
+    }
+}
+
+impl<T> From<(T, T, T)> for Point3<T> {
+    fn from((x, y, z): (T, T, T)) -> Point3<T> {
+        Point3::new(x, y, z)
+    }
+}
+
+impl<T: Coord> Add for Point3<T> {
+    type Output = Point3<T>;
+
+    fn add(self, other: Point3<T>) -> Point3<T> {
+        Point3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Coord> Sub for Point3<T> {
+    type Output = Point3<T>;
+
+    fn sub(self, other: Point3<T>) -> Point3<T> {
+        Point3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Coord> Neg for Point3<T> {
+    type Output = Point3<T>;
+
+    fn neg(self) -> Point3<T> {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Coord> Mul<T> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn mul(self, k: T) -> Point3<T> {
+        Point3::new(self.x * k, self.y * k, self.z * k)
+    }
+}
+
+impl<T: Display> Display for Point3<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+// Inclusive box around a set of points.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BBox<T = i64> {
+    pub min: Point<T>,
+    pub max: Point<T>,
+}
+
+impl<T: Coord> BBox<T> {
+    pub fn new(min: Point<T>, max: Point<T>) -> BBox<T> {
+        BBox { min, max }
+    }
+
+    pub fn around(point: Point<T>) -> BBox<T> {
+        BBox::new(point, point)
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Point<T>>) -> Option<BBox<T>> {
+        points.into_iter().fold(None, |bbox, point| match bbox {
+            Some(bbox) => Some(BBox::extend(&bbox, point)),
+            None => Some(BBox::around(point)),
+        })
+    }
+
+    // Box with the top left corner at the origin, empty when either side is 0.
+    pub fn from_size(width: T, height: T) -> BBox<T> {
+        BBox::new(Point::origin(), Point::new(width - T::ONE, height - T::ONE))
+    }
+
+    pub fn width(&self) -> T {
+        (self.max.x - self.min.x + T::ONE).max(T::ZERO)
+    }
+
+    pub fn height(&self) -> T {
+        (self.max.y - self.min.y + T::ONE).max(T::ZERO)
+    }
+
+    pub fn contains(&self, point: Point<T>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
+    pub fn extend(&self, point: Point<T>) -> BBox<T> {
+        BBox::new(
+            Point::new(self.min.x.min(point.x), self.min.y.min(point.y)),
+            Point::new(self.max.x.max(point.x), self.max.y.max(point.y)),
+        )
+    }
+}
+
+// Integer affine map: a rotation or reflection by quarter turns followed by
+// a translation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Transform<T = i64> {
+    matrix: [[T; 2]; 2],
+    offset: Vector<T>,
+}
+
+impl<T: Coord> Transform<T> {
+    fn linear(matrix: [[T; 2]; 2]) -> Transform<T> {
+        Transform {
+            matrix,
+            offset: Point::origin(),
+        }
+    }
+
+    pub fn identity() -> Transform<T> {
+        Transform::linear([[T::ONE, T::ZERO], [T::ZERO, T::ONE]])
+    }
+
+    pub fn translate(offset: Vector<T>) -> Transform<T> {
+        Transform {
+            offset,
+            ..Transform::identity()
+        }
+    }
+
+    // Quarter turn counter clockwise when y grows up, (1, 0) -> (0, 1).
+    pub fn rotate_left() -> Transform<T> {
+        Transform::linear([[T::ZERO, -T::ONE], [T::ONE, T::ZERO]])
+    }
+
+    pub fn rotate_right() -> Transform<T> {
+        Transform::linear([[T::ZERO, T::ONE], [-T::ONE, T::ZERO]])
+    }
+
+    // Mirrors x, keeps y.
+    pub fn flip_x() -> Transform<T> {
+        Transform::linear([[-T::ONE, T::ZERO], [T::ZERO, T::ONE]])
+    }
+
+    pub fn flip_y() -> Transform<T> {
+        Transform::linear([[T::ONE, T::ZERO], [T::ZERO, -T::ONE]])
+    }
+
+    pub fn apply(&self, point: Point<T>) -> Point<T> {
+        let [[a, b], [c, d]] = self.matrix;
+        Point::new(a * point.x + b * point.y, c * point.x + d * point.y) + self.offset
+    }
+
+    // This transform followed by `next`.
+    pub fn then(&self, next: &Transform<T>) -> Transform<T> {
+        let [[a, b], [c, d]] = next.matrix;
+        let [[e, f], [g, h]] = self.matrix;
+        Transform {
+            matrix: [
+                [a * e + b * g, a * f + b * h],
+                [c * e + d * g, c * f + d * h],
+            ],
+            offset: next.apply(self.offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_arithmetic() {
+        let a = Point::new(8, 7);
+        let b = Point::new(2, 10);
+        assert_eq!(a + b, Point::new(10, 17));
+        assert_eq!(a - b, Point::new(6, -3));
+        assert_eq!(-(a - b) * 2, Point::new(-12, 6));
+        assert_eq!((b - a).signum(), Point::new(-1, 1));
+        assert_eq!(a.manhattan(&b), 9);
+        assert_eq!(a.chebyshev(&b), 6);
+        assert_eq!(
+            "4000000, 20".parse::<Point<i32>>(),
+            Ok(Point::new(4000000, 20))
+        );
+        assert!("4000000".parse::<Point<i32>>().is_err());
+        assert_eq!(Point::<i64>::from((1, 2)).to_string(), "1,2");
+    }
+
+    #[test]
+    fn test_point3() {
+        let a = Point3::new(1, 2, 3);
+        let b = Point3::new(2, 0, 3);
+        assert_eq!(a.manhattan(&b), 3);
+        assert_eq!(a.chebyshev(&b), 2);
+        assert_eq!(a.neighbours6().len(), 6);
+        assert!(a
+            .neighbours6()
+            .iter()
+            .all(|n| n.manhattan(&a) == 1 && *n != a));
+    }
+
+    #[test]
+    fn test_bbox() {
+        let bbox =
+            BBox::from_points([Point::new(3, -1), Point::new(5, 1), Point::new(4, 0)]).unwrap();
+        assert_eq!(bbox, BBox::new(Point::new(3, -1), Point::new(5, 1)));
+        assert_eq!((bbox.width(), bbox.height()), (3, 3));
+        assert!(bbox.contains(Point::new(5, -1)));
+        assert!(!bbox.contains(Point::new(6, 0)));
+        assert_eq!(BBox::<i64>::from_points([]), None);
+        assert_eq!(BBox::from_size(0, 4).width(), 0);
+    }
+
+    #[test]
+    fn test_transform() {
+        let p = Point::new(1, 0);
+        assert_eq!(Transform::rotate_left().apply(p), Point::new(0, 1));
+        assert_eq!(Transform::rotate_right().apply(p), Point::new(0, -1));
+        assert_eq!(Transform::flip_x().apply(p), Point::new(-1, 0));
+        assert_eq!(
+            Transform::translate(Point::new(2, 3)).apply(p),
+            Point::new(3, 3)
+        );
+
+        let moved = Transform::rotate_left().then(&Transform::translate(Point::new(2, 3)));
+        assert_eq!(moved.apply(p), Point::new(2, 4));
+        let turned = Transform::translate(Point::new(2, 3)).then(&Transform::rotate_left());
+        assert_eq!(turned.apply(p), Point::new(-3, 3));
+        assert_eq!(
+            Transform::<i64>::rotate_left().then(&Transform::rotate_right()),
+            Transform::identity()
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use crate::error::ParseResult;
+use crate::geometry::{BBox, Point};
+use crate::input;
+use std::collections::{hash_map, HashMap};
+use std::fmt::{Display, Error, Formatter};
+use std::iter::Enumerate;
+use std::ops::Index;
+use std::slice;
+
+// Left, right, up, down, with y growing down as in parsed maps.
+pub const NEIGHBOURS4: [Point; 4] = [
+    Point::new(-1, 0),
+    Point::new(1, 0),
+    Point::new(0, -1),
+    Point::new(0, 1),
+];
+
+pub const NEIGHBOURS8: [Point; 8] = [
+    Point::new(-1, -1),
+    Point::new(0, -1),
+    Point::new(1, -1),
+    Point::new(-1, 0),
+    Point::new(1, 0),
+    Point::new(-1, 1),
+    Point::new(0, 1),
+    Point::new(1, 1),
+];
+
+// Position of the `i`th cell of a dense grid.
+fn position(bounds: &BBox, i: usize) -> Point {
+    let width = bounds.width() as usize;
+    bounds.min + Point::new((i % width) as i64, (i / width) as i64)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Storage<T> {
+    Dense(Vec<Option<T>>),
+    Sparse(HashMap<Point, T>),
+}
+
+// A map from positions to cells. Dense grids own every cell of fixed bounds,
+// sparse grids only the cells that were inserted and grow their bounds to
+// fit them. Cells can be missing in both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    storage: Storage<T>,
+    bounds: Option<BBox>,
+    len: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn dense(bounds: BBox) -> Grid<T> {
+        let cells = (0..bounds.width() * bounds.height())
+            .map(|_| None)
+            .collect();
+        Grid {
+            storage: Storage::Dense(cells),
+            bounds: Some(bounds),
+            len: 0,
+        }
+    }
+
+    pub fn sparse() -> Grid<T> {
+        Grid {
+            storage: Storage::Sparse(HashMap::new()),
+            bounds: None,
+            len: 0,
+        }
+    }
+
+    // Parses a character map into a dense grid, `cell` returns None for
+    // characters it does not accept. Every row has to be as wide as the first.
+    pub fn parse(
+        input: &str,
+        expected: &str,
+        mut cell: impl FnMut(Point, char) -> Option<T>,
+    ) -> ParseResult<Grid<T>> {
+        let mut rows = vec![];
+        for (y, line) in input::grid(input)?.into_iter().enumerate() {
+            let row = line
+                .text
+                .char_indices()
+                .enumerate()
+                .map(|(x, (i, c))| {
+                    cell(Point::new(x as i64, y as i64), c)
+                        .ok_or_else(|| line.error(&line.text[i..], expected))
+                })
+                .collect::<ParseResult<Vec<T>>>()?;
+            rows.push(row);
+        }
+
+        let width = rows.first().map_or(0, |r| r.len());
+        let mut grid = Grid::dense(BBox::from_size(width as i64, rows.len() as i64));
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, value) in row.into_iter().enumerate() {
+                grid.insert(Point::new(x as i64, y as i64), value);
+            }
+        }
+        Ok(grid)
+    }
+
+    // Same cells in a sparse grid, worth it when most of them get removed
+    // since removed cells are still visited when iterating a dense grid.
+    pub fn into_sparse(self) -> Grid<T> {
+        let cells = match self.storage {
+            Storage::Dense(cells) => {
+                let bounds = self.bounds.unwrap();
+                cells
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, cell)| cell.map(|value| (position(&bounds, i), value)))
+                    .collect()
+            }
+            Storage::Sparse(cells) => cells,
+        };
+        Grid {
+            storage: Storage::Sparse(cells),
+            bounds: self.bounds,
+            len: self.len,
+        }
+    }
+
+    pub fn bounds(&self) -> Option<BBox> {
+        self.bounds
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn index_of(&self, pos: Point) -> Option<usize> {
+        let bounds = self.bounds.filter(|b| b.contains(pos))?;
+        let offset = pos - bounds.min;
+        Some((offset.y * bounds.width() + offset.x) as usize)
+    }
+
+    pub fn get(&self, pos: impl Into<Point>) -> Option<&T> {
+        let pos = pos.into();
+        match &self.storage {
+            Storage::Dense(cells) => self.index_of(pos).and_then(|i| cells[i].as_ref()),
+            Storage::Sparse(cells) => cells.get(&pos),
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: impl Into<Point>) -> Option<&mut T> {
+        let pos = pos.into();
+        let index = self.index_of(pos);
+        match &mut self.storage {
+            Storage::Dense(cells) => index.and_then(|i| cells[i].as_mut()),
+            Storage::Sparse(cells) => cells.get_mut(&pos),
+        }
+    }
+
+    pub fn contains(&self, pos: impl Into<Point>) -> bool {
+        self.get(pos).is_some()
+    }
+
+    // Panics when a dense grid is asked to store a cell outside its bounds.
+    pub fn insert(&mut self, pos: impl Into<Point>, value: T) -> Option<T> {
+        let pos = pos.into();
+        let index = self.index_of(pos);
+        let old = match &mut self.storage {
+            Storage::Dense(cells) => {
+                let i = index.unwrap_or_else(|| {
+                    panic!("{:?} is outside of the grid {:?}", pos, self.bounds)
+                });
+                cells[i].replace(value)
+            }
+            Storage::Sparse(cells) => {
+                self.bounds = Some(match self.bounds {
+                    Some(bounds) => bounds.extend(pos),
+                    None => BBox::around(pos),
+                });
+                cells.insert(pos, value)
+            }
+        };
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    // Bounds stay as they are, removing cells never shrinks a grid.
+    pub fn remove(&mut self, pos: impl Into<Point>) -> Option<T> {
+        let pos = pos.into();
+        let index = self.index_of(pos);
+        let old = match &mut self.storage {
+            Storage::Dense(cells) => index.and_then(|i| cells[i].take()),
+            Storage::Sparse(cells) => cells.remove(&pos),
+        };
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    // Dense grids are walked row by row, sparse ones in no particular order.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        match &self.storage {
+            Storage::Dense(cells) => Iter::Dense {
+                bounds: self.bounds.unwrap(),
+                cells: cells.iter().enumerate(),
+            },
+            Storage::Sparse(cells) => Iter::Sparse(cells.iter()),
+        }
+    }
+
+    fn around<'a>(
+        &'a self,
+        from: Point,
+        offsets: &'a [Point],
+    ) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+        offsets.iter().filter_map(move |offset| {
+            let pos = from + *offset;
+            self.get(pos).map(|value| (pos, value))
+        })
+    }
+
+    pub fn neighbours4(&self, pos: impl Into<Point>) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.around(pos.into(), &NEIGHBOURS4)
+    }
+
+    pub fn neighbours8(&self, pos: impl Into<Point>) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.around(pos.into(), &NEIGHBOURS8)
+    }
+
+    // Cells met walking from `from` (not included) by `step` until leaving
+    // the bounds, missing cells are skipped.
+    pub fn ray(
+        &self,
+        from: impl Into<Point>,
+        step: Point,
+    ) -> impl Iterator<Item = (Point, &T)> + '_ {
+        let from = from.into();
+        let bounds = self.bounds;
+        (1..)
+            .map(move |k| from + step * k)
+            .take_while(move |pos| {
+                bounds.is_some_and(|b| b.contains(*pos)) && step != Point::origin()
+            })
+            .filter_map(move |pos| self.get(pos).map(|value| (pos, value)))
+    }
+
+    pub fn row(&self, y: i64) -> impl Iterator<Item = (Point, &T)> + '_ {
+        let min_x = self.bounds.map_or(0, |b| b.min.x);
+        self.ray(Point::new(min_x - 1, y), Point::new(1, 0))
+    }
+
+    pub fn column(&self, x: i64) -> impl Iterator<Item = (Point, &T)> + '_ {
+        let min_y = self.bounds.map_or(0, |b| b.min.y);
+        self.ray(Point::new(x, min_y - 1), Point::new(0, 1))
+    }
+
+    // One character per cell, one line per row. Rows go from the top of the
+    // bounds down, or bottom up with `y_up` for puzzles where y is a height.
+    pub fn render(&self, y_up: bool, cell: impl Fn(Point, Option<&T>) -> char) -> String {
+        let mut out = String::new();
+        if let Some(bounds) = self.bounds {
+            let rows = bounds.min.y..=bounds.max.y;
+            let rows: Box<dyn Iterator<Item = i64>> = if y_up {
+                Box::new(rows.rev())
+            } else {
+                Box::new(rows)
+            };
+            for y in rows {
+                for x in bounds.min.x..=bounds.max.x {
+                    let pos = Point::new(x, y);
+                    out.push(cell(pos, self.get(pos)));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+pub enum Iter<'a, T> {
+    Dense {
+        bounds: BBox,
+        cells: Enumerate<slice::Iter<'a, Option<T>>>,
+    },
+    Sparse(hash_map::Iter<'a, Point, T>),
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Point, &'a T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Dense { bounds, cells } => {
+                cells.find_map(|(i, cell)| cell.as_ref().map(|value| (position(bounds, i), value)))
+            }
+            Iter::Sparse(cells) => cells.next().map(|(pos, value)| (*pos, value)),
+        }
+    }
+
+    // Hands folds (and so min_by, count, ...) to the inner iterators, which
+    // are much faster at them than repeated `next` calls.
+    #[inline]
+    fn fold<B, F>(self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B,
+    {
+        match self {
+            Iter::Dense { bounds, cells } => cells.fold(init, |acc, (i, cell)| match cell {
+                Some(value) => f(acc, (position(&bounds, i), value)),
+                None => acc,
+            }),
+            Iter::Sparse(cells) => cells.fold(init, |acc, (pos, value)| f(acc, (*pos, value))),
+        }
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Point) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("no cell at {:?}", pos))
+    }
+}
+
+// Missing cells are drawn as `.`.
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if let Some(bounds) = self.bounds {
+            for y in bounds.min.y..=bounds.max.y {
+                for x in bounds.min.x..=bounds.max.x {
+                    match self.get(Point::new(x, y)) {
+                        Some(value) => write!(f, "{}", value)?,
+                        None => write!(f, ".")?,
+                    }
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(input: &str) -> ParseResult<Grid<u32>> {
+        Grid::parse(input, "a digit", |_, c| c.to_digit(10))
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let grid = digits("123\n456\n").unwrap();
+        assert_eq!(
+            grid.bounds(),
+            Some(BBox::new(Point::new(0, 0), Point::new(2, 1)))
+        );
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[Point::new(2, 1)], 6);
+        assert_eq!(grid.to_string(), "123\n456\n");
+
+        let error = digits("123\n4x6").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "<input>:2:2: expected a digit, found `x6`"
+        );
+        let error = digits("123\n45").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "<input>:2:3: expected a row of 3 cells, found end of line"
+        );
+    }
+
+    #[test]
+    fn test_neighbours_and_rays() {
+        let mut grid = digits("123\n456\n789").unwrap();
+        let values = |it: Box<dyn Iterator<Item = (Point, &u32)> + '_>| {
+            it.map(|(_, v)| *v).collect::<Vec<u32>>()
+        };
+
+        assert_eq!(values(Box::new(grid.neighbours4((1, 1)))), vec![4, 6, 2, 8]);
+        assert_eq!(values(Box::new(grid.neighbours4((0, 0)))), vec![2, 4]);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+        assert_eq!(values(Box::new(grid.row(2))), vec![7, 8, 9]);
+        assert_eq!(values(Box::new(grid.column(1))), vec![2, 5, 8]);
+        assert_eq!(
+            values(Box::new(grid.ray((0, 0), Point::new(1, 1)))),
+            vec![5, 9]
+        );
+
+        assert_eq!(grid.remove((1, 0)), Some(2));
+        assert_eq!(grid.len(), 8);
+        assert_eq!(values(Box::new(grid.column(1))), vec![5, 8]);
+        assert_eq!(grid.to_string(), "1.3\n456\n789\n");
+
+        let sparse = grid.clone().into_sparse();
+        assert_eq!(sparse.len(), 8);
+        assert_eq!(sparse.bounds(), grid.bounds());
+        assert_eq!(sparse.to_string(), grid.to_string());
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut grid = Grid::sparse();
+        assert_eq!(grid.bounds(), None);
+        grid.insert((3, -1), '#');
+        grid.insert((5, 1), '#');
+        assert_eq!(
+            grid.bounds(),
+            Some(BBox::new(Point::new(3, -1), Point::new(5, 1)))
+        );
+        assert_eq!(grid.insert((5, 1), 'o'), Some('#'));
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.to_string(), "#..\n...\n..o\n");
+        assert_eq!(
+            grid.render(true, |_, c| if c.is_some() { '#' } else { ' ' }),
+            "  #\n   \n#  \n"
+        );
+    }
+}
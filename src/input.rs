@@ -0,0 +1,178 @@
+use crate::error::{Line, ParseResult};
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+// Inputs saved on other machines come with CRLF line endings, a byte order
+// mark or spaces after the last character. Every day gets them the same way:
+// `\n` endings, nothing after the last character of a line and exactly one
+// newline at the end, blank lines at the end dropped.
+pub fn normalise(content: &str) -> String {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut out = String::with_capacity(content.len() + 1);
+    for line in content.split_inclusive('\n').flat_map(split_line) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.truncate(out.trim_end_matches('\n').len());
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+// The lines `normalise` makes of one line read up to its `\n`, a lone `\r`
+// is an old Mac line ending.
+fn split_line(line: &str) -> impl Iterator<Item = &str> {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r')
+        .unwrap_or(line)
+        .split('\r')
+        .map(|l| l.trim_end_matches([' ', '\t']))
+}
+
+// The lines of a reader as `normalise` leaves them, numbered from 1, for
+// inputs read a line at a time rather than whole. Blank lines at the end are
+// kept, they are only known to be the last ones once the reader is done.
+pub struct Lines<R> {
+    reader: R,
+    number: usize,
+    pending: VecDeque<String>,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub fn new(reader: R) -> Self {
+        Lines {
+            reader,
+            number: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let mut raw = String::new();
+            match self.reader.read_line(&mut raw) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            let raw = match self.number {
+                0 => raw.strip_prefix('\u{feff}').unwrap_or(&raw),
+                _ => &raw,
+            };
+            self.pending.extend(split_line(raw).map(String::from));
+        }
+        self.number += 1;
+        Some(Ok((self.number, self.pending.pop_front()?)))
+    }
+}
+
+// Every line with its number, the view of the days with one thing a line.
+pub fn lines(content: &str) -> impl Iterator<Item = Line<'_>> {
+    content.lines().enumerate().map(|(i, text)| Line {
+        number: i + 1,
+        text,
+    })
+}
+
+// Groups of lines separated by blank lines, e.g. the elves of day1 or the
+// monkeys of day11. Several blank lines in a row count as one.
+pub fn paragraphs(content: &str) -> Vec<Vec<Line<'_>>> {
+    let mut paragraphs = vec![vec![]];
+    for line in lines(content) {
+        if !line.text.is_empty() {
+            paragraphs.last_mut().unwrap().push(line);
+        } else if !paragraphs.last().unwrap().is_empty() {
+            paragraphs.push(vec![]);
+        }
+    }
+    paragraphs.retain(|p| !p.is_empty());
+    paragraphs
+}
+
+// Lines of a character map, checked to all be as wide as the first.
+pub fn grid(content: &str) -> ParseResult<Vec<Line<'_>>> {
+    let rows = lines(content).collect::<Vec<_>>();
+    let width = rows.first().map_or(0, |row| row.text.chars().count());
+    for row in rows.iter() {
+        if row.text.chars().count() != width {
+            let end = row
+                .text
+                .char_indices()
+                .nth(width)
+                .map_or(row.text.len(), |(i, _)| i);
+            return Err(row.error(&row.text[end..], &format!("a row of {} cells", width)));
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalise() {
+        assert_eq!(normalise("\u{feff}1 \r\n2\t\r\n\r\n3\r4"), "1\n2\n\n3\n4\n");
+        assert_eq!(normalise("a\n\n\n"), "a\n");
+        assert_eq!(normalise("  [D]\n"), "  [D]\n");
+        assert_eq!(normalise(""), "");
+        assert_eq!(normalise(" \r\n"), "");
+    }
+
+    #[test]
+    fn test_lines() {
+        let messy = "\u{feff}1 \r\n2\t\r\n\r\n3\r4";
+        let streamed = Lines::new(messy.as_bytes())
+            .map(|line| line.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            streamed,
+            normalise(messy)
+                .lines()
+                .zip(1..)
+                .map(|(text, number)| (number, text.to_string()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Lines::new("a\n\n".as_bytes()).count(), 2);
+    }
+
+    #[test]
+    fn test_messy_inputs() {
+        for day in ["day1", "day5", "day9", "day11", "day13"] {
+            let entry = crate::registry::find(day).unwrap();
+            let solver = entry.build(&crate::Params::new()).unwrap();
+            let spec = std::fs::read_to_string(entry.spec_path()).unwrap();
+            let messy = format!("\u{feff}{}\r\n\r\n", spec.trim_end().replace('\n', " \r\n"));
+            assert_eq!(
+                solver.solve_all_str(&messy).unwrap(),
+                solver.solve_all_str(&spec).unwrap(),
+                "{}",
+                day
+            );
+        }
+    }
+
+    #[test]
+    fn test_views() {
+        let paragraphs = paragraphs("\n1\n2\n\n\n3\n");
+        assert_eq!(
+            paragraphs
+                .iter()
+                .map(|p| p.iter().map(|l| l.text).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [vec!["1", "2"], vec!["3"]]
+        );
+        assert_eq!(paragraphs[1][0].number, 6);
+
+        assert_eq!(grid("ab\ncd\n").unwrap().len(), 2);
+        assert_eq!(
+            grid("abc\nab\n").unwrap_err().to_string(),
+            "<input>:2:3: expected a row of 3 cells, found end of line"
+        );
+    }
+}
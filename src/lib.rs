@@ -0,0 +1,53 @@
+//! Advent of Code 2022 solutions.
+//!
+//! Each `dayN` module exposes its parser, the functions answering both parts
+//! and a type implementing [`solution::Solution`]. The [`registry`] lists all
+//! of them so tools can run any day without knowing its input type.
+
+pub mod answers;
+pub mod bench;
+pub mod cache;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day11_2;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod differential;
+pub mod error;
+pub mod frames;
+pub mod generate;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod json;
+pub mod parallel;
+pub mod progress;
+pub mod registry;
+pub mod repl;
+pub mod scaffold;
+pub mod serve;
+pub mod simulation;
+pub mod solution;
+pub mod trace;
+pub mod watch;
+
+pub use error::ParseError;
+pub use registry::Entry;
+pub use solution::{Answer, FromParams, Params, Part, Solution, Solver, Timed};
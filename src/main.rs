@@ -1,154 +1,459 @@
-mod day1;
-mod day10;
-mod day11;
-mod day11_2;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod day19;
-mod day20;
-mod day21;
+mod cli;
 
-fn main() {
-    println!("day1: {:?}", day1::get_elf_and_energy("src/inputs/day1", 3));
-    println!("day2: {:?}", day2::calculate_win_score("src/inputs/day2"));
-    println!(
-        "day3 part1: {:?}",
-        day3::calculate_priorities_part1("src/inputs/day3")
-    );
-    println!(
-        "day3 part2: {:?}",
-        day3::calculate_priorities_part2("src/inputs/day3")
-    );
+use std::io::{self, IsTerminal, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
 
-    println!(
-        "day4 part1: {:?}",
-        day4::get_intersect_ranges("src/inputs/day4", day4::fully_overlaps)
-    );
+use advent_2022::cache::{self, Cache, Key};
+use advent_2022::error::{read_input, ParseResult};
+use advent_2022::frames::{Every, FrameSink, Images, Terminal, TextFile};
+use advent_2022::generate::Rng;
+use advent_2022::parallel::{self, Outcome, Task};
+use advent_2022::progress::{Progress, Step};
+use advent_2022::serve;
+use advent_2022::watch::{self, Watcher};
+use advent_2022::{answers, bench, registry, repl, scaffold, trace};
+use advent_2022::{Answer, Entry, Params, Part};
+use cli::{
+    AnimateArgs, AnswerFormat, BenchArgs, CheckArgs, Command, Format, FrameOutput, GenerateArgs,
+    Input, RunArgs, ServeArgs,
+};
 
-    println!(
-        "day4 part2: {:?}",
-        day4::get_intersect_ranges("src/inputs/day4", day4::partially_overlaps)
-    );
+fn read_stdin(input: &Input) -> Result<String, String> {
+    let mut stdin = String::new();
+    if *input == Input::Stdin {
+        io::stdin()
+            .read_to_string(&mut stdin)
+            .map_err(|e| format!("unable to read stdin: {}", e))?;
+    }
+    Ok(stdin)
+}
 
-    println!(
-        "day5 part1: {:?}",
-        day5::parse_input("src/inputs/day5", true)
-    );
-    println!(
-        "day5 part2: {:?}",
-        day5::parse_input("src/inputs/day5", false)
-    );
+fn input_name(entry: &Entry, input: &Input) -> String {
+    match input {
+        Input::Default => entry.input_path(),
+        Input::Stdin => "<stdin>".to_string(),
+        Input::Path(path) => path.clone(),
+    }
+}
 
-    println!("day6 part1: {:?}", day6::get_marker("src/inputs/day6", 4));
-    println!("day6 part2: {:?}", day6::get_marker("src/inputs/day6", 14));
-    // println!(
-    //     "day7 part1: {:?}",
-    //     day7::get_smallest_sum("src/inputs/day7")
-    // );
+// None when the input file does not exist, so `--all` can skip days that
+// have no input yet.
+fn load_input(name: &str, input: &Input, stdin: &str) -> Option<ParseResult<String>> {
+    match input {
+        Input::Stdin => Some(Ok(stdin.to_string())),
+        _ if !Path::new(name).exists() => None,
+        _ => Some(read_input(name)),
+    }
+}
 
-    // println!("day7 part1: {:?}", day7::get_biggest_sum("src/inputs/day7"));
-    println!(
-        "day8 part1: {:?}",
-        day8::count_edge_trees("src/inputs/day8")
+fn run(args: RunArgs) -> Result<(), String> {
+    let parts = match args.part {
+        Some(part) => vec![part],
+        None => Part::ALL.to_vec(),
+    };
+    let stdin = read_stdin(&args.input)?;
+    trace::init(args.trace.clone());
+    if args.watch {
+        return watch(&args, &parts);
+    }
+    if args.jobs.is_some() || args.format == AnswerFormat::Json {
+        return run_tasks(&args, &parts, &stdin);
+    }
+
+    let cache = args.cache.then(Cache::default);
+    let mut failed = false;
+    for entry in args.days {
+        let name = input_name(entry, &args.input);
+        let content = match load_input(&name, &args.input, &stdin) {
+            Some(content) => content,
+            None => {
+                println!("{}: no input at {}", entry.name, name);
+                continue;
+            }
+        };
+
+        let solver = entry.build(&args.params)?;
+        for part in parts.iter() {
+            let key = match (&content, &cache) {
+                (Ok(c), Some(_)) => Some(Key::new(entry, *part, &args.params, c)),
+                _ => None,
+            };
+            let cached = key.as_ref().zip(cache.as_ref());
+            if let Some(answer) = cached.and_then(|(key, cache)| cache.get(key)) {
+                println!("{} {}: {} (cached)", entry.name, part, answer);
+                continue;
+            }
+
+            // progress goes on a line of its own that the answer overwrites
+            let show = io::stderr().is_terminal();
+            let report = |step: Step| {
+                eprint!("\r\x1b[K{} {}: {}", entry.name, part, step);
+                io::stderr().flush().ok();
+            };
+            let mut progress = Progress::new();
+            if show {
+                progress = progress.with_report(Duration::from_millis(200), &report);
+            }
+            if let Some(timeout) = args.timeout {
+                progress = progress.with_timeout(timeout);
+            }
+
+            let answer = content.as_ref().map_err(|e| e.to_string()).and_then(|c| {
+                solver
+                    .solve_with(c, *part, &progress)
+                    .map_err(|e| e.in_file(&name).to_string())
+            });
+            if show {
+                eprint!("\r\x1b[K");
+            }
+            match answer {
+                Ok(Answer::TimedOut) => {
+                    println!("{} {}: {}", entry.name, part, Answer::TimedOut);
+                    failed = true;
+                }
+                Ok(answer) => {
+                    if let Some(Err(e)) = cached.map(|(key, cache)| cache.put(key, &answer)) {
+                        eprintln!("unable to cache {} {}: {}", entry.name, part, e);
+                    }
+                    println!("{} {}: {}", entry.name, part, answer);
+                }
+                Err(e) => {
+                    eprintln!("{} {}: {}", entry.name, part, e);
+                    failed = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// Every part on its own, on one thread unless --jobs says otherwise, with one
+// report at the end.
+fn run_tasks(args: &RunArgs, parts: &[Part], stdin: &str) -> Result<(), String> {
+    let mut inputs = vec![];
+    for entry in args.days.iter() {
+        let name = input_name(entry, &args.input);
+        // a missing input fails its parts, so it shows up in the report
+        let content = match load_input(&name, &args.input, stdin) {
+            Some(content) => content.map_err(|e| e.to_string()),
+            None => Err(format!("no input at {}", name)),
+        };
+        inputs.push((*entry, name, content));
+    }
+
+    let tasks = inputs
+        .iter()
+        .flat_map(|(entry, name, content)| {
+            parts.iter().map(move |part| Task {
+                entry,
+                part: *part,
+                name,
+                input: content.as_deref().map_err(|e| e.clone()),
+            })
+        })
+        .collect::<Vec<_>>();
+    let cache = args.cache.then(Cache::default);
+    let reports = parallel::run(
+        &tasks,
+        &args.params,
+        args.jobs.unwrap_or(1),
+        args.timeout,
+        cache.as_ref(),
+        args.format == AnswerFormat::Json,
     );
+    match args.format {
+        AnswerFormat::Text => print!("{}", parallel::to_table(&reports)),
+        AnswerFormat::Json => print!("{}", parallel::to_json(&reports)),
+    }
+
+    if reports.iter().any(|r| !r.outcome.is_ok()) {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// Answers for the input and the example, as shown, keyed by where they
+// came from.
+fn watched_answers(args: &RunArgs, parts: &[Part]) -> Vec<(String, String)> {
+    let entry = args.days[0];
+    let sources = [
+        ("input", input_name(entry, &args.input)),
+        ("example", entry.spec_path()),
+    ];
+    let contents = sources
+        .iter()
+        .filter(|(_, path)| Path::new(path).exists())
+        .map(|(source, path)| (*source, path, read_input(path).map_err(|e| e.to_string())))
+        .collect::<Vec<_>>();
+    let tasks = contents
+        .iter()
+        .flat_map(|(_, path, content)| {
+            parts.iter().map(move |part| Task {
+                entry,
+                part: *part,
+                name: path,
+                input: content.as_deref().map_err(|e| e.clone()),
+            })
+        })
+        .collect::<Vec<_>>();
+    let labels = contents
+        .iter()
+        .flat_map(|(source, _, _)| parts.iter().map(move |part| format!("{} {}", source, part)));
+
+    let reports = parallel::run(&tasks, &args.params, 1, args.timeout, None, false);
+    labels
+        .zip(reports)
+        .map(|(label, report)| {
+            let answer = match report.outcome {
+                Outcome::Solved(answer) => answer.to_string(),
+                Outcome::Failed(e) => format!("FAIL {}", e),
+                Outcome::Panicked(message) => format!("PANIC {}", message),
+            };
+            (label, answer)
+        })
+        .collect()
+}
+
+fn watch(args: &RunArgs, parts: &[Part]) -> Result<(), String> {
+    let entry = args.days[0];
+    entry.build(&args.params)?;
+    let paths = [input_name(entry, &args.input), entry.spec_path()];
+    let mut watcher = Watcher::new(paths.iter().map(PathBuf::from));
+    println!("watching {}, interrupt to stop", paths.join(" and "));
+
+    let mut previous = vec![];
+    loop {
+        let answers = watched_answers(args, parts);
+        let changes = watch::diff(&previous, &answers);
+        if changes.is_empty() {
+            println!("no change");
+        }
+        for change in changes {
+            println!("{}", change);
+        }
+        previous = answers;
+
+        for path in watcher.wait(Duration::from_millis(300)) {
+            println!("\n{} changed", path.display());
+        }
+    }
+}
+
+fn bench(args: BenchArgs) -> Result<(), String> {
+    let run = args.run;
+    let parts = match run.part {
+        Some(part) => vec![part],
+        None => Part::ALL.to_vec(),
+    };
+    let stdin = read_stdin(&run.input)?;
+
+    let mut reports = vec![];
+    let mut failed = false;
+    for entry in run.days {
+        let name = input_name(entry, &run.input);
+        let content = match load_input(&name, &run.input, &stdin) {
+            Some(Ok(content)) => content,
+            Some(Err(e)) => {
+                eprintln!("{}: {}", entry.name, e);
+                failed = true;
+                continue;
+            }
+            None => {
+                println!("{}: no input at {}", entry.name, name);
+                continue;
+            }
+        };
+
+        let solver = entry.build(&run.params)?;
+        for part in parts.iter() {
+            match bench::bench(entry.name, solver.as_ref(), &content, *part, args.runs) {
+                Ok(report) => reports.push(report),
+                Err(e) => {
+                    eprintln!("{} {}: {}", entry.name, part, e.in_file(&name));
+                    failed = true;
+                    break;
+                }
+            }
+        }
+    }
 
+    print!("{}", bench::to_table(&reports));
+    if let Some((path, format)) = args.output {
+        let out = match format {
+            Format::Csv => bench::to_csv(&reports),
+            Format::Json => bench::to_json(&reports),
+        };
+        fs::write(&path, out).map_err(|e| format!("unable to write {}: {}", path, e))?;
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn animate(args: AnimateArgs) -> Result<(), String> {
+    let entry = args.run.days[0];
+    let part = args.run.part.unwrap_or(Part::One);
+    let stdin = read_stdin(&args.run.input)?;
+    let name = input_name(entry, &args.run.input);
+    let content = load_input(&name, &args.run.input, &stdin)
+        .ok_or(format!("{}: no input at {}", entry.name, name))?
+        .map_err(|e| e.to_string())?;
+    // only to reject options the day does not take
+    entry.build(&args.run.params)?;
+
+    let created = |path: &str, e: io::Error| format!("unable to create {}: {}", path, e);
+    let mut sink: Box<dyn FrameSink> = match &args.output {
+        FrameOutput::Terminal => Box::new(Terminal::stdout(args.delay)),
+        FrameOutput::Text(path) => Box::new(TextFile::create(path).map_err(|e| created(path, e))?),
+        FrameOutput::Images(path, format) => {
+            Box::new(Images::new(path, *format, args.scale).map_err(|e| created(path, e))?)
+        }
+    };
+    let animate = entry.animate.unwrap();
+    animate(
+        &name,
+        &content,
+        part,
+        &mut Every::new(sink.as_mut(), args.every),
+    )
+}
+
+fn generate(args: GenerateArgs) -> Result<(), String> {
+    let seed = args.seed.unwrap_or_else(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let seed = now.as_nanos() as u64;
+        eprintln!("seed {}", seed);
+        seed
+    });
+    let input = (args.day.generate)(&mut Rng::new(seed), args.size);
+    match args.output {
+        Some(path) => {
+            fs::write(&path, input).map_err(|e| format!("unable to write {}: {}", path, e))
+        }
+        None => {
+            print!("{}", input);
+            Ok(())
+        }
+    }
+}
+
+fn check(args: CheckArgs) -> Result<(), String> {
+    let manifest = answers::parse_manifest(&args.manifest).map_err(|e| e.to_string())?;
+    let manifest = manifest
+        .iter()
+        .filter(|expected| args.day.is_none_or(|entry| entry.name == expected.day))
+        .collect::<Vec<_>>();
+    if manifest.is_empty() {
+        return Err(format!("no answers in {}", args.manifest));
+    }
+
+    println!("{:<8} {:<6} {:<20} result", "day", "part", "input");
+    let mut failed = 0;
+    for expected in manifest.iter() {
+        let result = registry::find(&expected.day)
+            .ok_or(format!("unknown day {}", expected.day))
+            .and_then(|entry| entry.build(&Params::new()))
+            .and_then(|solver| {
+                solver
+                    .solve(&expected.input, expected.part)
+                    .map_err(|e| e.to_string())
+            });
+        let status = match result {
+            Ok(answer) if expected.matches(&answer) => "ok".to_string(),
+            Ok(answer) => format!(
+                "FAIL expected {}, got {}",
+                answers::escape(&expected.answer),
+                answers::escape(&answer.to_string())
+            ),
+            Err(e) => format!("FAIL {}", e),
+        };
+        if status != "ok" {
+            failed += 1;
+        }
+        println!(
+            "{:<8} {:<6} {:<20} {}",
+            expected.day,
+            expected.part.to_string(),
+            expected.input,
+            status
+        );
+    }
+
+    println!("{} passed, {} failed", manifest.len() - failed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn serve(args: ServeArgs) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", args.port))
+        .map_err(|e| format!("unable to listen on port {}: {}", args.port, e))?;
+    println!("listening on http://127.0.0.1:{}", args.port);
+    serve::serve(listener, args.timeout);
+    Ok(())
+}
+
+fn repl(day: Option<&'static Entry>) -> Result<(), String> {
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    repl::run(day, stdin.lock(), io::stdout(), prompt).map_err(|e| e.to_string())
+}
+
+fn new_day(day: u8) -> Result<(), String> {
+    let (created, changed) = scaffold::new_day(Path::new("."), day)?;
+    for path in created {
+        println!("created {}", path.display());
+    }
+    for path in changed {
+        println!("updated {}", path.display());
+    }
     println!(
-        "day8 part2: {:?}",
-        day8::get_scenic_score("src/inputs/day8")
+        "next: the example in src/specs/day{0}, its answers in src/specs/day{0}.expected, then part1 and part2",
+        day
     );
+    Ok(())
+}
+
+fn clear_cache() -> Result<(), String> {
+    let removed = Cache::default()
+        .clear()
+        .map_err(|e| format!("unable to clear {}: {}", cache::DIR, e))?;
+    println!("removed {} cached answers", removed);
+    Ok(())
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let result = match cli::parse_args(&args) {
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+        Ok(Command::Run(run_args)) => run(run_args),
+        Ok(Command::Bench(bench_args)) => bench(bench_args),
+        Ok(Command::Animate(animate_args)) => animate(animate_args),
+        Ok(Command::Check(check_args)) => check(check_args),
+        Ok(Command::Generate(generate_args)) => generate(generate_args),
+        Ok(Command::ClearCache) => clear_cache(),
+        Ok(Command::Serve(serve_args)) => serve(serve_args),
+        Ok(Command::Repl(day)) => repl(day),
+        Ok(Command::NewDay(day)) => new_day(day),
+        Err(e) => Err(e),
+    };
 
-    // println!(
-    //     "day10 part1: {:?}",
-    //     day10::parse_input("src/inputs/day10").0
-    // );
-    // println!("day10 part2:");
-
-    // let parsed = day10::parse_input("src/inputs/day10").1;
-    // let mut result = parsed.split("").collect::<Vec<&str>>();
-    // result.pop();
-
-    // print!(
-    //     "{}",
-    //     result
-    //         .chunks(40)
-    //         .map(|x| x.join(""))
-    //         .map(|x| format!("{}{}", x, '\n'))
-    //         .collect::<String>()
-    // );
-
-    // println!("\n");
-
-    // println!("day9 part1: {:?}", day9::parse_input("src/inputs/day9", 1));
-    // println!("day9 part2: {:?}", day9::parse_input("src/inputs/day9", 9));
-    // println!(
-    //     "day11 part1: {:?}",
-    //     day11::monkeys_to_string("src/inputs/day11", 20)
-    // );
-
-    // println!(
-    //     "day11 part2: {:?}",
-    //     day11_2::monkeys_to_string("src/inputs/day11", 10000)
-    // );
-
-    // println!("day12 part1: {:?}", day12::get_path("src/inputs/day12"));
-    // println!(
-    //     "day12 part2: {:?}",
-    //     day12::get_path_part2("src/inputs/day12")
-    // );
-
-    // println!(
-    //     "day13 part1: {:?}",
-    //     day13::parse_input("src/inputs/day13").0
-    // );
-
-    // println!(
-    //     "day13 part2: {:?}",
-    //     day13::parse_input("src/inputs/day13").1
-    // );
-
-    // println!(
-    //     "day14 part1: {:?}",
-    //     day14::parse_input("src/inputs/day14").0
-    // );
-
-    // println!(
-    //     "day14 part2: {:?}",
-    //     day14::parse_input("src/inputs/day14").1 + 1
-    // );
-
-    // println!("day15 part1: {:?}", day15::part_1("src/inputs/day15"));
-    // println!(
-    //     "day15 part2: {:?}",
-    //     day15::part_2(
-    //         "src/inputs/day15",
-    //         day15::Point::new(0, 0),
-    //         day15::Point::new(4000000, 4000000)
-    //     )
-    // );
-
-    // println!("day 16 part1: {}", day16::part_1("src/inputs/day16"));
-
-    // println!("day 17 part1: {}", day17::part_1("src/inputs/day17"));
-    // println!("day 17 part2: {}", day17::part_2("src/inputs/day17"));
-
-    // println!("day 18 part1: {}", day18::part_1("src/inputs/day18"));
-    // println!("day 18 part2: {}", day18::part_2("src/inputs/day18"));
-    // println!("day 18 part2: {}", day18::part_2("src/inputs/day18"));
-
-    println!("day 20 part1: {}", day20::part_1("src/inputs/day20"));
-    println!("day 20 part2: {}", day20::part_2("src/inputs/day20"));
-    println!("day 21 part1: {:?}", day21::parse_input("src/inputs/day21"));
+    if let Err(e) = result {
+        eprintln!("error: {}\n\n{}", e, cli::USAGE);
+        process::exit(2);
+    }
 }
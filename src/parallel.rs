@@ -0,0 +1,345 @@
+use crate::cache::{Cache, Key};
+use crate::json::Json;
+use crate::progress::Progress;
+use crate::registry::Entry;
+use crate::solution::{Answer, Details, Params, Part};
+use std::any::Any;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Runs every day and part on its own, spread over a few threads, and keeps
+// whatever each of them gave back, panics included, for one report at the end.
+
+pub struct Task<'a> {
+    pub entry: &'static Entry,
+    pub part: Part,
+    // name of the input for error messages
+    pub name: &'a str,
+    // the input itself, or why it could not be read
+    pub input: Result<&'a str, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Solved(Answer),
+    Failed(String),
+    Panicked(String),
+}
+
+impl Outcome {
+    // Timed out solvers count as failures too, they gave no answer.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Outcome::Solved(answer) if *answer != Answer::TimedOut)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskReport {
+    pub day: &'static str,
+    pub part: Part,
+    pub time: Duration,
+    pub outcome: Outcome,
+    // cached answers come without their details
+    pub details: Details,
+    pub cached: bool,
+}
+
+pub fn panic_message(e: &(dyn Any + Send)) -> String {
+    e.downcast_ref::<String>()
+        .cloned()
+        .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+fn run_task(
+    task: &Task,
+    params: &Params,
+    timeout: Option<Duration>,
+    cache: Option<&Cache>,
+    details: bool,
+) -> TaskReport {
+    let start = Instant::now();
+    let key = task
+        .input
+        .as_ref()
+        .ok()
+        .zip(cache)
+        .filter(|_| task.entry.cacheable(params))
+        .map(|(input, cache)| (Key::new(task.entry, task.part, params, input), cache));
+    if let Some(answer) = key.as_ref().and_then(|(key, cache)| cache.get(key)) {
+        return TaskReport {
+            day: task.entry.name,
+            part: task.part,
+            time: start.elapsed(),
+            outcome: Outcome::Solved(answer),
+            details: vec![],
+            cached: true,
+        };
+    }
+
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        let input = task.input.clone()?;
+        let solver = task.entry.build(params)?;
+        let mut progress = Progress::new();
+        if let Some(timeout) = timeout {
+            progress = progress.with_timeout(timeout);
+        }
+        solver
+            .solve_timed_with(input, task.part, &progress, details)
+            .map_err(|e| e.in_file(task.name).to_string())
+    }));
+    // the time spent on details is left out
+    let (time, outcome, details) = match outcome {
+        Ok(Ok(timed)) => (
+            timed.parse + timed.solve,
+            Outcome::Solved(timed.answer),
+            timed.details,
+        ),
+        Ok(Err(e)) => (start.elapsed(), Outcome::Failed(e), vec![]),
+        Err(e) => (
+            start.elapsed(),
+            Outcome::Panicked(panic_message(e.as_ref())),
+            vec![],
+        ),
+    };
+    if let (Some((key, cache)), Outcome::Solved(answer)) = (&key, &outcome) {
+        if let Err(e) = cache.put(key, answer) {
+            eprintln!("unable to cache {} {}: {}", task.entry.name, task.part, e);
+        }
+    }
+    TaskReport {
+        day: task.entry.name,
+        part: task.part,
+        time,
+        outcome,
+        details,
+        cached: false,
+    }
+}
+
+// Reports come back in the order of `tasks`, whichever finished first.
+// Details are only worked out when asked for, they are only written out as
+// JSON.
+pub fn run(
+    tasks: &[Task],
+    params: &Params,
+    jobs: usize,
+    timeout: Option<Duration>,
+    cache: Option<&Cache>,
+    details: bool,
+) -> Vec<TaskReport> {
+    let next = AtomicUsize::new(0);
+    let mut done = thread::scope(|scope| {
+        let workers = (0..jobs.clamp(1, tasks.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match tasks.get(i) {
+                            Some(task) => {
+                                done.push((i, run_task(task, params, timeout, cache, details)))
+                            }
+                            None => return done,
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    done.sort_by_key(|(i, _)| *i);
+    done.into_iter().map(|(_, report)| report).collect()
+}
+
+pub fn to_table(reports: &[TaskReport]) -> String {
+    let mut out = format!("{:<8} {:<6} {:>10}  result\n", "day", "part", "time ms");
+    for r in reports {
+        let mut result = match &r.outcome {
+            // multi line answers (day10) stay lined up under the first line
+            Outcome::Solved(answer) => answer.to_string().replace('\n', &format!("\n{:28}", "")),
+            Outcome::Failed(e) => format!("FAIL {}", e),
+            Outcome::Panicked(message) => format!("PANIC {}", message),
+        };
+        if r.cached {
+            result.push_str(" (cached)");
+        }
+        writeln!(
+            out,
+            "{:<8} {:<6} {:>10.3}  {}",
+            r.day,
+            r.part.to_string(),
+            r.time.as_secs_f64() * 1000.0,
+            result
+        )
+        .unwrap();
+    }
+    let failed = reports.iter().filter(|r| !r.outcome.is_ok()).count();
+    writeln!(out, "{} solved, {} failed", reports.len() - failed, failed).unwrap();
+    out
+}
+
+pub fn report_json(r: &TaskReport) -> Json {
+    let (status, answer, error) = match &r.outcome {
+        Outcome::Solved(Answer::Unsolved) => ("unsolved", Json::Null, None),
+        Outcome::Solved(Answer::TimedOut) => ("timed out", Json::Null, None),
+        Outcome::Solved(answer) => ("solved", answer.into(), None),
+        Outcome::Failed(e) => ("failed", Json::Null, Some(e.clone())),
+        Outcome::Panicked(message) => ("panicked", Json::Null, Some(message.clone())),
+    };
+    Json::object([
+        ("day", Json::from(r.day)),
+        ("part", Json::from(r.part.number() as usize)),
+        ("status", Json::from(status)),
+        ("answer", answer),
+        ("details", Json::object(r.details.clone())),
+        ("time_us", Json::from(r.time.as_micros() as usize)),
+        ("cached", Json::from(r.cached)),
+        ("error", Json::from(error)),
+    ])
+}
+
+// One object per line, `answer` is null unless `status` is "solved".
+pub fn to_json(reports: &[TaskReport]) -> String {
+    let rows = reports
+        .iter()
+        .map(|r| format!("  {}", report_json(r)))
+        .collect::<Vec<String>>();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+    use crate::registry;
+    use crate::solution::{Solution, Solver};
+    use crate::ParseError;
+
+    #[derive(Default)]
+    struct Panics;
+
+    impl Solution for Panics {
+        type Input = ();
+
+        fn parse_str(&self, _input: &str) -> Result<(), ParseError> {
+            Ok(())
+        }
+
+        fn part1(&self, _input: &()) -> Answer {
+            panic!("no answer")
+        }
+
+        fn part2(&self, _input: &()) -> Answer {
+            Answer::Number(2)
+        }
+    }
+
+    fn panics(_params: &Params) -> Result<Box<dyn Solver>, String> {
+        Ok(Box::new(Panics))
+    }
+
+    const PANICS: Entry = Entry {
+        name: "panics",
+        day: 0,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: panics,
+        animate: None,
+        simulate: None,
+        generate: generate::day1,
+    };
+
+    #[test]
+    fn test_run() {
+        let day1 = registry::find("day1").unwrap();
+        let spec = std::fs::read_to_string(day1.spec_path()).unwrap();
+        let tasks = [
+            (day1, Part::One, Ok(spec.as_str())),
+            (&PANICS, Part::One, Ok("")),
+            (day1, Part::Two, Ok("1\n\nx\n")),
+            (&PANICS, Part::Two, Ok("")),
+            (day1, Part::Two, Err("missing".to_string())),
+        ]
+        .map(|(entry, part, input)| Task {
+            entry,
+            part,
+            name: "input",
+            input,
+        });
+        let reports = run(&tasks, &Params::new(), 3, None, None, true);
+
+        let outcomes = reports
+            .iter()
+            .map(|r| r.outcome.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(outcomes[0], Outcome::Solved(Answer::Number(24000)));
+        assert_eq!(outcomes[1], Outcome::Panicked("no answer".to_string()));
+        assert!(matches!(outcomes[2], Outcome::Failed(_)));
+        assert_eq!(outcomes[3], Outcome::Solved(Answer::Number(2)));
+        assert_eq!(outcomes[4], Outcome::Failed("missing".to_string()));
+        assert_eq!(reports[1].day, "panics");
+
+        let table = to_table(&reports);
+        assert_eq!(table.lines().count(), 7);
+        assert!(table.ends_with("2 solved, 3 failed\n"));
+
+        let json = to_json(&reports);
+        let lines = json.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[1].starts_with(
+            r#"  {"day": "day1", "part": 1, "status": "solved", "answer": 24000, "details": {"elves": [3]}, "time_us": "#
+        ));
+        assert!(lines[2].contains(r#""status": "panicked", "answer": null, "details": {}"#));
+        assert!(lines[5].ends_with(r#""error": "missing"}"#));
+    }
+
+    #[test]
+    fn test_cached() {
+        let dir = std::env::temp_dir().join(format!("advent-parallel-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let day1 = registry::find("day1").unwrap();
+        let spec = std::fs::read_to_string(day1.spec_path()).unwrap();
+        let tasks = [Task {
+            entry: day1,
+            part: Part::Two,
+            name: "input",
+            input: Ok(spec.as_str()),
+        }];
+        let mut params = Params::new();
+        params.set("above", "10000");
+
+        let solved = run(&tasks, &Params::new(), 1, None, Some(&cache), true).remove(0);
+        let cached = run(&tasks, &Params::new(), 1, None, Some(&cache), true).remove(0);
+        assert!(!solved.cached && cached.cached);
+        assert_eq!(cached.outcome, Outcome::Solved(Answer::Number(45000)));
+        assert_eq!(cached.details, []);
+
+        // the answers to day1 queries are only in the details
+        let tasks = [Task {
+            entry: day1,
+            part: Part::One,
+            name: "input",
+            input: Ok(spec.as_str()),
+        }];
+        let mut params = Params::new();
+        params.set("above", "10000");
+        for _ in 0..2 {
+            let queried = run(&tasks, &params, 1, None, Some(&cache), true).remove(0);
+            assert!(!queried.cached);
+            assert_eq!(
+                Json::object(queried.details).to_string(),
+                r#"{"elves": [3], "above": [2, 3]}"#
+            );
+        }
+        cache.clear().unwrap();
+        std::fs::remove_dir(dir).unwrap();
+    }
+}
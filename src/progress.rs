@@ -0,0 +1,138 @@
+use std::cell::Cell;
+use std::fmt::{Display, Error, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Handed to the long running solvers so they can tell the runner how far
+// they got and stop early once it gives up on them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl Display for TimedOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "timed out")
+    }
+}
+
+// How far a solver got, `total` is known for the ones scanning a fixed range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+// `45% (1800000/4000000)` or `1200 steps`
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.total {
+            Some(total) if total > 0 => {
+                write!(f, "{}% ({}/{})", self.done * 100 / total, self.done, total)
+            }
+            _ => write!(f, "{} steps", self.done),
+        }
+    }
+}
+
+pub struct Progress<'a> {
+    deadline: Option<Instant>,
+    cancelled: Option<&'a AtomicBool>,
+    report: Option<&'a dyn Fn(Step)>,
+    // reports are at most this often, so printing them costs nothing
+    every: Duration,
+    next_report: Cell<Instant>,
+}
+
+impl Progress<'static> {
+    // Never stops and reports nothing, for callers that just want the answer.
+    pub fn none() -> Progress<'static> {
+        Progress::new()
+    }
+}
+
+impl<'a> Progress<'a> {
+    pub fn new() -> Progress<'a> {
+        Progress {
+            deadline: None,
+            cancelled: None,
+            report: None,
+            every: Duration::from_millis(500),
+            next_report: Cell::new(Instant::now()),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Progress<'a> {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    // Stops the solver as soon as `cancelled` is set, from any thread.
+    pub fn with_cancel(mut self, cancelled: &'a AtomicBool) -> Progress<'a> {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    // `report` is called at most every `every`, starting `every` from now.
+    pub fn with_report(mut self, every: Duration, report: &'a dyn Fn(Step)) -> Progress<'a> {
+        self.report = Some(report);
+        self.every = every;
+        self.next_report.set(Instant::now() + every);
+        self
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.cancelled
+            .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // Called by solvers as they go, Err once they should give up.
+    pub fn step(&self, done: u64, total: Option<u64>) -> Result<(), TimedOut> {
+        if self.is_stopped() {
+            return Err(TimedOut);
+        }
+        if let Some(report) = self.report {
+            let now = Instant::now();
+            if now >= self.next_report.get() {
+                self.next_report.set(now + self.every);
+                report(Step { done, total });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Progress<'_> {
+    fn default() -> Self {
+        Progress::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_progress() {
+        assert_eq!(Progress::none().step(10, None), Ok(()));
+
+        let timed_out = Progress::new().with_timeout(Duration::ZERO);
+        assert_eq!(timed_out.step(1, Some(10)), Err(TimedOut));
+
+        let cancelled = AtomicBool::new(false);
+        let progress = Progress::new().with_cancel(&cancelled);
+        assert_eq!(progress.step(1, None), Ok(()));
+        cancelled.store(true, Ordering::Relaxed);
+        assert_eq!(progress.step(2, None), Err(TimedOut));
+
+        let steps = RefCell::new(vec![]);
+        let report = |step: Step| steps.borrow_mut().push(step.to_string());
+        let progress = Progress::new().with_report(Duration::ZERO, &report);
+        progress.step(1, Some(4)).unwrap();
+        progress.step(7, None).unwrap();
+        assert_eq!(*steps.borrow(), ["25% (1/4)", "7 steps"]);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::frames::{Animate, FrameSink};
+use crate::generate::{self, Generator};
+use crate::input::normalise;
+use crate::simulation::{Simulate, Simulation};
+use crate::solution::{FromParams, Params, Part, Solver};
+use crate::{
+    day1, day10, day11, day11_2, day12, day13, day14, day15, day16, day17, day18, day19, day2,
+    day20, day21, day3, day4, day5, day6, day7, day8, day9,
+};
+
+pub struct Entry {
+    pub name: &'static str,
+    pub day: u8,
+    // Bumped whenever a fix changes the answers, so cached ones are not used.
+    pub version: u32,
+    pub params: &'static [&'static str],
+    // Those of `params` that only add to the details. The cache keeps no
+    // details, so answers asked with one of them are never taken from it.
+    pub detail_params: &'static [&'static str],
+    pub create: fn(&Params) -> Result<Box<dyn Solver>, String>,
+    // Replays the simulation of a part as frames, for the days that have one.
+    pub animate: Option<Animator>,
+    // Steps through the simulation of a part, for the repl.
+    pub simulate: Option<Simulator>,
+    // Writes a random input of about the given size.
+    pub generate: Generator,
+}
+
+// input name, input, part, sink
+pub type Animator = fn(&str, &str, Part, &mut dyn FrameSink) -> Result<(), String>;
+
+// input name, input, part
+pub type Simulator = fn(&str, &str, Part) -> Result<Box<dyn Simulation>, String>;
+
+impl Entry {
+    // Rejects options the day does not know about before building it, so a
+    // typo like `--rows` is not silently ignored.
+    pub fn build(&self, params: &Params) -> Result<Box<dyn Solver>, String> {
+        if let Some(key) = params.keys().find(|k| !self.params.contains(&k.as_str())) {
+            return Err(format!("{} does not take --{}", self.name, key));
+        }
+        (self.create)(params)
+    }
+
+    pub fn cacheable(&self, params: &Params) -> bool {
+        !params
+            .keys()
+            .any(|k| self.detail_params.contains(&k.as_str()))
+    }
+
+    pub fn input_path(&self) -> String {
+        format!("src/inputs/day{}", self.day)
+    }
+
+    pub fn spec_path(&self) -> String {
+        format!("src/specs/day{}", self.day)
+    }
+}
+
+fn boxed<S: Solver + Default + 'static>(_params: &Params) -> Result<Box<dyn Solver>, String> {
+    Ok(Box::<S>::default())
+}
+
+fn configured<S: Solver + FromParams + 'static>(
+    params: &Params,
+) -> Result<Box<dyn Solver>, String> {
+    Ok(Box::new(S::from_params(params)?))
+}
+
+fn animated<S: Animate + Default>(
+    name: &str,
+    input: &str,
+    part: Part,
+    sink: &mut dyn FrameSink,
+) -> Result<(), String> {
+    let solution = S::default();
+    let input = solution
+        .parse_str(&normalise(input))
+        .map_err(|e| e.in_file(name).to_string())?;
+    solution
+        .animate(&input, part, sink)
+        .map_err(|e| format!("unable to write frames: {}", e))
+}
+
+fn simulated<S: Simulate + Default>(
+    name: &str,
+    input: &str,
+    part: Part,
+) -> Result<Box<dyn Simulation>, String> {
+    let solution = S::default();
+    let input = solution
+        .parse_str(&normalise(input))
+        .map_err(|e| e.in_file(name).to_string())?;
+    Ok(solution.simulate(&input, part))
+}
+
+pub const DAYS: [Entry; 22] = [
+    Entry {
+        name: "day1",
+        day: 1,
+        version: 1,
+        params: &["total-elves", "above", "target", "tolerance"],
+        detail_params: &["above", "target", "tolerance"],
+        create: configured::<day1::Day1>,
+        animate: None,
+        simulate: None,
+        generate: generate::day1,
+    },
+    Entry {
+        name: "day2",
+        day: 2,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day2::Day2>,
+        animate: None,
+        simulate: None,
+        generate: generate::day2,
+    },
+    Entry {
+        name: "day3",
+        day: 3,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day3::Day3>,
+        animate: None,
+        simulate: None,
+        generate: generate::day3,
+    },
+    Entry {
+        name: "day4",
+        day: 4,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day4::Day4>,
+        animate: None,
+        simulate: None,
+        generate: generate::day4,
+    },
+    Entry {
+        name: "day5",
+        day: 5,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day5::Day5>,
+        animate: None,
+        simulate: None,
+        generate: generate::day5,
+    },
+    Entry {
+        name: "day6",
+        day: 6,
+        version: 2,
+        params: &["window", "window-part2"],
+        detail_params: &[],
+        create: configured::<day6::Day6>,
+        animate: None,
+        simulate: None,
+        generate: generate::day6,
+    },
+    Entry {
+        name: "day7",
+        day: 7,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day7::Day7>,
+        animate: None,
+        simulate: None,
+        generate: generate::day7,
+    },
+    Entry {
+        name: "day8",
+        day: 8,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day8::Day8>,
+        animate: None,
+        simulate: None,
+        generate: generate::day8,
+    },
+    Entry {
+        name: "day9",
+        day: 9,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day9::Day9>,
+        animate: Some(animated::<day9::Day9>),
+        simulate: Some(simulated::<day9::Day9>),
+        generate: generate::day9,
+    },
+    Entry {
+        name: "day10",
+        day: 10,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day10::Day10>,
+        animate: Some(animated::<day10::Day10>),
+        simulate: Some(simulated::<day10::Day10>),
+        generate: generate::day10,
+    },
+    Entry {
+        name: "day11",
+        day: 11,
+        version: 1,
+        params: &["rounds", "rounds-part2"],
+        detail_params: &[],
+        create: configured::<day11::Day11>,
+        animate: None,
+        simulate: None,
+        generate: generate::day11,
+    },
+    Entry {
+        name: "day11_2",
+        day: 11,
+        version: 1,
+        params: &["rounds"],
+        detail_params: &[],
+        create: configured::<day11_2::Day11Part2>,
+        animate: None,
+        simulate: None,
+        generate: generate::day11,
+    },
+    Entry {
+        name: "day12",
+        day: 12,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day12::Day12>,
+        animate: None,
+        simulate: None,
+        generate: generate::day12,
+    },
+    Entry {
+        name: "day13",
+        day: 13,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day13::Day13>,
+        animate: None,
+        simulate: None,
+        generate: generate::day13,
+    },
+    Entry {
+        name: "day14",
+        day: 14,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day14::Day14>,
+        animate: Some(animated::<day14::Day14>),
+        simulate: Some(simulated::<day14::Day14>),
+        generate: generate::day14,
+    },
+    Entry {
+        name: "day15",
+        day: 15,
+        version: 1,
+        params: &["row", "min", "max"],
+        detail_params: &[],
+        create: configured::<day15::Day15>,
+        animate: None,
+        simulate: None,
+        generate: generate::day15,
+    },
+    Entry {
+        name: "day16",
+        day: 16,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day16::Day16>,
+        animate: None,
+        simulate: None,
+        generate: generate::day16,
+    },
+    Entry {
+        name: "day17",
+        day: 17,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day17::Day17>,
+        animate: Some(animated::<day17::Day17>),
+        simulate: Some(simulated::<day17::Day17>),
+        generate: generate::day17,
+    },
+    Entry {
+        name: "day18",
+        day: 18,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day18::Day18>,
+        animate: None,
+        simulate: None,
+        generate: generate::day18,
+    },
+    Entry {
+        name: "day19",
+        day: 19,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day19::Day19>,
+        animate: None,
+        simulate: None,
+        generate: generate::day19,
+    },
+    Entry {
+        name: "day20",
+        day: 20,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day20::Day20>,
+        animate: None,
+        simulate: None,
+        generate: generate::day20,
+    },
+    Entry {
+        name: "day21",
+        day: 21,
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day21::Day21>,
+        animate: None,
+        simulate: None,
+        generate: generate::day21,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Entry> {
+    DAYS.iter().find(|e| e.name == name)
+}
+
+pub fn for_day(day: u8) -> impl Iterator<Item = &'static Entry> {
+    DAYS.iter().filter(move |e| e.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Answer;
+
+    #[test]
+    fn test_registry() {
+        // sorted by day, one name each, and every name finds its own entry
+        assert!(DAYS.windows(2).all(|pair| pair[0].day <= pair[1].day));
+        assert!(DAYS.iter().all(|entry| (1..=25).contains(&entry.day)));
+        for entry in DAYS.iter() {
+            assert_eq!(DAYS.iter().filter(|e| e.name == entry.name).count(), 1);
+            assert_eq!(find(entry.name).unwrap().day, entry.day);
+            assert!(entry.detail_params.iter().all(|p| entry.params.contains(p)));
+        }
+        assert_eq!(for_day(11).count(), 2);
+        assert_eq!(find("day11_2").unwrap().input_path(), "src/inputs/day11");
+        assert!(find("day99").is_none());
+    }
+
+    #[test]
+    fn test_build_params() {
+        let mut params = Params::new();
+        params.set("row", "10");
+        params.set("max", "20,20");
+        let solver = find("day15").unwrap().build(&params).unwrap();
+        assert_eq!(
+            solver.solve_all("src/specs/day15").unwrap(),
+            [Answer::Number(26), Answer::Number(56000011)]
+        );
+
+        params.set("rows", "10");
+        assert!(find("day15").unwrap().build(&params).is_err());
+        assert!(find("day1").unwrap().build(&Params::new()).is_ok());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::error::read_input;
+use crate::registry::{self, Entry};
+use crate::simulation::Simulation;
+use crate::solution::{Params, Part};
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+// Loads a day's input, solves it and steps through its simulation one
+// command at a time, for looking around without adding prints to a day.
+
+pub const HELP: &str = "commands:
+  day N                   pick a day, forgets the input and the simulation
+  load [PATH|example]     load the day's input, its example or a file
+  set NAME VALUE          set a tunable, as --NAME VALUE would
+  run [1|2]               solve one or both parts of the loaded input
+  sim [1|2]               start the simulation of a part (days 9, 10, 14, 17)
+  step [N]                advance the simulation by N steps, 1 by default
+  show                    print the state of the simulation
+  reset                   start the simulation over
+  help                    this text
+  quit";
+
+// Steps printed one per line, beyond that only the last one is.
+const SHOWN_STEPS: usize = 10;
+
+#[derive(Default)]
+pub struct Repl {
+    entry: Option<&'static Entry>,
+    params: Params,
+    // name and content
+    input: Option<(String, String)>,
+    simulation: Option<(Part, Box<dyn Simulation>)>,
+}
+
+fn parse_part(arg: Option<&str>) -> Result<Option<Part>, String> {
+    match arg {
+        None => Ok(None),
+        Some("1") => Ok(Some(Part::One)),
+        Some("2") => Ok(Some(Part::Two)),
+        Some(part) => Err(format!("unknown part: {}", part)),
+    }
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        Repl::default()
+    }
+
+    fn entry(&self) -> Result<&'static Entry, String> {
+        self.entry
+            .ok_or("pick a day first, e.g. day 14".to_string())
+    }
+
+    fn input(&self) -> Result<&(String, String), String> {
+        self.input
+            .as_ref()
+            .ok_or("load an input first, e.g. load example".to_string())
+    }
+
+    fn simulation(&mut self) -> Result<&mut Box<dyn Simulation>, String> {
+        match self.simulation.as_mut() {
+            Some((_, simulation)) => Ok(simulation),
+            None => Err("start a simulation first, e.g. sim 1".to_string()),
+        }
+    }
+
+    fn start(&mut self, part: Part) -> Result<String, String> {
+        let entry = self.entry()?;
+        let simulate = entry
+            .simulate
+            .ok_or(format!("{} has no simulation to step through", entry.name))?;
+        let (name, content) = self.input()?;
+        let simulation = simulate(name, content, part)?;
+        let shown = simulation.to_string();
+        self.simulation = Some((part, simulation));
+        Ok(shown)
+    }
+
+    fn step(&mut self, count: usize) -> Result<String, String> {
+        let simulation = self.simulation()?;
+        let mut steps = vec![];
+        for _ in 0..count {
+            match simulation.step() {
+                Some(step) => steps.push(step),
+                None => {
+                    steps.push("the simulation is over".to_string());
+                    break;
+                }
+            }
+        }
+        if steps.len() > SHOWN_STEPS {
+            let skipped = steps.len() - 1;
+            steps.drain(..skipped);
+            steps.insert(0, format!("... {} steps", skipped));
+        }
+        Ok(steps.join("\n"))
+    }
+
+    fn run(&self, part: Option<Part>) -> Result<String, String> {
+        let entry = self.entry()?;
+        let (name, content) = self.input()?;
+        let solver = entry.build(&self.params)?;
+        let parts = part.map_or(Part::ALL.to_vec(), |part| vec![part]);
+        let mut out = vec![];
+        for part in parts {
+            let start = Instant::now();
+            let answer = solver
+                .solve_str(content, part)
+                .map_err(|e| e.in_file(name).to_string())?;
+            out.push(format!(
+                "{} {}: {} ({:.3} ms)",
+                entry.name,
+                part,
+                answer,
+                start.elapsed().as_secs_f64() * 1000.0
+            ));
+        }
+        Ok(out.join("\n"))
+    }
+
+    // What to print for one line of input.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            [] => Ok(String::new()),
+            ["help"] => Ok(HELP.to_string()),
+            ["day", day] => {
+                let entry = registry::find(&format!("day{}", day))
+                    .ok_or(format!("unknown day: {}", day))?;
+                *self = Repl {
+                    entry: Some(entry),
+                    ..Repl::default()
+                };
+                Ok(format!("{}, load an input next", entry.name))
+            }
+            ["load", ref rest @ ..] if rest.len() <= 1 => {
+                let entry = self.entry()?;
+                let path = match rest.first() {
+                    None => entry.input_path(),
+                    Some(&"example") => entry.spec_path(),
+                    Some(path) => path.to_string(),
+                };
+                let content = read_input(&path).map_err(|e| e.to_string())?;
+                let loaded = format!("loaded {} lines from {}", content.lines().count(), path);
+                self.input = Some((path, content));
+                self.simulation = None;
+                Ok(loaded)
+            }
+            ["set", name, value] => {
+                let entry = self.entry()?;
+                let mut params = self.params.clone();
+                params.set(name, value);
+                entry.build(&params)?;
+                self.params = params;
+                Ok(format!("--{} {}", name, value))
+            }
+            ["run"] => self.run(None),
+            ["run", part] => self.run(parse_part(Some(part))?),
+            ["sim"] => self.start(Part::One),
+            ["sim", part] => self.start(parse_part(Some(part))?.unwrap()),
+            ["step"] => self.step(1),
+            ["step", count] => {
+                let count = count
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid step count: {}", count))?;
+                self.step(count)
+            }
+            ["show"] => Ok(self.simulation()?.to_string()),
+            ["reset"] => {
+                let part = self.simulation.as_ref().map(|(part, _)| *part);
+                self.start(part.ok_or("no simulation to reset".to_string())?)
+            }
+            _ => Err(format!("unknown command: {}, try help", line.trim())),
+        }
+    }
+}
+
+// Reads commands until `quit` or the end of `input`, the prompt is only
+// worth showing when someone is typing.
+pub fn run(
+    day: Option<&'static Entry>,
+    input: impl BufRead,
+    mut output: impl Write,
+    prompt: bool,
+) -> io::Result<()> {
+    let mut repl = Repl {
+        entry: day,
+        ..Repl::default()
+    };
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        match repl.execute(&line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => writeln!(output, "{}", out.trim_end())?,
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(commands: &str) -> String {
+        let mut out = vec![];
+        run(None, commands.as_bytes(), &mut out, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_repl() {
+        let out = session(
+            "step\nday 14\nrun\nload example\nrun 2\nsim\nstep 2\nstep 30\nreset\nquit\nshow\n",
+        );
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "error: start a simulation first, e.g. sim 1");
+        assert_eq!(lines[1], "day14, load an input next");
+        assert_eq!(lines[2], "error: load an input first, e.g. load example");
+        assert_eq!(lines[3], "loaded 2 lines from src/specs/day14");
+        assert!(lines[4].starts_with("day14 part2: 93 ("));
+        assert_eq!(lines[5], "0 pebbles dropped");
+        assert_eq!(lines[16], "pebble 1 rests at 500,8");
+        assert_eq!(lines[17], "pebble 2 rests at 499,8");
+        assert_eq!(lines[18], "... 23 steps");
+        assert_eq!(lines[19], "the simulation is over");
+        assert_eq!(lines[20], "0 pebbles dropped");
+        assert!(!out.contains("25 pebbles"));
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut repl = Repl::new();
+        assert!(repl.execute("day 99").is_err());
+        repl.execute("day 15").unwrap();
+        repl.execute("load example").unwrap();
+        assert!(repl.execute("set rows 10").is_err());
+        repl.execute("set row 10").unwrap();
+        assert!(repl
+            .execute("run 1")
+            .unwrap()
+            .starts_with("day15 part1: 26 ("));
+        assert_eq!(
+            repl.execute("sim").unwrap_err(),
+            "day15 has no simulation to step through"
+        );
+
+        repl.execute("day 10").unwrap();
+        repl.execute("load example").unwrap();
+        repl.execute("sim 2").unwrap();
+        assert_eq!(repl.execute("step").unwrap(), "cycle 0: X is 1, draws #");
+        repl.execute("step 40").unwrap();
+        let shown = repl.execute("show").unwrap();
+        assert!(shown.starts_with("cycle 40, X is 1\n##..##..##..##..##..##..##..##..##..##..\n#"));
+        assert!(repl.execute("frobnicate").is_err());
+    }
+}
@@ -0,0 +1,365 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+// Starts a new day: its module from a template, an empty example and input,
+// and the lines registering it in lib.rs, the registry and the generators.
+// Nothing is written unless every file can be, existing files are never
+// touched beyond adding those lines.
+
+const TEMPLATE: &str = "use crate::error::{from_path, ParseResult};
+use crate::input::lines;
+use crate::solution::{Answer, Solution};
+
+pub fn parse_str(input: &str) -> ParseResult<Vec<String>> {
+    Ok(lines(input).map(|line| line.text.to_string()).collect())
+}
+
+pub fn parse_input(path: &str) -> ParseResult<Vec<String>> {
+    from_path(path, parse_str)
+}
+
+#[derive(Default)]
+pub struct Day{n};
+
+impl Solution for Day{n} {
+    type Input = Vec<String>;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input> {
+        parse_str(input)
+    }
+
+    fn part1(&self, _input: &Self::Input) -> Answer {
+        Answer::Unsolved
+    }
+
+    fn part2(&self, _input: &Self::Input) -> Answer {
+        Answer::Unsolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day{n}() {
+        let input = parse_input(\"src/specs/day{n}\").unwrap();
+        assert_eq!(Day{n}.part1(&input), Answer::Unsolved);
+    }
+}
+";
+
+const ENTRY: &str = "    Entry {
+        name: \"day{n}\",
+        day: {n},
+        version: 1,
+        params: &[],
+        detail_params: &[],
+        create: boxed::<day{n}::Day{n}>,
+        animate: None,
+        simulate: None,
+        generate: generate::day{n},
+    },
+";
+
+const GENERATOR: &str = "// Placeholder lines until day{n} has a real generator.
+pub fn day{n}(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| format!(\"{}\\n\", rng.range(0, 100)))
+        .collect()
+}
+
+";
+
+fn fill(template: &str, day: u8) -> String {
+    template.replace("{n}", &day.to_string())
+}
+
+pub fn module(day: u8) -> String {
+    fill(TEMPLATE, day)
+}
+
+// `pub mod dayN;` among the other modules, which are kept sorted.
+fn register_module(lib: &str, day: u8) -> Result<String, String> {
+    let line = format!("pub mod day{};", day);
+    if lib.lines().any(|l| l == line) {
+        return Err(format!("day{} is already a module", day));
+    }
+    let mut lines = lib.lines().collect::<Vec<_>>();
+    let mods = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| Some((i, l.strip_prefix("pub mod ")?.strip_suffix(';')?)))
+        .collect::<Vec<_>>();
+    let (last, _) = mods.last().ok_or("no modules in lib.rs")?;
+    let name = format!("day{}", day);
+    let at = mods
+        .iter()
+        .find(|(_, m)| *m > name.as_str())
+        .map_or(last + 1, |(i, _)| *i);
+    lines.insert(at, &line);
+    Ok(lines.join("\n") + "\n")
+}
+
+// Lays out `use crate::{...};` the way rustfmt does, on one line when it
+// fits in 100 columns and filling lines of at most 100 otherwise.
+fn use_list(names: &[String]) -> String {
+    let one_line = format!("use crate::{{{}}};", names.join(", "));
+    if one_line.len() <= 100 {
+        return one_line;
+    }
+    let mut out = "use crate::{\n".to_string();
+    let mut line = String::new();
+    for name in names {
+        if !line.is_empty() && line.len() + name.len() + 1 > 100 {
+            out.push_str(line.trim_end());
+            out.push('\n');
+            line.clear();
+        }
+        if line.is_empty() {
+            line.push_str("    ");
+        }
+        line.push_str(name);
+        line.push_str(", ");
+    }
+    out.push_str(line.trim_end());
+    out.push_str("\n};");
+    out
+}
+
+// Where the registry's `use crate::{...};` is and the modules it names.
+fn imports(registry: &str) -> Result<(Range<usize>, Vec<String>), String> {
+    let start = registry
+        .find("use crate::{")
+        .ok_or("no `use crate::{` in the registry")?;
+    let end = start + registry[start..].find("};").ok_or("unterminated use")? + 2;
+    let names = registry[start + "use crate::{".len()..end - 2]
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    Ok((start..end, names))
+}
+
+// Imports the module, grows `DAYS` by one and appends its entry.
+fn register_entry(registry: &str, day: u8) -> Result<String, String> {
+    let name = format!("day{}", day);
+    if registry.contains(&format!("name: \"{}\",", name)) {
+        return Err(format!("{} is already in the registry", name));
+    }
+
+    let (range, mut names) = imports(registry)?;
+    names.push(name);
+    names.sort();
+    let mut out = format!(
+        "{}{}{}",
+        &registry[..range.start],
+        use_list(&names),
+        &registry[range.end..]
+    );
+
+    let days = out
+        .find("pub const DAYS: [Entry; ")
+        .ok_or("no DAYS in the registry")?;
+    let count_at = days + "pub const DAYS: [Entry; ".len();
+    let count_len = out[count_at..].find(']').ok_or("no size for DAYS")?;
+    let count = out[count_at..count_at + count_len]
+        .parse::<usize>()
+        .map_err(|_| "DAYS has no literal size".to_string())?;
+    out.replace_range(count_at..count_at + count_len, &(count + 1).to_string());
+
+    let close = days + out[days..].find("\n];\n").ok_or("unterminated DAYS")? + 1;
+    out.insert_str(close, &fill(ENTRY, day));
+    Ok(out)
+}
+
+// The generator goes last, before the tests.
+fn register_generator(generate: &str, day: u8) -> Result<String, String> {
+    if generate.contains(&format!("pub fn day{}(", day)) {
+        return Err(format!("day{} already has a generator", day));
+    }
+    let at = generate.find("#[cfg(test)]").unwrap_or(generate.len());
+    let mut out = generate.to_string();
+    out.insert_str(at, &fill(GENERATOR, day));
+    Ok(out)
+}
+
+// Files created then files changed, relative to `root`.
+pub fn new_day(root: &Path, day: u8) -> Result<(Vec<PathBuf>, Vec<PathBuf>), String> {
+    if !(1..=25).contains(&day) {
+        return Err(format!("no day {} in advent of code", day));
+    }
+    let created = [
+        (format!("src/day{}.rs", day), module(day)),
+        (format!("src/specs/day{}", day), String::new()),
+        (format!("src/inputs/day{}", day), String::new()),
+    ];
+    for (path, _) in created.iter() {
+        if root.join(path).exists() {
+            return Err(format!("{} already exists", path));
+        }
+    }
+
+    let read = |path: &str| {
+        fs::read_to_string(root.join(path)).map_err(|e| format!("unable to read {}: {}", path, e))
+    };
+    let changed = [
+        ("src/lib.rs", register_module(&read("src/lib.rs")?, day)?),
+        (
+            "src/registry.rs",
+            register_entry(&read("src/registry.rs")?, day)?,
+        ),
+        (
+            "src/generate.rs",
+            register_generator(&read("src/generate.rs")?, day)?,
+        ),
+    ];
+
+    let write = |path: &str, content: &str| {
+        fs::write(root.join(path), content).map_err(|e| format!("unable to write {}: {}", path, e))
+    };
+    for (path, content) in created.iter() {
+        if let Some(dir) = root.join(path).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("unable to create {:?}: {}", dir, e))?;
+        }
+        write(path, content)?;
+    }
+    for (path, content) in changed.iter() {
+        write(path, content)?;
+    }
+    Ok((
+        created
+            .iter()
+            .map(|(path, _)| PathBuf::from(path))
+            .collect(),
+        changed
+            .iter()
+            .map(|(path, _)| PathBuf::from(path))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIB: &str = "//! docs\n\npub mod cache;\npub mod day1;\npub mod day21;\npub mod day3;\npub mod error;\n\npub use error::ParseError;\n";
+    const REGISTRY: &str = "use crate::{day1, day21};\n\npub const DAYS: [Entry; 2] = [\n    Entry {\n        name: \"day1\",\n    },\n    Entry {\n        name: \"day21\",\n    },\n];\n\npub fn find() {}\n";
+    const GENERATE: &str = "pub fn day1() {}\n\n#[cfg(test)]\nmod tests {}\n";
+
+    #[test]
+    fn test_register() {
+        assert_eq!(
+            register_module(LIB, 22).unwrap(),
+            LIB.replace("day21;\n", "day21;\npub mod day22;\n")
+        );
+        assert!(register_module(LIB, 21).is_err());
+
+        let registered = register_entry(REGISTRY, 22).unwrap();
+        assert!(registered
+            .starts_with("use crate::{day1, day21, day22};\n\npub const DAYS: [Entry; 3] = ["));
+        assert!(registered.contains("generate: generate::day22,\n    },\n];\n\npub fn find"));
+        assert!(register_entry(&registered, 22).is_err());
+        assert!(register_entry(REGISTRY, 1).is_err());
+        assert!(register_generator(GENERATE, 22)
+            .unwrap()
+            .contains("}\n\n// Placeholder lines until day22 has a real generator."));
+        assert!(register_generator(GENERATE, 1).is_err());
+
+        // the real registry is laid out by rustfmt, so is what replaces it
+        let registry = fs::read_to_string("src/registry.rs").unwrap();
+        let (range, names) = imports(&registry).unwrap();
+        assert_eq!(use_list(&names), registry[range]);
+    }
+
+    #[test]
+    fn test_new_day() {
+        let root = std::env::temp_dir().join(format!("advent-scaffold-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), LIB).unwrap();
+        fs::write(root.join("src/registry.rs"), REGISTRY).unwrap();
+        fs::write(root.join("src/generate.rs"), GENERATE).unwrap();
+
+        let (created, changed) = new_day(&root, 22).unwrap();
+        assert_eq!(created.len(), 3);
+        assert_eq!(changed.len(), 3);
+        let module = fs::read_to_string(root.join("src/day22.rs")).unwrap();
+        assert!(module.contains("impl Solution for Day22 {"));
+        assert_eq!(
+            fs::read_to_string(root.join("src/specs/day22")).unwrap(),
+            ""
+        );
+        let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+        assert!(lib.contains("pub mod day21;\npub mod day22;\npub mod day3;\n"));
+
+        // nothing is overwritten, not even by a half done run
+        fs::write(root.join("src/day22.rs"), "mine").unwrap();
+        assert_eq!(
+            new_day(&root, 22).unwrap_err(),
+            "src/day22.rs already exists"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("src/day22.rs")).unwrap(),
+            "mine"
+        );
+        fs::remove_file(root.join("src/day22.rs")).unwrap();
+        fs::remove_file(root.join("src/specs/day22")).unwrap();
+        fs::remove_file(root.join("src/inputs/day22")).unwrap();
+        assert_eq!(new_day(&root, 22).unwrap_err(), "day22 is already a module");
+        assert!(!root.join("src/day22.rs").exists());
+        assert!(new_day(&root, 26).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    fn copy_dir(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let path = entry.unwrap().path();
+            let target = to.join(path.file_name().unwrap());
+            if path.is_dir() {
+                copy_dir(&path, &target);
+            } else {
+                fs::copy(&path, &target).unwrap();
+            }
+        }
+    }
+
+    // A day added to a copy of this tree builds, and the registry and
+    // command line tests still pass with it. Built in a target directory of
+    // its own that is kept between runs, so only the first one is slow.
+    // Takes about a minute, run it with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_generated_tree() {
+        let day = (1..=25)
+            .find(|day| crate::registry::for_day(*day).count() == 0)
+            .expect("every day is taken");
+        let here = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let root = std::env::temp_dir().join(format!("advent-tree-{}", std::process::id()));
+        for file in ["Cargo.toml", "Cargo.lock", "build.rs"] {
+            if here.join(file).exists() {
+                fs::create_dir_all(&root).unwrap();
+                fs::copy(here.join(file), root.join(file)).unwrap();
+            }
+        }
+        copy_dir(&here.join("src"), &root.join("src"));
+        new_day(&root, day).unwrap();
+
+        let module = format!("day{}::", day);
+        let output = std::process::Command::new(env!("CARGO"))
+            .args(["test", "--offline", "--lib", "--bins", "--quiet", "--"])
+            .args(["registry::", "cli::", &module])
+            .current_dir(&root)
+            .env("CARGO_TARGET_DIR", here.join("target/scaffold"))
+            .output()
+            .unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert!(
+            output.status.success(),
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use crate::json::Json;
+use crate::parallel::{self, Outcome, Task};
+use crate::registry;
+use crate::solution::{Params, Part};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// A small HTTP front for the registry: `POST /days/{n}/parts/{p}` with the
+// input as the body and tunables in the query, e.g.
+// `POST /days/15/parts/1?row=10`, answers with the same JSON object as
+// `run --format json`. One connection per request, one thread per connection
+// and at most `MAX_CONNECTIONS` of them.
+
+// Inputs are a few kilobytes, anything much bigger is not a puzzle input.
+const MAX_BODY: usize = 16 << 20;
+
+// Connections handled at once, more wait to be accepted rather than each
+// getting a thread of their own.
+const MAX_CONNECTIONS: usize = 32;
+
+// How long a client may take to send its request or read the answer, so a
+// stalled one does not hold on to its thread. Solving is not included.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: Json::object([("error", Json::from(message))]),
+        }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+// `%2C` and `+` as they come from forms and most clients.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn parse_target(target: &str) -> (String, Vec<(String, String)>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+    (path.to_string(), query)
+}
+
+// Err is the response to send back when the request itself is broken.
+pub fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Ok(Err(Response::error(400, "malformed request line"))),
+    };
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = match value.trim().parse::<usize>() {
+                    Ok(length) => length,
+                    Err(_) => return Ok(Err(Response::error(400, "invalid Content-Length"))),
+                };
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Ok(Err(Response::error(413, "input too large")));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let body = match String::from_utf8(body) {
+        Ok(body) => body,
+        Err(_) => return Ok(Err(Response::error(400, "input is not UTF-8"))),
+    };
+    let (path, query) = parse_target(&target);
+    Ok(Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    }))
+}
+
+pub fn handle(request: &Request, timeout: Option<Duration>) -> Response {
+    let segments = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+    let (day, part) = match segments[..] {
+        ["days", day, "parts", part] => (day, part),
+        _ => return Response::error(404, "expected /days/{n}/parts/{p}"),
+    };
+    let entry = match registry::find(&format!("day{}", day)) {
+        Some(entry) => entry,
+        None => return Response::error(404, &format!("unknown day: {}", day)),
+    };
+    let part = match part {
+        "1" => Part::One,
+        "2" => Part::Two,
+        _ => return Response::error(404, &format!("unknown part: {}", part)),
+    };
+    if request.method != "POST" {
+        return Response::error(405, "use POST with the input as the body");
+    }
+
+    let mut params = Params::new();
+    for (key, value) in request.query.iter() {
+        params.set(key, value);
+    }
+    if let Err(e) = entry.build(&params) {
+        return Response::error(400, &e);
+    }
+
+    let task = Task {
+        entry,
+        part,
+        name: "<body>",
+        input: Ok(&request.body),
+    };
+    let report = parallel::run(&[task], &params, 1, timeout, None, true).remove(0);
+    let status = match report.outcome {
+        Outcome::Solved(_) => 200,
+        Outcome::Failed(_) => 422,
+        Outcome::Panicked(_) => 500,
+    };
+    Response {
+        status,
+        body: parallel::report_json(&report),
+    }
+}
+
+fn send(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    let body = format!("{}\n", response.body);
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn respond(stream: TcpStream, timeout: Option<Duration>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader)? {
+        Ok(request) => handle(&request, timeout),
+        Err(response) => response,
+    };
+    send(stream, &response)
+}
+
+// Connections being handled, waited on once there are as many as allowed.
+struct Slots {
+    busy: Mutex<usize>,
+    freed: Condvar,
+}
+
+// A connection being handled, given back when its thread is done however
+// it ends.
+struct Slot(Arc<Slots>);
+
+impl Slots {
+    fn take(slots: &Arc<Slots>, limit: usize) -> Slot {
+        let mut busy = slots.busy.lock().unwrap();
+        while *busy >= limit {
+            busy = slots.freed.wait(busy).unwrap();
+        }
+        *busy += 1;
+        Slot(slots.clone())
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.0.busy.lock().unwrap() -= 1;
+        self.0.freed.notify_one();
+    }
+}
+
+// Runs until the process is stopped, a failing connection only ends itself.
+pub fn serve(listener: TcpListener, timeout: Option<Duration>) {
+    serve_limited(listener, timeout, MAX_CONNECTIONS, IO_TIMEOUT)
+}
+
+fn serve_limited(
+    listener: TcpListener,
+    timeout: Option<Duration>,
+    connections: usize,
+    io_timeout: Duration,
+) {
+    let slots = Arc::new(Slots {
+        busy: Mutex::new(0),
+        freed: Condvar::new(),
+    });
+    loop {
+        // the next connections wait to be accepted until one is done
+        let slot = Slots::take(&slots, connections);
+        let stream = listener.accept().and_then(|(stream, _)| {
+            stream.set_read_timeout(Some(io_timeout))?;
+            stream.set_write_timeout(Some(io_timeout))?;
+            Ok(stream)
+        });
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || {
+                    let _slot = slot;
+                    if let Err(e) = respond(stream, timeout) {
+                        eprintln!("serve: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("serve: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::SocketAddr;
+    use std::time::Instant;
+
+    fn post(address: SocketAddr, target: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            target,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("0%2C0+x%"), "0,0 x%");
+        assert_eq!(
+            parse_target("/days/15/parts/2?min=0%2C0&max=20,20"),
+            (
+                "/days/15/parts/2".to_string(),
+                vec![
+                    ("min".to_string(), "0,0".to_string()),
+                    ("max".to_string(), "20,20".to_string())
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_serve() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, None));
+
+        let spec = std::fs::read_to_string("src/specs/day15").unwrap();
+        let (status, body) = post(address, "/days/15/parts/1?row=10", &spec);
+        assert_eq!(status, 200);
+        assert!(
+            body.starts_with(r#"{"day": "day15", "part": 1, "status": "solved", "answer": 26, "#)
+        );
+        let (status, body) = post(address, "/days/15/parts/2?min=0%2C0&max=20,20", &spec);
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""answer": 56000011"#));
+
+        let (status, body) = post(address, "/days/1/parts/1", "1\n\nx\n");
+        assert_eq!(status, 422);
+        assert!(body.contains(r#""status": "failed""#));
+        assert_eq!(post(address, "/days/1/parts/1?row=1", "1\n").0, 400);
+        assert_eq!(post(address, "/days/30/parts/1", "").0, 404);
+        assert_eq!(post(address, "/days/1/parts/3", "").0, 404);
+        assert_eq!(post(address, "/days/1", "").0, 404);
+    }
+
+    #[test]
+    fn test_hostile_input() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, None));
+
+        // both would overflow the solver's stack if they were solved
+        let cyclic =
+            "root: aaaa + humn\naaaa: bbbb * cccc\nbbbb: aaaa + dddd\ncccc: 2\ndddd: 2\nhumn: 1\n";
+        assert_eq!(post(address, "/days/21/parts/2", cyclic).0, 422);
+        let deep = format!("[]\n{}{}\n", "[".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(post(address, "/days/13/parts/1", &deep).0, 422);
+
+        assert_eq!(post(address, "/days/1/parts/1", "1\n").0, 200);
+    }
+
+    #[test]
+    fn test_limits() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let idle = Duration::from_millis(200);
+        thread::spawn(move || serve_limited(listener, None, 1, idle));
+
+        // a client that never sends its request holds the only connection
+        // until it times out, the next one waits for it
+        let stalled = TcpStream::connect(address).unwrap();
+        thread::sleep(idle / 4);
+        let start = Instant::now();
+        assert_eq!(post(address, "/days/1/parts/1", "1\n").0, 200);
+        assert!(start.elapsed() >= idle / 2);
+        drop(stalled);
+    }
+}
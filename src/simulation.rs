@@ -0,0 +1,14 @@
+use crate::solution::{Part, Solution};
+use std::fmt::Display;
+
+// A simulation that can be advanced one step at a time, for looking at the
+// state of a day while debugging. Display shows the current state.
+pub trait Simulation: Display {
+    // Advances by one step and says what happened, None once it is over.
+    fn step(&mut self) -> Option<String>;
+}
+
+// Implemented by the days whose solution is a simulation.
+pub trait Simulate: Solution {
+    fn simulate(&self, input: &Self::Input, part: Part) -> Box<dyn Simulation>;
+}
@@ -0,0 +1,276 @@
+use crate::error::{from_path, from_reader, ParseResult};
+use crate::input::normalise;
+use crate::json::Json;
+use crate::progress::{Progress, TimedOut};
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter};
+use std::io::BufRead;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: [Part; 2] = [Part::One, Part::Two];
+
+    pub fn number(&self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "part{}", self.number())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    Unsolved,
+    // The solver gave up when the runner's time limit was hit.
+    TimedOut,
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Unsolved => write!(f, "unsolved"),
+            Answer::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Number(n)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Number(n as i64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Number(n as i64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+// Unsolved and timed out parts have no value.
+impl From<&Answer> for Json {
+    fn from(answer: &Answer) -> Self {
+        match answer {
+            Answer::Number(n) => Json::Number(*n),
+            Answer::Text(s) => Json::String(s.clone()),
+            Answer::Unsolved | Answer::TimedOut => Json::Null,
+        }
+    }
+}
+
+impl<T: Into<Answer>> From<Result<T, TimedOut>> for Answer {
+    fn from(result: Result<T, TimedOut>) -> Self {
+        result.map_or(Answer::TimedOut, |answer| answer.into())
+    }
+}
+
+// What a solver found on the way to its answer, e.g. which elves carry the
+// most for day1.
+pub type Details = Vec<(&'static str, Json)>;
+
+// An answer together with how long parsing and solving took.
+#[derive(Debug, Clone)]
+pub struct Timed {
+    pub answer: Answer,
+    pub details: Details,
+    pub parse: Duration,
+    pub solve: Duration,
+}
+
+// Per-day tunables passed from the command line, e.g. `--row 10` for day15.
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    pub fn new() -> Params {
+        Params::default()
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.values.keys()
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        Ok(self.optional(key)?.unwrap_or(default))
+    }
+
+    // For tunables that turn something on rather than change a default.
+    pub fn optional<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        self.values
+            .get(key)
+            .map(|v| {
+                v.parse::<T>()
+                    .map_err(|_| format!("invalid value for --{}: {}", key, v))
+            })
+            .transpose()
+    }
+}
+
+// Implemented by days that have tunables, the registry uses it to build
+// them from the runner options.
+pub trait FromParams: Sized {
+    fn from_params(params: &Params) -> Result<Self, String>;
+}
+
+// Every day parses its input once and then answers both parts from the
+// parsed representation.
+pub trait Solution {
+    type Input;
+
+    fn parse_str(&self, input: &str) -> ParseResult<Self::Input>;
+
+    fn parse(&self, path: &str) -> ParseResult<Self::Input> {
+        from_path(path, |input| self.parse_str(input))
+    }
+
+    fn parse_reader(&self, reader: impl BufRead) -> ParseResult<Self::Input>
+    where
+        Self: Sized,
+    {
+        from_reader(reader, |input| self.parse_str(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Answer;
+
+    fn part2(&self, input: &Self::Input) -> Answer;
+
+    // Overridden by the days that can run for minutes, to report how far
+    // they got and give up once `progress` says so.
+    fn part1_with(&self, input: &Self::Input, _progress: &Progress) -> Answer {
+        self.part1(input)
+    }
+
+    fn part2_with(&self, input: &Self::Input, _progress: &Progress) -> Answer {
+        self.part2(input)
+    }
+
+    // Only asked for when the answer is written out as JSON.
+    fn details(&self, _input: &Self::Input, _part: Part) -> Details {
+        vec![]
+    }
+
+    // Overridden by the days whose details take as long as an answer.
+    fn details_with(
+        &self,
+        input: &Self::Input,
+        part: Part,
+        _progress: &Progress,
+    ) -> Result<Details, TimedOut> {
+        Ok(self.details(input, part))
+    }
+}
+
+// Object safe view of a `Solution`, used by the registry so days with
+// different input types can live in one list.
+pub trait Solver {
+    fn solve_str(&self, input: &str, part: Part) -> ParseResult<Answer> {
+        self.solve_with(input, part, &Progress::none())
+    }
+
+    fn solve_with(&self, input: &str, part: Part, progress: &Progress) -> ParseResult<Answer>;
+
+    fn solve_all_str(&self, input: &str) -> ParseResult<[Answer; 2]>;
+
+    fn solve_timed(&self, input: &str, part: Part) -> ParseResult<Timed> {
+        self.solve_timed_with(input, part, &Progress::none(), false)
+    }
+
+    // Details are only worked out when `details` is set, after the timing
+    // stops. They share `progress` with the answer, a part whose details run
+    // out of time times out as a whole.
+    fn solve_timed_with(
+        &self,
+        input: &str,
+        part: Part,
+        progress: &Progress,
+        details: bool,
+    ) -> ParseResult<Timed>;
+
+    fn solve(&self, path: &str, part: Part) -> ParseResult<Answer> {
+        from_path(path, |input| self.solve_str(input, part))
+    }
+
+    fn solve_all(&self, path: &str) -> ParseResult<[Answer; 2]> {
+        from_path(path, |input| self.solve_all_str(input))
+    }
+}
+
+impl<S: Solution> Solver for S {
+    fn solve_with(&self, input: &str, part: Part, progress: &Progress) -> ParseResult<Answer> {
+        let input = self.parse_str(&normalise(input))?;
+        Ok(match part {
+            Part::One => self.part1_with(&input, progress),
+            Part::Two => self.part2_with(&input, progress),
+        })
+    }
+
+    fn solve_all_str(&self, input: &str) -> ParseResult<[Answer; 2]> {
+        let input = self.parse_str(&normalise(input))?;
+        Ok([self.part1(&input), self.part2(&input)])
+    }
+
+    fn solve_timed_with(
+        &self,
+        input: &str,
+        part: Part,
+        progress: &Progress,
+        details: bool,
+    ) -> ParseResult<Timed> {
+        let start = Instant::now();
+        let input = self.parse_str(&normalise(input))?;
+        let parsed = Instant::now();
+        let answer = match part {
+            Part::One => self.part1_with(&input, progress),
+            Part::Two => self.part2_with(&input, progress),
+        };
+        let solve = parsed.elapsed();
+        let (answer, details) = match answer {
+            Answer::TimedOut => (answer, vec![]),
+            _ if !details => (answer, vec![]),
+            _ => match self.details_with(&input, part, progress) {
+                Ok(details) => (answer, details),
+                Err(TimedOut) => (Answer::TimedOut, vec![]),
+            },
+        };
+        Ok(Timed {
+            answer,
+            details,
+            parse: parsed - start,
+            solve,
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+
+// Debug output of the solvers, silent unless the runner is started with
+// `--trace`. Events are written to stderr so answers on stdout stay clean.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info = 1,
+    Debug = 2,
+    Trace = 3,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let name = match self {
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("unknown trace level: {}", s)),
+        }
+    }
+}
+
+// `target[=level]`, e.g. `day16` or `day20=trace`. The level defaults to
+// debug and `all` matches every day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub target: String,
+    pub level: Level,
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, level) = match s.split_once('=') {
+            Some((target, level)) => (target, level.parse()?),
+            None => (s, Level::Debug),
+        };
+        if target.is_empty() {
+            return Err(format!("missing trace target: {}", s));
+        }
+        Ok(Filter {
+            target: target.to_string(),
+            level,
+        })
+    }
+}
+
+impl Filter {
+    fn matches(&self, target: &str) -> bool {
+        self.target == "all" || self.target == target
+    }
+}
+
+// Comma separated filters, as given to `--trace`.
+pub fn parse_filters(s: &str) -> Result<Vec<Filter>, String> {
+    s.split(',').map(|filter| filter.trim().parse()).collect()
+}
+
+// Most verbose level enabled for `target`, if any.
+pub fn level_for(filters: &[Filter], target: &str) -> Option<Level> {
+    filters
+        .iter()
+        .filter(|filter| filter.matches(target))
+        .map(|filter| filter.level)
+        .max()
+}
+
+// 0 while tracing is off, checked first so disabled events cost one load.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+static FILTERS: RwLock<Vec<Filter>> = RwLock::new(Vec::new());
+
+pub fn init(filters: Vec<Filter>) {
+    let max = filters.iter().map(|filter| filter.level as u8).max();
+    *FILTERS.write().unwrap() = filters;
+    MAX_LEVEL.store(max.unwrap_or(0), Ordering::Relaxed);
+}
+
+// `advent_2022::day16` -> `day16`
+pub fn target(module_path: &str) -> &str {
+    module_path.rsplit("::").next().unwrap_or(module_path)
+}
+
+#[inline]
+pub fn enabled(level: Level, module_path: &str) -> bool {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    level_for(&FILTERS.read().unwrap(), target(module_path)).is_some_and(|max| level <= max)
+}
+
+pub struct Event<'a> {
+    pub level: Level,
+    pub target: &'a str,
+    pub message: &'a str,
+    pub fields: &'a [(&'a str, &'a dyn Debug)],
+}
+
+// `day16 debug: picking best="DD" from="AA"`
+impl Display for Event<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{} {}: {}", self.target, self.level, self.message)?;
+        for (key, value) in self.fields {
+            write!(f, " {}={:?}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn emit(event: &Event) {
+    eprintln!("{}", event);
+}
+
+// trace!(Debug, "picking", best = best_name, from = name);
+// Fields are only evaluated when the event is enabled for the calling day.
+#[macro_export]
+macro_rules! trace {
+    ($level:ident, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::enabled($crate::trace::Level::$level, module_path!()) {
+            $crate::trace::emit(&$crate::trace::Event {
+                level: $crate::trace::Level::$level,
+                target: $crate::trace::target(module_path!()),
+                message: $message,
+                fields: &[$((stringify!($key), &$value as &dyn ::std::fmt::Debug)),*],
+            });
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters() {
+        let filters = parse_filters("day16, day20=trace").unwrap();
+        assert_eq!(
+            filters[0],
+            Filter {
+                target: "day16".to_string(),
+                level: Level::Debug
+            }
+        );
+        assert_eq!(level_for(&filters, "day20"), Some(Level::Trace));
+        assert_eq!(level_for(&filters, "day9"), None);
+
+        let all = parse_filters("all=info,day9").unwrap();
+        assert_eq!(level_for(&all, "day12"), Some(Level::Info));
+        assert_eq!(level_for(&all, "day9"), Some(Level::Debug));
+
+        assert!(parse_filters("day16=loud").is_err());
+        assert!(parse_filters("=debug").is_err());
+    }
+
+    #[test]
+    fn test_event() {
+        let name = "AA".to_string();
+        let event = Event {
+            level: Level::Debug,
+            target: target("advent_2022::day16"),
+            message: "picking",
+            fields: &[("from", &name), ("score", &20)],
+        };
+        assert_eq!(
+            event.to_string(),
+            "day16 debug: picking from=\"AA\" score=20"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Polls files for changes, a file counts as changed when its modification
+// time or size is different or it appeared or went away since the last look.
+pub struct Watcher {
+    files: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+}
+
+fn stamp(path: &PathBuf) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl Watcher {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Watcher {
+        Watcher {
+            files: paths
+                .into_iter()
+                .map(|path| {
+                    let stamp = stamp(&path);
+                    (path, stamp)
+                })
+                .collect(),
+        }
+    }
+
+    // Files changed since the last call.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+        for (path, last) in self.files.iter_mut() {
+            let now = stamp(path);
+            if now != *last {
+                *last = now;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+
+    // Blocks until something changes, looking every `every`.
+    pub fn wait(&mut self, every: Duration) -> Vec<PathBuf> {
+        loop {
+            let changed = self.changed();
+            if !changed.is_empty() {
+                return changed;
+            }
+            thread::sleep(every);
+        }
+    }
+}
+
+// What changed between two runs, both lists of `(what, answer)`. Multi line
+// answers are shown whole under their label, old one first.
+pub fn diff(previous: &[(String, String)], current: &[(String, String)]) -> Vec<String> {
+    let mut out = vec![];
+    for (label, answer) in current {
+        let before = previous.iter().find(|(l, _)| l == label).map(|(_, a)| a);
+        match before {
+            Some(before) if before == answer => {}
+            Some(before) if before.contains('\n') || answer.contains('\n') => {
+                out.push(format!("{}:\n{}\n->\n{}", label, before, answer))
+            }
+            Some(before) => out.push(format!("{}: {} -> {}", label, before, answer)),
+            None => out.push(format!("{}: {}", label, answer)),
+        }
+    }
+    for (label, _) in previous {
+        if !current.iter().any(|(l, _)| l == label) {
+            out.push(format!("{}: gone", label));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::slice;
+
+    fn answers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(l, a)| (l.to_string(), a.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = answers(&[
+            ("input part1", "24"),
+            ("spec part1", "1"),
+            ("spec part2", "#."),
+        ]);
+        let after = answers(&[
+            ("input part1", "25"),
+            ("spec part1", "1"),
+            ("spec part2", "##"),
+            ("x", "2"),
+        ]);
+        assert_eq!(
+            diff(&before, &after),
+            ["input part1: 24 -> 25", "spec part2: #. -> ##", "x: 2"]
+        );
+        assert_eq!(diff(&after, &after), Vec::<String>::new());
+        assert_eq!(
+            diff(&answers(&[("a", "#\n.")]), &answers(&[("b", "1")])),
+            ["b: 1", "a: gone"]
+        );
+        assert_eq!(
+            diff(&answers(&[("a", "#\n.")]), &answers(&[("a", "#\n#")])),
+            ["a:\n#\n.\n->\n#\n#"]
+        );
+    }
+
+    #[test]
+    fn test_watcher() {
+        let path = std::env::temp_dir().join(format!("advent-watch-{}", std::process::id()));
+        let mut watcher = Watcher::new([path.clone()]);
+        assert!(watcher.changed().is_empty());
+        fs::write(&path, "1\n").unwrap();
+        assert_eq!(watcher.changed(), slice::from_ref(&path));
+        assert!(watcher.changed().is_empty());
+        fs::write(&path, "12\n").unwrap();
+        assert_eq!(
+            watcher.wait(Duration::from_millis(1)),
+            slice::from_ref(&path)
+        );
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.changed(), [path]);
+    }
+}
@@ -0,0 +1,11 @@
+// Example tests generated from the `.expected` sidecars in `src/specs`, adding
+// a line there adds a test here.
+use advent_2022::answers::check_example;
+
+fn check(spec: &str, index: usize) {
+    if let Err(e) = check_example(spec, index) {
+        panic!("{}", e);
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/spec_tests.rs"));